@@ -1,18 +1,12 @@
 use crate::state::AppState;
 use crate::storage::file_manager::FileManager;
+use crate::storage::{image_codec, paths};
 use arboard::Clipboard;
-use image::ImageFormat;
 use tauri::State;
 
 /// Convert Windows path to WSL path
-fn to_wsl_path(win_path: &str) -> String {
-    if win_path.len() >= 3 && win_path.as_bytes()[1] == b':' {
-        let drive = (win_path.as_bytes()[0] as char).to_ascii_lowercase();
-        let rest = &win_path[2..].replace('\\', "/");
-        format!("/mnt/{}{}", drive, rest)
-    } else {
-        win_path.replace('\\', "/")
-    }
+pub fn to_wsl_path(win_path: &str) -> String {
+    paths::windows_to_wsl(win_path).unwrap_or_else(|| win_path.replace('\\', "/"))
 }
 
 fn format_path(path: &str, path_format: &str) -> String {
@@ -38,11 +32,8 @@ pub fn copy_image(filename: String, state: State<'_, AppState>) -> Result<(), St
     let settings = state.settings.lock().map_err(|e| e.to_string())?;
     let file_manager = FileManager::new(&settings);
 
-    let png_data = file_manager.get_image_data(&filename)?;
-
-    let img =
-        image::load_from_memory_with_format(&png_data, ImageFormat::Png)
-            .map_err(|e| format!("Failed to load image: {}", e))?;
+    let data = file_manager.get_image_data(&filename)?;
+    let img = image_codec::decode(&data)?;
 
     let rgba = img.to_rgba8();
     let (width, height) = rgba.dimensions();
@@ -60,3 +51,65 @@ pub fn copy_image(filename: String, state: State<'_, AppState>) -> Result<(), St
 
     Ok(())
 }
+
+/// Put an image and its accompanying text (and HTML, if any) on the clipboard
+/// as one set of formats, so each target picks the one it understands.
+/// `data` may be in any stored screenshot format.
+pub fn set_image_with_text(
+    clipboard: &mut Clipboard,
+    data: &[u8],
+    text: &str,
+    html: Option<&str>,
+) -> Result<(), String> {
+    let img = image_codec::decode(data)?;
+
+    #[cfg(windows)]
+    {
+        use clipboard_win::{options::NoClear, raw, register_format};
+        let _ = clipboard;
+
+        let mut bmp = Vec::new();
+        img.to_rgb8()
+            .write_to(&mut std::io::Cursor::new(&mut bmp), image::ImageFormat::Bmp)
+            .map_err(|e| format!("Failed to encode bitmap: {}", e))?;
+        // Stored PNGs go on as they are; other formats are converted
+        let png_data = if image::guess_format(data).ok() == Some(image::ImageFormat::Png) {
+            data.to_vec()
+        } else {
+            image_codec::encode_png(&img, false)?
+        };
+
+        // Held open until all formats are written; setting each one through
+        // arboard would clear the ones before it
+        let _open = clipboard_win::Clipboard::new_attempts(10)
+            .map_err(|e| format!("Clipboard error: {}", e))?;
+        raw::empty().map_err(|e| format!("Failed to clear clipboard: {}", e))?;
+        if let Some(format) = register_format("PNG") {
+            raw::set_without_clear(format.get(), &png_data)
+                .map_err(|e| format!("Failed to set image: {}", e))?;
+        }
+        raw::set_bitmap_with(&bmp, NoClear).map_err(|e| format!("Failed to set image: {}", e))?;
+        raw::set_string_with(text, NoClear).map_err(|e| format!("Failed to set text: {}", e))?;
+        if let (Some(html), Some(format)) = (html, register_format("HTML Format")) {
+            raw::set_html_with(format.get(), html, NoClear)
+                .map_err(|e| format!("Failed to set HTML: {}", e))?;
+        }
+        Ok(())
+    }
+
+    // arboard replaces the clipboard on every set, so elsewhere only the image
+    // is restored
+    #[cfg(not(windows))]
+    {
+        let _ = (text, html);
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        clipboard
+            .set_image(arboard::ImageData {
+                width: width as usize,
+                height: height as usize,
+                bytes: rgba.into_raw().into(),
+            })
+            .map_err(|e| format!("Failed to set image: {}", e))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::state::AppState;
+use crate::storage::crypto;
+use crate::storage::file_manager::FileManager;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Serialize, Clone)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub configured: bool,
+    pub unlocked: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct EncryptionReport {
+    pub text_entries: u32,
+    pub screenshots: u32,
+}
+
+#[tauri::command]
+pub fn get_encryption_status(state: State<'_, AppState>) -> Result<EncryptionStatus, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(EncryptionStatus {
+        enabled: settings.encrypt_at_rest,
+        configured: crypto::is_configured(),
+        unlocked: crypto::is_unlocked(),
+    })
+}
+
+/// Create the passphrase vault and turn on encryption for new captures.
+/// Existing data stays plaintext until `encrypt_existing_data` is run.
+#[tauri::command]
+pub fn setup_encryption(passphrase: String, state: State<'_, AppState>) -> Result<(), String> {
+    crypto::setup(&passphrase)?;
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.encrypt_at_rest = true;
+    settings.save()
+}
+
+#[tauri::command]
+pub fn unlock_encryption(passphrase: String) -> Result<(), String> {
+    crypto::unlock(&passphrase)
+}
+
+#[tauri::command]
+pub fn lock_encryption() -> Result<(), String> {
+    crypto::lock();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn encrypt_existing_data(state: State<'_, AppState>) -> Result<EncryptionReport, String> {
+    if !crypto::is_unlocked() {
+        return Err("Unlock encryption before migrating existing data".to_string());
+    }
+
+    let file_manager = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if !settings.encrypt_at_rest {
+            return Err("Encryption is not enabled".to_string());
+        }
+        FileManager::new(&settings)
+    };
+
+    let text_entries = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .encrypt_existing()?;
+    let screenshots = file_manager.encrypt_existing()?;
+
+    // The pre-database history file is plaintext too
+    let legacy = dirs::config_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("cli-buddy")
+        .join("clipboard_history.json.imported");
+    let _ = std::fs::remove_file(legacy);
+
+    Ok(EncryptionReport {
+        text_entries,
+        screenshots,
+    })
+}
@@ -1,102 +1,563 @@
+use crate::commands::clipboard::set_image_with_text;
+use crate::commands::snippets;
 use crate::state::AppState;
-use crate::storage::file_manager::FileManager;
-use crate::storage::text_history::TextEntry;
+use crate::storage::archive::{self, ArchiveSummary};
+use crate::storage::classifier;
+use crate::storage::file_manager::{FileManager, ScreenshotInfo};
+use crate::storage::paths;
+use crate::storage::screenshot_index::{Page, DEFAULT_PAGE_SIZE};
+use crate::storage::sync;
+use crate::storage::text_history::{SearchFilters, SearchHit, TextEntry};
+use chrono::{DateTime, Local};
 use serde::Serialize;
+use std::collections::HashMap;
 use tauri::State;
 
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type", content = "data")]
 pub enum ClipboardEntry {
     #[serde(rename = "image")]
-    Image(crate::storage::file_manager::ScreenshotInfo),
+    Image(ScreenshotInfo),
     #[serde(rename = "text")]
     Text(TextEntry),
+    #[serde(rename = "html")]
+    Html(TextEntry),
+    #[serde(rename = "files")]
+    Files(FileList),
+    #[serde(rename = "multi")]
+    Multi(MultiEntry),
 }
 
+/// Copied files: the entry plus its paths as given and in WSL form.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileList {
+    #[serde(flatten)]
+    pub entry: TextEntry,
+    pub paths: Vec<String>,
+    pub wsl_paths: Vec<String>,
+}
+
+/// Text or HTML copied together with an image; pasted as all of them at once.
+#[derive(Debug, Clone, Serialize)]
+pub struct MultiEntry {
+    #[serde(flatten)]
+    pub entry: TextEntry,
+    pub image: ScreenshotInfo,
+}
+
+fn check_kind(kind: Option<&str>) -> Result<(), String> {
+    match kind {
+        Some(k) if !classifier::KINDS.contains(&k) => Err(format!("Unknown kind: {}", k)),
+        _ => Ok(()),
+    }
+}
+
+impl ClipboardEntry {
+    /// Wrap a history entry in the variant matching its clipboard format.
+    /// `image` is the screenshot linked to it, when that still exists.
+    fn from_text(entry: TextEntry, image: Option<ScreenshotInfo>) -> Self {
+        if let Some(image) = image {
+            return ClipboardEntry::Multi(MultiEntry { entry, image });
+        }
+        match entry.format.as_str() {
+            "html" => ClipboardEntry::Html(entry),
+            "files" => {
+                let paths: Vec<String> = entry.content.lines().map(str::to_string).collect();
+                let wsl_paths = paths
+                    .iter()
+                    .map(|p| paths::windows_to_wsl(p).unwrap_or_else(|| p.clone()))
+                    .collect();
+                ClipboardEntry::Files(FileList {
+                    entry,
+                    paths,
+                    wsl_paths,
+                })
+            }
+            _ => ClipboardEntry::Text(entry),
+        }
+    }
+
+    fn text_entry(&self) -> Option<&TextEntry> {
+        match self {
+            ClipboardEntry::Image(_) => None,
+            ClipboardEntry::Text(t) | ClipboardEntry::Html(t) => Some(t),
+            ClipboardEntry::Files(f) => Some(&f.entry),
+            ClipboardEntry::Multi(m) => Some(&m.entry),
+        }
+    }
+
+    /// When the entry was last copied or pasted, falling back to its capture time.
+    fn last_touched(&self) -> &str {
+        match (self, self.text_entry()) {
+            (ClipboardEntry::Image(s), _) => s.last_used_at.as_deref().unwrap_or(&s.created_at),
+            (_, Some(t)) => t.last_used_at.as_deref().unwrap_or(&t.created_at),
+            (_, None) => "",
+        }
+    }
+
+    fn use_count(&self) -> u32 {
+        match (self, self.text_entry()) {
+            (ClipboardEntry::Image(s), _) => s.use_count,
+            (_, Some(t)) => t.use_count,
+            (_, None) => 0,
+        }
+    }
+
+    fn key(&self) -> String {
+        match (self, self.text_entry()) {
+            (ClipboardEntry::Image(s), _) => format!("image:{}", s.filename),
+            (_, Some(t)) => format!("text:{}", t.id),
+            (_, None) => String::new(),
+        }
+    }
+
+    /// Sort key for `order`; larger ranks are listed first. Also used as the
+    /// page cursor, so it ends with a key unique to the entry.
+    fn rank(&self, order: &str) -> String {
+        match order {
+            "frecency" => format!(
+                "{:020.4}\u{1f}{}\u{1f}{}",
+                self.frecency(),
+                self.last_touched(),
+                self.key()
+            ),
+            _ => format!("{}\u{1f}{}", self.last_touched(), self.key()),
+        }
+    }
+
+    /// Uses weighted by how recently the entry was touched, so a clip pasted
+    /// often last week can outrank one copied once today.
+    fn frecency(&self) -> f64 {
+        let age_days = DateTime::parse_from_str(self.last_touched(), "%Y-%m-%dT%H:%M:%S%z")
+            .map(|t| (Local::now().fixed_offset() - t).num_hours() as f64 / 24.0)
+            .unwrap_or(f64::MAX);
+        let weight = match age_days {
+            d if d < 1.0 => 100.0,
+            d if d < 4.0 => 70.0,
+            d if d < 14.0 => 50.0,
+            d if d < 31.0 => 30.0,
+            d if d < 90.0 => 10.0,
+            _ => 1.0,
+        };
+        (self.use_count() + 1) as f64 * weight
+    }
+}
+
+/// Text entries and standalone screenshots are listed separately and merged,
+/// so a page cursor holds the position reached in each: the text entry's
+/// `score.seq` and the screenshot's rank, either empty when none was listed yet.
+fn parse_cursor(cursor: Option<&str>) -> (Option<(i64, i64)>, Option<&str>) {
+    let Some((text, image)) = cursor.and_then(|c| c.split_once('\u{1e}')) else {
+        return (None, None);
+    };
+    let text = text
+        .split_once('.')
+        .and_then(|(score, seq)| Some((score.parse().ok()?, seq.parse().ok()?)));
+    (text, Some(image).filter(|i| !i.is_empty()))
+}
+
+fn format_cursor(text: Option<(i64, i64)>, image: Option<&str>) -> String {
+    let text = text.map_or(String::new(), |(score, seq)| format!("{}.{}", score, seq));
+    format!("{}\u{1e}{}", text, image.unwrap_or(""))
+}
+
+/// With `kind` set only text entries of that kind are returned. `order` is
+/// "recent" (default, most recently copied or pasted first) or "frecency".
+/// Text entries only carry their preview; paste them by id.
 #[tauri::command]
-pub fn get_clipboard_history(state: State<AppState>) -> Result<Vec<ClipboardEntry>, String> {
+pub fn get_clipboard_history(
+    kind: Option<String>,
+    order: Option<String>,
+    cursor: Option<String>,
+    limit: Option<u32>,
+    state: State<AppState>,
+) -> Result<Page<ClipboardEntry>, String> {
+    check_kind(kind.as_deref())?;
+    let order = order.as_deref().unwrap_or("recent");
+    if order != "recent" && order != "frecency" {
+        return Err(format!("Unknown order: {}", order));
+    }
+    let limit = limit.map_or(DEFAULT_PAGE_SIZE, |l| l as usize);
+    let (text_after, image_after) = parse_cursor(cursor.as_deref());
     let settings = state
         .settings
         .lock()
         .map_err(|e| format!("Lock error: {}", e))?;
 
-    let file_manager = FileManager::new(&settings);
-    let screenshots = file_manager.get_screenshot_list()?;
+    let screenshots: HashMap<String, ScreenshotInfo> = {
+        let mut index = state
+            .screenshot_index
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        index.refresh(&FileManager::new(&settings))?;
+        index
+            .all()
+            .iter()
+            .map(|s| (s.filename.clone(), s.clone()))
+            .collect()
+    };
     let text_entries = state
         .text_history
         .lock()
         .map_err(|e| format!("Lock error: {}", e))?;
 
-    let mut entries: Vec<ClipboardEntry> = Vec::new();
+    // One more than fits, to tell whether another page follows
+    let texts = text_entries.list_page(kind.as_deref(), order == "frecency", text_after, limit + 1);
+    let mut total = text_entries.count(kind.as_deref());
 
-    for s in screenshots {
-        entries.push(ClipboardEntry::Image(s));
-    }
-    for t in text_entries.get_entries() {
-        entries.push(ClipboardEntry::Text(t.clone()));
+    // Screenshots linked to an entry are shown as part of it, not on their own
+    let mut images: Vec<(String, ClipboardEntry)> = Vec::new();
+    if kind.is_none() {
+        let linked = text_entries.linked_screenshots();
+        let usage = text_entries.screenshot_usage();
+        for s in screenshots
+            .values()
+            .filter(|s| !linked.contains(&s.filename))
+        {
+            total += 1;
+            let mut s = s.clone();
+            if let Some((count, last_used_at)) = usage.get(&s.filename) {
+                s.use_count = *count;
+                s.last_used_at = last_used_at.clone();
+            }
+            let entry = ClipboardEntry::Image(s);
+            let rank = entry.rank(order);
+            if image_after.is_none_or(|after| rank.as_str() < after) {
+                images.push((rank, entry));
+            }
+        }
+        images.sort_by(|a, b| b.0.cmp(&a.0));
     }
+    drop(text_entries);
 
-    // Sort by created_at descending
-    entries.sort_by(|a, b| {
-        let a_time = match a {
-            ClipboardEntry::Image(s) => &s.created_at,
-            ClipboardEntry::Text(t) => &t.created_at,
+    let mut texts = texts
+        .into_iter()
+        .map(|(position, t)| {
+            let image = t
+                .screenshot
+                .as_ref()
+                .and_then(|f| screenshots.get(f).cloned());
+            let entry = ClipboardEntry::from_text(t, image);
+            (position, entry.rank(order), entry)
+        })
+        .peekable();
+    let mut images = images.into_iter().peekable();
+    let mut text_position = text_after;
+    let mut image_position = image_after.map(str::to_string);
+    let mut items = Vec::new();
+    while items.len() < limit {
+        let text_first = match (texts.peek(), images.peek()) {
+            (Some((_, text_rank, _)), Some((image_rank, _))) => text_rank >= image_rank,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
         };
-        let b_time = match b {
-            ClipboardEntry::Image(s) => &s.created_at,
-            ClipboardEntry::Text(t) => &t.created_at,
+        let entry = if text_first {
+            texts.next().map(|(position, _, entry)| {
+                text_position = Some(position);
+                entry
+            })
+        } else {
+            images.next().map(|(rank, entry)| {
+                image_position = Some(rank);
+                entry
+            })
         };
-        b_time.cmp(a_time)
-    });
+        items.extend(entry);
+    }
+    let next_cursor = (texts.peek().is_some() || images.peek().is_some())
+        .then(|| format_cursor(text_position, image_position.as_deref()));
+
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
+}
+
+#[tauri::command]
+pub fn get_text_history(
+    kind: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<TextEntry>, String> {
+    check_kind(kind.as_deref())?;
+    let th = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    Ok(th.get_entries(kind.as_deref()))
+}
+
+#[tauri::command]
+pub fn search_history(
+    query: String,
+    filters: Option<SearchFilters>,
+    state: State<AppState>,
+) -> Result<Vec<ClipboardEntry>, String> {
+    let filters = filters.unwrap_or_default();
+    check_kind(filters.kind.as_deref())?;
+    let hits = {
+        let th = state
+            .text_history
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        th.search(&query, &filters)?
+    };
+
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let file_manager = FileManager::new(&settings);
+
+    // Results keep the ranking order from the index
+    let mut entries = Vec::with_capacity(hits.len());
+    for hit in hits {
+        match hit {
+            SearchHit::Text(t) => {
+                let image = t
+                    .screenshot
+                    .as_ref()
+                    .and_then(|f| file_manager.get_screenshot_info(f).ok());
+                entries.push(ClipboardEntry::from_text(*t, image));
+            }
+            SearchHit::Screenshot(filename) => {
+                if let Ok(info) = file_manager.get_screenshot_info(&filename) {
+                    entries.push(ClipboardEntry::Image(info));
+                }
+            }
+        }
+    }
 
     Ok(entries)
 }
 
+/// Write all history and screenshots to a zip archive at `path`.
 #[tauri::command]
-pub fn get_text_history(state: State<AppState>) -> Result<Vec<TextEntry>, String> {
+pub fn export_history(path: String, state: State<AppState>) -> Result<ArchiveSummary, String> {
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
     let th = state
         .text_history
         .lock()
         .map_err(|e| format!("Lock error: {}", e))?;
-    Ok(th.get_entries().to_vec())
+    archive::export(
+        std::path::Path::new(&path),
+        &FileManager::new(&settings),
+        &th,
+    )
+}
+
+/// Merge an archive made by `export_history`, skipping content already present.
+#[tauri::command]
+pub fn import_history(path: String, state: State<AppState>) -> Result<ArchiveSummary, String> {
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let mut th = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    th.set_max_entries(settings.max_text_entries as usize);
+    th.set_encrypt(settings.encrypt_at_rest);
+    th.set_size_limits(settings.max_inline_text_kb, settings.max_text_size_mb);
+    archive::import(
+        std::path::Path::new(&path),
+        &FileManager::new(&settings),
+        &mut th,
+    )
 }
 
 #[tauri::command]
 pub fn delete_text_entry(id: String, state: State<AppState>) -> Result<bool, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
     let mut th = state
         .text_history
         .lock()
         .map_err(|e| format!("Lock error: {}", e))?;
-    Ok(th.delete_entry(&id))
+    let hash = th.hash_of(&id);
+    let deleted = th.delete_entry(&id);
+    // Sync takes its state lock before the history, so release the history first
+    drop(th);
+    if deleted {
+        sync::note_text_deleted(&settings, hash.into_iter().collect());
+    }
+    Ok(deleted)
 }
 
 #[tauri::command]
 pub fn clear_text_history(state: State<AppState>) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
     let mut th = state
         .text_history
         .lock()
         .map_err(|e| format!("Lock error: {}", e))?;
+    let hashes = th.unpinned_hashes();
     th.clear();
+    drop(th);
+    sync::note_text_deleted(&settings, hashes);
     Ok(())
 }
 
+fn set_entry_pinned(
+    id: &str,
+    entry_type: &str,
+    pinned: bool,
+    state: &State<AppState>,
+) -> Result<bool, String> {
+    match entry_type {
+        "text" => {
+            let mut th = state
+                .text_history
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?;
+            Ok(th.set_pinned(id, pinned))
+        }
+        // Pinning the image too keeps screenshot cleanup from dropping it
+        "multi" => {
+            // Settings before the history, as everywhere else
+            let settings = state
+                .settings
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?
+                .clone();
+            let mut th = state
+                .text_history
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?;
+            if let Some(filename) = th.screenshot_of(id) {
+                FileManager::new(&settings).set_pinned(&filename, pinned)?;
+            }
+            Ok(th.set_pinned(id, pinned))
+        }
+        "image" => {
+            // For images, id is the filename
+            let settings = state
+                .settings
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?;
+            FileManager::new(&settings).set_pinned(id, pinned)
+        }
+        _ => Err("Unknown entry type".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn pin_entry(id: String, entry_type: String, state: State<AppState>) -> Result<bool, String> {
+    set_entry_pinned(&id, &entry_type, true, &state)
+}
+
+#[tauri::command]
+pub fn unpin_entry(id: String, entry_type: String, state: State<AppState>) -> Result<bool, String> {
+    set_entry_pinned(&id, &entry_type, false, &state)
+}
+
 #[tauri::command]
-pub fn paste_from_history(content: String, entry_type: String) -> Result<(), String> {
+pub fn paste_from_history(
+    content: String,
+    entry_type: String,
+    id: Option<String>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    paste_entry(&state, content, entry_type, id)
+}
+
+/// Put a history entry, snippet or image back on the clipboard and count the use.
+pub fn paste_entry(
+    state: &AppState,
+    content: String,
+    entry_type: String,
+    id: Option<String>,
+) -> Result<(), String> {
     let mut clipboard =
         arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?;
 
+    let mut html = None;
+    let mut screenshot = None;
+    let content = match (entry_type.as_str(), &id) {
+        // Large text entries only carry their start; load the full clip by id
+        ("text" | "html" | "files" | "multi", Some(id)) => {
+            let th = state
+                .text_history
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?;
+            if entry_type == "html" || entry_type == "multi" {
+                html = th.get_html(id)?;
+            }
+            if entry_type == "multi" {
+                screenshot = th.screenshot_of(id);
+            }
+            th.get_full_content(id)?
+        }
+        ("snippet", Some(id)) => {
+            let template = state
+                .snippets
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?
+                .get(id)
+                .map(|s| s.content.clone())
+                .ok_or_else(|| format!("Snippet not found: {}", id))?;
+            let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+            snippets::render(&template, &settings, &mut clipboard)
+        }
+        ("snippet", None) => return Err("Snippet id is required".to_string()),
+        _ => content,
+    };
+
     match entry_type.as_str() {
-        "text" => {
+        "text" | "snippet" => {
             clipboard
                 .set_text(&content)
                 .map_err(|e| format!("Failed to set text: {}", e))?;
         }
+        // Markup for rich targets, with the text as fallback for plain ones
+        "html" => match html {
+            Some(html) => clipboard
+                .set_html(html, Some(content.clone()))
+                .map_err(|e| format!("Failed to set HTML: {}", e))?,
+            None => clipboard
+                .set_text(&content)
+                .map_err(|e| format!("Failed to set text: {}", e))?,
+        },
+        "files" => {
+            let files: Vec<std::path::PathBuf> = content
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(std::path::PathBuf::from)
+                .collect();
+            clipboard
+                .set()
+                .file_list(&files)
+                .map_err(|e| format!("Failed to set file list: {}", e))?;
+        }
+        "multi" => {
+            let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+            let image =
+                screenshot.and_then(|f| FileManager::new(&settings).get_image_data(&f).ok());
+            match image {
+                Some(data) => {
+                    set_image_with_text(&mut clipboard, &data, &content, html.as_deref())?
+                }
+                // The image was deleted since; the text is still worth pasting
+                None => match html {
+                    Some(html) => clipboard
+                        .set_html(html, Some(content.clone()))
+                        .map_err(|e| format!("Failed to set HTML: {}", e))?,
+                    None => clipboard
+                        .set_text(&content)
+                        .map_err(|e| format!("Failed to set text: {}", e))?,
+                },
+            }
+        }
         "image" => {
             // For images, content is the file path
             let data =
                 std::fs::read(&content).map_err(|e| format!("Failed to read image: {}", e))?;
-            let img = image::load_from_memory(&data)
-                .map_err(|e| format!("Failed to decode image: {}", e))?;
+            let data = crate::storage::crypto::decrypt_if_needed(data)?;
+            let img = crate::storage::image_codec::decode(&data)?;
             let rgba = img.to_rgba8();
             let (w, h) = rgba.dimensions();
             let img_data = arboard::ImageData {
@@ -111,5 +572,31 @@ pub fn paste_from_history(content: String, entry_type: String) -> Result<(), Str
         _ => return Err("Unknown entry type".to_string()),
     }
 
+    if entry_type == "snippet" {
+        if let (Some(id), Ok(mut store)) = (&id, state.snippets.lock()) {
+            store.record_use(id);
+        }
+        return Ok(());
+    }
+
+    // Screenshots in subfolders are known by their path under the save directory
+    let screenshot_name = if entry_type == "image" {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+        FileManager::new(&settings).filename_of(std::path::Path::new(&content))
+    } else {
+        None
+    };
+    let mut th = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    if entry_type == "image" {
+        if let Some(filename) = screenshot_name {
+            th.record_screenshot_use(&filename);
+        }
+    } else if let Some(id) = &id {
+        th.record_use(id);
+    }
+
     Ok(())
 }
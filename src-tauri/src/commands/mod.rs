@@ -1,6 +1,12 @@
 pub mod clipboard;
+pub mod encryption;
 pub mod history;
 pub mod monitor;
+pub mod queue;
 pub mod screenshot;
 pub mod settings;
+pub mod snippets;
+pub mod sync;
 pub mod system;
+pub mod transforms;
+pub mod trash;
@@ -0,0 +1,189 @@
+use crate::commands::history::paste_entry;
+use crate::monitor::paste_queue::PasteQueueState;
+use crate::state::AppState;
+use log::error;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Time the target app gets to read the clipboard before the next item replaces it
+const PASTE_SETTLE: Duration = Duration::from_millis(250);
+
+#[tauri::command]
+pub fn get_paste_queue(state: State<'_, AppState>) -> Result<PasteQueueState, String> {
+    let queue = state
+        .paste_queue
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    Ok(queue.state())
+}
+
+#[tauri::command]
+pub fn toggle_paste_queue(app: AppHandle) -> Result<PasteQueueState, String> {
+    toggle(&app)
+}
+
+#[tauri::command]
+pub fn clear_paste_queue(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let mut queue = state
+        .paste_queue
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    queue.clear();
+    let _ = app.emit("paste-queue-changed", queue.state());
+    Ok(())
+}
+
+/// Switch queue mode on or off, as the shortcut and tray menu do.
+pub fn toggle(app: &AppHandle) -> Result<PasteQueueState, String> {
+    let state = app.state::<AppState>();
+    let mut queue = state
+        .paste_queue
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let active = !queue.is_active();
+    queue.set_active(active);
+    log::info!("Paste queue {}", if active { "on" } else { "off" });
+    let queue_state = queue.state();
+    let _ = app.emit("paste-queue-changed", &queue_state);
+    Ok(queue_state)
+}
+
+/// Handler for the queue paste shortcut. Runs off the shortcut thread since it
+/// waits for the target app to take the paste.
+pub fn paste_next(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = paste_next_blocking(&app) {
+            error!("Queue paste failed: {}", e);
+        }
+    });
+}
+
+fn paste_next_blocking(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let item = {
+        let mut queue = state
+            .paste_queue
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        let Some(item) = queue.front().filter(|_| queue.is_active()).cloned() else {
+            return Ok(());
+        };
+        queue.mark_loaded(&item);
+        item
+    };
+    paste_entry(
+        &state,
+        item.content.clone(),
+        item.entry_type.clone(),
+        item.id.clone(),
+    )?;
+
+    let pasted = send_paste_keys();
+    if pasted {
+        std::thread::sleep(PASTE_SETTLE);
+    }
+
+    let next = {
+        let mut queue = state
+            .paste_queue
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        if queue.front() == Some(&item) {
+            queue.pop();
+        }
+        // Without a simulated paste the item stays on the clipboard for the user
+        let next = queue.front().cloned().filter(|_| pasted);
+        if let Some(next) = &next {
+            queue.mark_loaded(next);
+        }
+        let _ = app.emit("paste-queue-changed", queue.state());
+        next
+    };
+    if let Some(next) = next {
+        paste_entry(&state, next.content, next.entry_type, next.id)?;
+    }
+    Ok(())
+}
+
+/// Send Ctrl+V to the focused window, first releasing modifiers still held
+/// from the shortcut. Returns false where this is not supported.
+#[cfg(target_os = "windows")]
+fn send_paste_keys() -> bool {
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct KEYBDINPUT {
+        w_vk: u16,
+        w_scan: u16,
+        dw_flags: u32,
+        time: u32,
+        dw_extra_info: usize,
+    }
+    // Only here to give the union the size Windows expects
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct MOUSEINPUT {
+        dx: i32,
+        dy: i32,
+        mouse_data: u32,
+        dw_flags: u32,
+        time: u32,
+        dw_extra_info: usize,
+    }
+    #[repr(C)]
+    union INPUT_0 {
+        ki: KEYBDINPUT,
+        _mi: MOUSEINPUT,
+    }
+    #[repr(C)]
+    struct INPUT {
+        r#type: u32,
+        u: INPUT_0,
+    }
+    extern "system" {
+        fn SendInput(c_inputs: u32, p_inputs: *const INPUT, cb_size: i32) -> u32;
+    }
+
+    const INPUT_KEYBOARD: u32 = 1;
+    const KEYEVENTF_KEYUP: u32 = 0x0002;
+    const VK_SHIFT: u16 = 0x10;
+    const VK_CONTROL: u16 = 0x11;
+    const VK_MENU: u16 = 0x12;
+    const VK_LWIN: u16 = 0x5B;
+    const VK_V: u16 = 0x56;
+
+    let key = |vk: u16, up: bool| INPUT {
+        r#type: INPUT_KEYBOARD,
+        u: INPUT_0 {
+            ki: KEYBDINPUT {
+                w_vk: vk,
+                w_scan: 0,
+                dw_flags: if up { KEYEVENTF_KEYUP } else { 0 },
+                time: 0,
+                dw_extra_info: 0,
+            },
+        },
+    };
+    let inputs = [
+        key(VK_SHIFT, true),
+        key(VK_MENU, true),
+        key(VK_LWIN, true),
+        key(VK_CONTROL, false),
+        key(VK_V, false),
+        key(VK_V, true),
+        key(VK_CONTROL, true),
+    ];
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    sent == inputs.len() as u32
+}
+
+#[cfg(not(target_os = "windows"))]
+fn send_paste_keys() -> bool {
+    false
+}
@@ -1,43 +1,126 @@
+use crate::config::AppSettings;
 use crate::state::AppState;
+use crate::storage::duplicates;
 use crate::storage::file_manager::{FileManager, ScreenshotInfo};
-use base64::Engine;
-use tauri::State;
+use crate::storage::hash::sha256_hash;
+use crate::storage::screenshot_index::{Page, DEFAULT_PAGE_SIZE};
+use crate::storage::sync;
+use crate::storage::trash::{self, REASON_DELETED};
+use log::warn;
+use tauri::{AppHandle, State};
 
+/// Newest screenshots first. Images and thumbnails are loaded from the
+/// `buddy://` protocol.
 #[tauri::command]
-pub fn get_screenshots(state: State<'_, AppState>) -> Result<Vec<ScreenshotInfo>, String> {
+pub fn get_screenshots(
+    cursor: Option<String>,
+    limit: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Page<ScreenshotInfo>, String> {
     let settings = state.settings.lock().map_err(|e| e.to_string())?;
     let file_manager = FileManager::new(&settings);
-    file_manager.get_screenshot_list()
+    let mut index = state
+        .screenshot_index
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    index.refresh(&file_manager)?;
+    let limit = limit.map_or(DEFAULT_PAGE_SIZE, |l| l as usize);
+    Ok(index.page(cursor.as_deref(), limit))
 }
 
 #[tauri::command]
 pub fn delete_screenshot(filename: String, state: State<'_, AppState>) -> Result<(), String> {
     let settings = state.settings.lock().map_err(|e| e.to_string())?;
-    let file_manager = FileManager::new(&settings);
-    file_manager.delete_screenshot(&filename)
+    remove_screenshot(&state, &settings, &filename, trash::new_batch())
 }
 
+/// Delete several screenshots at once, as when cleaning up duplicates. They
+/// go to the trash together. Returns how many were deleted.
 #[tauri::command]
-pub fn get_save_directory(state: State<'_, AppState>) -> Result<String, String> {
+pub fn delete_screenshots(
+    filenames: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<u32, String> {
     let settings = state.settings.lock().map_err(|e| e.to_string())?;
-    Ok(settings.save_directory.clone())
+    let batch = trash::new_batch();
+    let mut deleted = 0;
+    for filename in &filenames {
+        match remove_screenshot(&state, &settings, filename, batch) {
+            Ok(()) => deleted += 1,
+            Err(e) => warn!("Failed to delete {}: {}", filename, e),
+        }
+    }
+    Ok(deleted)
+}
+
+fn remove_screenshot(
+    state: &AppState,
+    settings: &AppSettings,
+    filename: &str,
+    batch: i64,
+) -> Result<(), String> {
+    let file_manager = FileManager::new(settings);
+    let hash = file_manager
+        .get_image_data(filename)
+        .ok()
+        .map(|data| sha256_hash(&data));
+    file_manager.delete_screenshot(filename, batch, REASON_DELETED)?;
+    if let Ok(mut index) = state.screenshot_index.lock() {
+        index.invalidate();
+    }
+    if let Ok(mut th) = state.text_history.lock() {
+        th.forget_screenshot(filename);
+    }
+    if let Some(hash) = hash {
+        sync::note_screenshot_deleted(settings, hash);
+    }
+    Ok(())
+}
+
+/// Groups of near-identical screenshots, newest first within each group.
+/// `threshold` overrides the configured number of differing hash bits.
+#[tauri::command]
+pub fn find_duplicate_screenshots(
+    threshold: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Vec<ScreenshotInfo>>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let file_manager = FileManager::new(&settings);
+    let mut index = state
+        .screenshot_index
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    index.refresh(&file_manager)?;
+    let mut th = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let hashes = duplicates::fill_missing_hashes(&mut th, index.all());
+    let threshold = threshold.unwrap_or(settings.duplicate_threshold);
+    Ok(duplicates::group(index.all(), &hashes, threshold))
 }
 
+/// Re-encode the whole library with the current format settings in the
+/// background. Progress arrives as `reencode-progress` events.
 #[tauri::command]
-pub fn get_image_base64(path: String) -> Result<String, String> {
-    let data = std::fs::read(&path).map_err(|e| format!("Failed to read image: {}", e))?;
-    let ext = std::path::Path::new(&path)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("png")
-        .to_lowercase();
-    let mime = match ext.as_str() {
-        "jpg" | "jpeg" => "image/jpeg",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        "bmp" => "image/bmp",
-        _ => "image/png",
-    };
-    let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
-    Ok(format!("data:{};base64,{}", mime, b64))
+pub fn reencode_screenshots(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    state.reencoder.start(
+        app,
+        settings,
+        state.text_history.clone(),
+        state.screenshot_index.clone(),
+        state.hash_index.clone(),
+    )
+}
+
+#[tauri::command]
+pub fn is_reencoding(state: State<'_, AppState>) -> bool {
+    state.reencoder.is_running()
+}
+
+#[tauri::command]
+pub fn get_save_directory(state: State<'_, AppState>) -> Result<String, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.save_directory.clone())
 }
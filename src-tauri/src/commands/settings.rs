@@ -1,7 +1,8 @@
 use crate::config::AppSettings;
 use crate::state::AppState;
+use crate::storage::{image_codec, naming};
+use std::path::Path;
 use tauri::{AppHandle, State};
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
 
 #[tauri::command]
 pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
@@ -12,36 +13,65 @@ pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
 #[tauri::command]
 pub fn update_settings(
     app: AppHandle,
-    new_settings: AppSettings,
+    mut new_settings: AppSettings,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    let old_shortcut_str = {
-        let settings = state.settings.lock().map_err(|e| e.to_string())?;
-        settings.global_shortcut.clone()
-    };
+    let old_settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+
+    if new_settings.encrypt_at_rest && !crate::storage::crypto::is_configured() {
+        return Err("Set up an encryption passphrase before enabling encryption".to_string());
+    }
+
+    image_codec::OutputFormat::parse(&new_settings.image_format)?;
+    naming::validate(&new_settings.filename_template)?;
+    if new_settings.trash_retention_days == 0 {
+        return Err("Trash retention must be at least one day".to_string());
+    }
+    new_settings
+        .import_folders
+        .retain(|folder| !folder.path.trim().is_empty());
+    for folder in &new_settings.import_folders {
+        if folder.mode != "copy" && folder.mode != "move" {
+            return Err(format!("Unknown import mode: {}", folder.mode));
+        }
+        // Imports are saved in the save directory, so they would be imported again
+        if Path::new(folder.path.trim()).starts_with(&new_settings.save_directory) {
+            return Err(format!(
+                "Import folder {} is inside the save directory",
+                folder.path
+            ));
+        }
+    }
 
     new_settings.save()?;
 
-    let new_shortcut_str = new_settings.global_shortcut.clone();
+    let shortcuts: [(String, String, fn(&AppHandle)); 3] = [
+        (
+            old_settings.global_shortcut,
+            new_settings.global_shortcut.clone(),
+            crate::open_popup,
+        ),
+        (
+            old_settings.queue_shortcut,
+            new_settings.queue_shortcut.clone(),
+            crate::toggle_paste_queue,
+        ),
+        (
+            old_settings.queue_paste_shortcut,
+            new_settings.queue_paste_shortcut.clone(),
+            crate::commands::queue::paste_next,
+        ),
+    ];
 
     let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
     *settings = new_settings;
     drop(settings);
 
-    // Re-register global shortcut if changed
-    if old_shortcut_str != new_shortcut_str {
-        // Unregister old
-        if let Ok(old_shortcut) = old_shortcut_str.parse::<tauri_plugin_global_shortcut::Shortcut>() {
-            let _ = app.global_shortcut().unregister(old_shortcut);
-        }
-        // Register new
-        if let Ok(new_shortcut) = new_shortcut_str.parse::<tauri_plugin_global_shortcut::Shortcut>() {
-            let app_handle = app.clone();
-            let _ = app.global_shortcut().on_shortcut(new_shortcut, move |_app, _shortcut, event| {
-                if event.state == ShortcutState::Pressed {
-                    let _ = crate::create_popup_window(&app_handle);
-                }
-            });
+    // Re-register global shortcuts that changed
+    for (old_shortcut, new_shortcut, action) in shortcuts {
+        if old_shortcut != new_shortcut {
+            crate::unbind_shortcut(&app, &old_shortcut);
+            crate::bind_shortcut(&app, &new_shortcut, action);
         }
     }
 
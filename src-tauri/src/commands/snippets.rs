@@ -0,0 +1,78 @@
+use crate::commands::clipboard::to_wsl_path;
+use crate::config::AppSettings;
+use crate::state::AppState;
+use crate::storage::file_manager::FileManager;
+use crate::storage::snippets::{self, Snippet};
+use arboard::Clipboard;
+use chrono::Local;
+use tauri::State;
+
+/// Fill in a snippet's placeholders. Values are looked up only when the
+/// template uses them, so `{clipboard}` reads the clipboard just before it is
+/// replaced by the paste.
+pub fn render(template: &str, settings: &AppSettings, clipboard: &mut Clipboard) -> String {
+    snippets::expand(template, |name| match name {
+        "date" => Some(Local::now().format("%Y-%m-%d").to_string()),
+        "time" => Some(Local::now().format("%H:%M:%S").to_string()),
+        "clipboard" => Some(clipboard.get_text().unwrap_or_default()),
+        "latest_screenshot" | "latest_screenshot_wsl" => {
+            let latest = FileManager::new(settings)
+                .scan_screenshots()
+                .ok()?
+                .into_iter()
+                .next()?;
+            Some(if name == "latest_screenshot_wsl" {
+                to_wsl_path(&latest.path)
+            } else {
+                latest.path
+            })
+        }
+        "uuid" => Some(snippets::new_uuid()),
+        _ => None,
+    })
+}
+
+#[tauri::command]
+pub fn get_snippets(state: State<'_, AppState>) -> Result<Vec<Snippet>, String> {
+    let store = state
+        .snippets
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    Ok(store.list())
+}
+
+#[tauri::command]
+pub fn create_snippet(
+    name: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> Result<Snippet, String> {
+    let mut store = state
+        .snippets
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    store.create(&name, &content)
+}
+
+#[tauri::command]
+pub fn update_snippet(
+    id: String,
+    name: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> Result<Snippet, String> {
+    let mut store = state
+        .snippets
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    store.update(&id, &name, &content)
+}
+
+#[tauri::command]
+pub fn delete_snippet(id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let mut store = state
+        .snippets
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    store.delete(&id)
+}
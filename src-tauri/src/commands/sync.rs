@@ -0,0 +1,15 @@
+use crate::state::AppState;
+use crate::storage::sync::{self, SyncReport, SyncStatus};
+use tauri::State;
+
+#[tauri::command]
+pub fn get_sync_status(state: State<'_, AppState>) -> Result<SyncStatus, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    sync::status(&settings, &state.text_history)
+}
+
+#[tauri::command]
+pub fn sync_now(state: State<'_, AppState>) -> Result<SyncReport, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    sync::run(&settings, &state.text_history)
+}
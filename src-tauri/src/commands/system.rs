@@ -97,11 +97,8 @@ fn get_wsl_memory_limit() -> Option<u64> {
     None
 }
 
-#[tauri::command]
-pub fn get_claude_sessions() -> Result<Vec<ClaudeSession>, String> {
-    let mut sessions = Vec::new();
-
-    // Try multiple paths to find Claude sessions
+/// Folders holding Claude project session logs, native Windows first, then WSL.
+fn claude_project_dirs() -> Vec<std::path::PathBuf> {
     let mut search_paths: Vec<std::path::PathBuf> = Vec::new();
 
     // 1. Windows native: %USERPROFILE%\.claude\projects
@@ -122,6 +119,53 @@ pub fn get_claude_sessions() -> Result<Vec<ClaudeSession>, String> {
         }
     }
 
+    search_paths
+}
+
+/// Short project name from a session folder name, which is the project path
+/// with separators replaced by `-`.
+fn project_name(project_dir_name: &str) -> String {
+    project_dir_name
+        .rsplit('-')
+        .next()
+        .unwrap_or(project_dir_name)
+        .to_string()
+}
+
+/// Project of the most recently written Claude session, for the `{project}`
+/// screenshot filename token.
+pub fn active_project() -> Option<String> {
+    let mut newest: Option<(std::time::SystemTime, String)> = None;
+    for projects_dir in claude_project_dirs() {
+        let Ok(entries) = std::fs::read_dir(&projects_dir) else {
+            continue;
+        };
+        for project_entry in entries.flatten() {
+            let Ok(files) = std::fs::read_dir(project_entry.path()) else {
+                continue;
+            };
+            for file_entry in files.flatten() {
+                if !file_entry.file_name().to_string_lossy().ends_with(".jsonl") {
+                    continue;
+                }
+                let Ok(modified) = file_entry.metadata().and_then(|m| m.modified()) else {
+                    continue;
+                };
+                if newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+                    let dir_name = project_entry.file_name().to_string_lossy().to_string();
+                    newest = Some((modified, project_name(&dir_name)));
+                }
+            }
+        }
+    }
+    newest.map(|(_, name)| name)
+}
+
+#[tauri::command]
+pub fn get_claude_sessions() -> Result<Vec<ClaudeSession>, String> {
+    let mut sessions = Vec::new();
+    let search_paths = claude_project_dirs();
+
     for projects_dir in &search_paths {
         if let Ok(entries) = std::fs::read_dir(projects_dir) {
             for project_entry in entries.flatten() {
@@ -131,11 +175,7 @@ pub fn get_claude_sessions() -> Result<Vec<ClaudeSession>, String> {
 
                 let project_dir_name = project_entry.file_name().to_string_lossy().to_string();
                 let project_path = project_dir_name.replace('-', "/");
-                let project_name = project_dir_name
-                    .rsplit('-')
-                    .next()
-                    .unwrap_or(&project_dir_name)
-                    .to_string();
+                let project_name = project_name(&project_dir_name);
 
                 let project_path_full = project_entry.path();
                 let file_entries = match std::fs::read_dir(&project_path_full) {
@@ -250,6 +290,12 @@ fn extract_session_info(path: &std::path::Path) -> (u32, String) {
 
 /// Use wslpath to get the Windows-accessible path to Claude projects in WSL
 fn get_wsl_claude_path() -> Result<String, String> {
+    // Starting wsl.exe is slow, and the path does not change once found
+    static CACHED: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+    if let Some(path) = CACHED.lock().ok().and_then(|c| c.clone()) {
+        return Ok(path);
+    }
+
     // wslpath auto-resolves the correct distro name (Ubuntu, Ubuntu-24.04, Debian, etc.)
     let output = new_hidden_command("wsl.exe")
         .args(["-e", "sh", "-c", "wslpath -w \"$HOME/.claude/projects\""])
@@ -261,6 +307,9 @@ fn get_wsl_claude_path() -> Result<String, String> {
         return Err("Could not get WSL Claude path".to_string());
     }
 
+    if let Ok(mut cached) = CACHED.lock() {
+        *cached = Some(path.clone());
+    }
     Ok(path)
 }
 
@@ -0,0 +1,124 @@
+use crate::config::{AppSettings, TransformPreset};
+use crate::state::AppState;
+use crate::storage::transforms;
+use tauri::State;
+
+/// Expand preset names into their transforms and check every step exists.
+fn resolve_chain(names: &[String], settings: &AppSettings) -> Result<Vec<String>, String> {
+    let mut chain = Vec::new();
+    for name in names {
+        if transforms::is_builtin(name) {
+            chain.push(name.clone());
+        } else if let Some(preset) = settings.transform_presets.iter().find(|p| &p.name == name) {
+            chain.extend(preset.transforms.iter().cloned());
+        } else {
+            return Err(format!("Unknown transform or preset: {}", name));
+        }
+    }
+    Ok(chain)
+}
+
+fn transform_entry(
+    entry_id: &str,
+    names: &[String],
+    state: &State<'_, AppState>,
+) -> Result<String, String> {
+    let chain = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        resolve_chain(names, &settings)?
+    };
+    let content = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .get_full_content(entry_id)?;
+    transforms::apply_chain(&content, &chain)
+}
+
+#[tauri::command]
+pub fn get_transforms() -> Vec<String> {
+    transforms::TRANSFORMS
+        .iter()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Result of a chain without touching the clipboard, for previews.
+#[tauri::command]
+pub fn preview_transformed(
+    entry_id: String,
+    transforms: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    transform_entry(&entry_id, &transforms, &state)
+}
+
+/// Apply `transforms` (built-in names or preset names) to a text entry and put
+/// the result on the clipboard.
+#[tauri::command]
+pub fn paste_transformed(
+    entry_id: String,
+    transforms: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let text = transform_entry(&entry_id, &transforms, &state)?;
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?;
+    clipboard
+        .set_text(&text)
+        .map_err(|e| format!("Failed to set text: {}", e))?;
+
+    if let Ok(mut th) = state.text_history.lock() {
+        th.record_use(&entry_id);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn save_transform_preset(
+    name: String,
+    transforms: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TransformPreset>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name must not be empty".to_string());
+    }
+    if transforms::is_builtin(&name) {
+        return Err(format!("\"{}\" is the name of a built-in transform", name));
+    }
+    if transforms.is_empty() {
+        return Err("A preset needs at least one transform".to_string());
+    }
+    if let Some(unknown) = transforms.iter().find(|t| !transforms::is_builtin(t)) {
+        return Err(format!("Unknown transform: {}", unknown));
+    }
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut updated = settings.clone();
+    match updated
+        .transform_presets
+        .iter_mut()
+        .find(|p| p.name == name)
+    {
+        Some(preset) => preset.transforms = transforms,
+        None => updated
+            .transform_presets
+            .push(TransformPreset { name, transforms }),
+    }
+    updated.save()?;
+    *settings = updated;
+    Ok(settings.transform_presets.clone())
+}
+
+#[tauri::command]
+pub fn delete_transform_preset(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TransformPreset>, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut updated = settings.clone();
+    updated.transform_presets.retain(|p| p.name != name);
+    updated.save()?;
+    *settings = updated;
+    Ok(settings.transform_presets.clone())
+}
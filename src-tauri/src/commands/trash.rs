@@ -0,0 +1,120 @@
+use crate::state::AppState;
+use crate::storage::file_manager::FileManager;
+use crate::storage::trash::{TrashItem, TrashRef, REASON_DELETED};
+use log::warn;
+use tauri::State;
+
+/// Trashed text entries and screenshots, most recently deleted first.
+#[tauri::command]
+pub fn get_trash(state: State<'_, AppState>) -> Result<Vec<TrashItem>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut items = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .trashed_entries();
+    items.extend(
+        FileManager::new(&settings)
+            .trashed_screenshots()
+            .iter()
+            .map(|s| s.to_item()),
+    );
+    items.sort_by_key(|item| std::cmp::Reverse(item.batch));
+    Ok(items)
+}
+
+/// Put the given items back. Returns how many were restored, or an error
+/// listing the ones that could not be.
+#[tauri::command]
+pub fn restore_from_trash(items: Vec<TrashRef>, state: State<'_, AppState>) -> Result<u32, String> {
+    let mut restored = 0;
+    let mut failed = Vec::new();
+    for item in &items {
+        match restore(&state, item.batch, &item.kind, Some(&item.id)) {
+            Ok(n) => restored += n,
+            Err(e) => {
+                warn!("Failed to restore {}: {}", item.id, e);
+                failed.push(e);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!(
+            "Restored {} of {} items: {}",
+            restored,
+            items.len(),
+            failed.join("; ")
+        ));
+    }
+    Ok(restored)
+}
+
+/// Restore everything removed by the last deletion made from the app, as
+/// opposed to retention limits or sync. Returns how many items came back.
+#[tauri::command]
+pub fn undo_delete(state: State<'_, AppState>) -> Result<u32, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let text_batch = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .last_trash_batch(REASON_DELETED);
+    let screenshot_batch = FileManager::new(&settings)
+        .trashed_screenshots()
+        .iter()
+        .filter(|s| s.reason == REASON_DELETED)
+        .map(|s| s.batch)
+        .max();
+    let Some(batch) = text_batch.max(screenshot_batch) else {
+        return Err("Nothing to undo".to_string());
+    };
+    Ok(restore(&state, batch, "text", None)? + restore(&state, batch, "image", None)?)
+}
+
+/// Permanently delete everything in the trash. Returns how many items were deleted.
+#[tauri::command]
+pub fn empty_trash(state: State<'_, AppState>) -> Result<u32, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let text = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .purge_trash(None);
+    let screenshots = FileManager::new(&settings).purge_trash(None)?;
+    Ok(text as u32 + screenshots)
+}
+
+/// Restore one item of `batch`, or the whole batch of that kind when `id` is unset.
+fn restore(state: &AppState, batch: i64, kind: &str, id: Option<&str>) -> Result<u32, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?.clone();
+    let mut th = state
+        .text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    match kind {
+        "text" => return Ok(th.restore_trashed(batch, id)? as u32),
+        "image" => {}
+        _ => return Err(format!("Unknown trash item kind: {}", kind)),
+    }
+
+    let file_manager = FileManager::new(&settings);
+    let filenames: Vec<String> = match id {
+        Some(id) => vec![id.to_string()],
+        None => file_manager
+            .trashed_screenshots()
+            .into_iter()
+            .filter(|s| s.batch == batch)
+            .map(|s| s.filename)
+            .collect(),
+    };
+    let mut restored = 0;
+    for filename in &filenames {
+        let info = file_manager.restore_screenshot(batch, filename)?;
+        th.record_screenshot(&info);
+        restored += 1;
+    }
+    if let Ok(mut index) = state.screenshot_index.lock() {
+        index.invalidate();
+    }
+    Ok(restored)
+}
@@ -1,7 +1,13 @@
+use crate::storage::safe_file;
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
+/// Version of the settings.json layout, stored as `schema_version`.
+const SETTINGS_VERSION: u64 = 1;
+
 fn default_capture_text() -> bool {
     true
 }
@@ -14,6 +20,84 @@ fn default_global_shortcut() -> String {
     "Alt+Shift+V".to_string()
 }
 
+fn default_queue_shortcut() -> String {
+    "Alt+Shift+Q".to_string()
+}
+
+fn default_queue_paste_shortcut() -> String {
+    "Alt+Shift+N".to_string()
+}
+
+fn default_skip_near_duplicates() -> bool {
+    true
+}
+
+// Low enough that terminal captures showing different output stay apart
+fn default_duplicate_threshold() -> u32 {
+    3
+}
+
+/// "png", "webp_lossless", "webp" (lossy) or "jpeg"
+fn default_image_format() -> String {
+    "png".to_string()
+}
+
+fn default_image_quality() -> u32 {
+    85
+}
+
+fn default_filename_template() -> String {
+    crate::storage::naming::DEFAULT_TEMPLATE.to_string()
+}
+
+/// "none", "day" or "month"
+fn default_screenshot_subfolders() -> String {
+    "none".to_string()
+}
+
+/// What to do with copied text that contains secrets:
+/// "off", "store" (flag only), "skip", "redact" or "expire"
+fn default_secret_action() -> String {
+    "redact".to_string()
+}
+
+fn default_secret_expiry_minutes() -> u32 {
+    60
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_max_inline_text_kb() -> u32 {
+    10
+}
+
+fn default_max_text_size_mb() -> u32 {
+    32
+}
+
+/// A named chain of built-in transforms for `paste_transformed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformPreset {
+    pub name: String,
+    pub transforms: Vec<String>,
+}
+
+/// "copy" leaves the original in place, "move" deletes it once imported
+fn default_import_mode() -> String {
+    "copy".to_string()
+}
+
+/// A folder another screenshot tool saves into, whose new images are
+/// imported into the library.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportFolder {
+    pub path: String,
+    #[serde(default = "default_import_mode")]
+    pub mode: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub save_directory: String,
@@ -30,6 +114,68 @@ pub struct AppSettings {
     pub max_text_entries: u32,
     #[serde(default = "default_global_shortcut")]
     pub global_shortcut: String,
+    #[serde(default)]
+    pub encrypt_at_rest: bool,
+    #[serde(default = "default_secret_action")]
+    pub secret_action: String,
+    #[serde(default = "default_secret_expiry_minutes")]
+    pub secret_expiry_minutes: u32,
+    /// Age and size limits enforced by the janitor; 0 disables a limit
+    #[serde(default)]
+    pub text_max_age_days: u32,
+    #[serde(default)]
+    pub screenshot_max_age_days: u32,
+    #[serde(default)]
+    pub screenshot_max_total_mb: u64,
+    /// Days deleted and evicted items stay in the trash; at least 1
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Text above this size is kept in a blob file with only its start in the index
+    #[serde(default = "default_max_inline_text_kb")]
+    pub max_inline_text_kb: u32,
+    /// Larger clips are cut at this size; 0 keeps everything
+    #[serde(default = "default_max_text_size_mb")]
+    pub max_text_size_mb: u32,
+    /// Shared folder used to merge history with other machines; empty disables sync
+    #[serde(default)]
+    pub sync_folder: String,
+    #[serde(default)]
+    pub transform_presets: Vec<TransformPreset>,
+    /// Turns paste queue mode on and off
+    #[serde(default = "default_queue_shortcut")]
+    pub queue_shortcut: String,
+    /// Pastes the oldest queued clip and loads the next one
+    #[serde(default = "default_queue_paste_shortcut")]
+    pub queue_paste_shortcut: String,
+    /// Drop a capture that looks the same as a stored screenshot
+    #[serde(default = "default_skip_near_duplicates")]
+    pub skip_near_duplicates: bool,
+    /// Most perceptual hash bits (of 64) two screenshots may differ in and
+    /// still count as near-duplicates
+    #[serde(default = "default_duplicate_threshold")]
+    pub duplicate_threshold: u32,
+    /// File format new screenshots are saved in
+    #[serde(default = "default_image_format")]
+    pub image_format: String,
+    /// 1-100, for lossy WebP and JPEG
+    #[serde(default = "default_image_quality")]
+    pub image_quality: u32,
+    /// Losslessly recompress PNG screenshots; slower to save
+    #[serde(default)]
+    pub optimize_png: bool,
+    /// Longest side a screenshot is stored at; 0 keeps the original size
+    #[serde(default)]
+    pub max_image_dimension: u32,
+    /// Name of new screenshots, without extension. Tokens: `{date}`, `{time}`,
+    /// `{seq}`, `{hash8}` and `{project}`
+    #[serde(default = "default_filename_template")]
+    pub filename_template: String,
+    /// Group new screenshots into per-day or per-month folders
+    #[serde(default = "default_screenshot_subfolders")]
+    pub screenshot_subfolders: String,
+    /// Folders watched for images saved by tools like Snipping Tool or ShareX
+    #[serde(default)]
+    pub import_folders: Vec<ImportFolder>,
 }
 
 impl Default for AppSettings {
@@ -50,6 +196,28 @@ impl Default for AppSettings {
             capture_text: default_capture_text(),
             max_text_entries: default_max_text_entries(),
             global_shortcut: default_global_shortcut(),
+            encrypt_at_rest: false,
+            secret_action: default_secret_action(),
+            secret_expiry_minutes: default_secret_expiry_minutes(),
+            text_max_age_days: 0,
+            screenshot_max_age_days: 0,
+            screenshot_max_total_mb: 0,
+            trash_retention_days: default_trash_retention_days(),
+            max_inline_text_kb: default_max_inline_text_kb(),
+            max_text_size_mb: default_max_text_size_mb(),
+            sync_folder: String::new(),
+            transform_presets: Vec::new(),
+            queue_shortcut: default_queue_shortcut(),
+            queue_paste_shortcut: default_queue_paste_shortcut(),
+            skip_near_duplicates: default_skip_near_duplicates(),
+            duplicate_threshold: default_duplicate_threshold(),
+            image_format: default_image_format(),
+            image_quality: default_image_quality(),
+            optimize_png: false,
+            max_image_dimension: 0,
+            filename_template: default_filename_template(),
+            screenshot_subfolders: default_screenshot_subfolders(),
+            import_folders: Vec::new(),
         }
     }
 }
@@ -64,25 +232,83 @@ impl AppSettings {
 
     pub fn load() -> Self {
         let path = Self::config_path();
-        if path.exists() {
-            match fs::read_to_string(&path) {
-                Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-                Err(_) => Self::default(),
-            }
-        } else {
+        if !path.exists() {
             let settings = Self::default();
             let _ = settings.save();
-            settings
+            return settings;
+        }
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read settings: {}", e);
+                return Self::default();
+            }
+        };
+
+        match Self::parse(&content) {
+            Ok((settings, migrated)) => {
+                if migrated {
+                    let _ = settings.save();
+                }
+                settings
+            }
+            Err(e) => {
+                // Keep the broken file for inspection rather than overwriting it
+                match safe_file::backup_corrupt(&path) {
+                    Ok(backup) => warn!(
+                        "Settings file is corrupt ({}); moved to {}",
+                        e,
+                        backup.display()
+                    ),
+                    Err(backup_err) => {
+                        error!("Settings file is corrupt ({}): {}", e, backup_err);
+                        return Self::default();
+                    }
+                }
+                let settings = Self::default();
+                let _ = settings.save();
+                settings
+            }
         }
     }
 
-    pub fn save(&self) -> Result<(), String> {
-        let path = Self::config_path();
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    /// Parse settings, migrating older layouts. Returns whether a migration ran.
+    fn parse(content: &str) -> Result<(Self, bool), String> {
+        let mut value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        if !value.is_object() {
+            return Err("expected a JSON object".to_string());
         }
-        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, content).map_err(|e| e.to_string())?;
-        Ok(())
+        let version = value
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+
+        if version > SETTINGS_VERSION {
+            warn!(
+                "Settings were written by a newer version (schema {}); unknown fields are ignored",
+                version
+            );
+        }
+        let migrated = Self::migrate(&mut value, version);
+
+        let settings = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok((settings, migrated))
+    }
+
+    fn migrate(value: &mut Value, from: u64) -> bool {
+        if from >= SETTINGS_VERSION {
+            return false;
+        }
+        // v0 -> v1: `schema_version` introduced; new fields are filled by serde defaults
+        value["schema_version"] = SETTINGS_VERSION.into();
+        true
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        value["schema_version"] = SETTINGS_VERSION.into();
+        let content = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        safe_file::write_atomic(&Self::config_path(), content.as_bytes())
     }
 }
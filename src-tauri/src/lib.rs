@@ -1,11 +1,15 @@
 mod commands;
 mod config;
 mod monitor;
+mod protocol;
 mod state;
 mod storage;
 mod tray;
 
-use commands::{clipboard, history, monitor as monitor_cmd, screenshot, settings, system};
+use commands::{
+    clipboard, encryption, history, monitor as monitor_cmd, queue, screenshot, settings, snippets,
+    sync, system, transforms, trash,
+};
 use state::AppState;
 use tauri::Manager;
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
@@ -87,6 +91,41 @@ pub fn create_popup_window(app: &tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Run `action` whenever `shortcut` is pressed. An empty shortcut is left unbound.
+pub fn bind_shortcut(app: &tauri::AppHandle, shortcut: &str, action: fn(&tauri::AppHandle)) {
+    if shortcut.trim().is_empty() {
+        return;
+    }
+    let Ok(parsed) = shortcut.parse::<tauri_plugin_global_shortcut::Shortcut>() else {
+        log::warn!("Failed to parse global shortcut: {}", shortcut);
+        return;
+    };
+    let app_handle = app.clone();
+    let _ = app
+        .global_shortcut()
+        .on_shortcut(parsed, move |_app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                action(&app_handle);
+            }
+        });
+}
+
+pub fn unbind_shortcut(app: &tauri::AppHandle, shortcut: &str) {
+    if let Ok(parsed) = shortcut.parse::<tauri_plugin_global_shortcut::Shortcut>() {
+        let _ = app.global_shortcut().unregister(parsed);
+    }
+}
+
+pub fn open_popup(app: &tauri::AppHandle) {
+    let _ = create_popup_window(app);
+}
+
+pub fn toggle_paste_queue(app: &tauri::AppHandle) {
+    if let Err(e) = queue::toggle(app) {
+        log::error!("Failed to toggle paste queue: {}", e);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
@@ -104,15 +143,26 @@ pub fn run() {
             tauri_plugin_global_shortcut::Builder::new().build(),
         )
         .manage(AppState::new())
+        .register_asynchronous_uri_scheme_protocol(protocol::SCHEME, |ctx, request, responder| {
+            // Reading and decrypting images stays off the webview's thread
+            let app = ctx.app_handle().clone();
+            std::thread::spawn(move || responder.respond(protocol::handle(&app, &request)));
+        })
         .invoke_handler(tauri::generate_handler![
             clipboard::copy_path,
             clipboard::copy_image,
             screenshot::get_screenshots,
             screenshot::delete_screenshot,
+            screenshot::delete_screenshots,
+            screenshot::find_duplicate_screenshots,
+            screenshot::reencode_screenshots,
+            screenshot::is_reencoding,
             screenshot::get_save_directory,
-            screenshot::get_image_base64,
             monitor_cmd::toggle_monitor,
             monitor_cmd::get_monitor_status,
+            queue::get_paste_queue,
+            queue::toggle_paste_queue,
+            queue::clear_paste_queue,
             settings::get_settings,
             settings::update_settings,
             history::get_clipboard_history,
@@ -120,9 +170,34 @@ pub fn run() {
             history::delete_text_entry,
             history::clear_text_history,
             history::paste_from_history,
+            history::export_history,
+            history::import_history,
+            history::search_history,
+            history::pin_entry,
+            history::unpin_entry,
+            encryption::get_encryption_status,
+            encryption::setup_encryption,
+            encryption::unlock_encryption,
+            encryption::lock_encryption,
+            encryption::encrypt_existing_data,
+            snippets::get_snippets,
+            snippets::create_snippet,
+            snippets::update_snippet,
+            snippets::delete_snippet,
+            sync::get_sync_status,
+            sync::sync_now,
             system::get_vmmem_stats,
             system::get_claude_sessions,
             system::restart_wsl,
+            transforms::get_transforms,
+            transforms::preview_transformed,
+            transforms::paste_transformed,
+            transforms::save_transform_preset,
+            transforms::delete_transform_preset,
+            trash::get_trash,
+            trash::restore_from_trash,
+            trash::undo_delete,
+            trash::empty_trash,
         ])
         .setup(|app| {
             // Setup system tray
@@ -135,23 +210,53 @@ pub fn run() {
                 handle.clone(),
                 state.settings.clone(),
                 state.text_history.clone(),
+                state.paste_queue.clone(),
+                state.hash_index.clone(),
+            );
+
+            // Background retention cleanup
+            state.janitor.start(
+                handle.clone(),
+                state.settings.clone(),
+                state.text_history.clone(),
             );
 
-            // Register global shortcut
-            let shortcut_str = {
+            // Background merge with the shared sync folder
+            state.sync.start(
+                handle.clone(),
+                state.settings.clone(),
+                state.text_history.clone(),
+            );
+
+            // Pick up screenshots added or removed outside the app
+            state.folder_watcher.start(
+                handle.clone(),
+                state.settings.clone(),
+                state.text_history.clone(),
+                state.screenshot_index.clone(),
+                state.hash_index.clone(),
+            );
+
+            // Import images saved by other screenshot tools
+            state.imports.start(
+                handle.clone(),
+                state.settings.clone(),
+                state.text_history.clone(),
+                state.paste_queue.clone(),
+                state.hash_index.clone(),
+            );
+
+            // Register global shortcuts
+            let shortcuts: [(String, fn(&tauri::AppHandle)); 3] = {
                 let s = state.settings.lock().unwrap();
-                s.global_shortcut.clone()
+                [
+                    (s.global_shortcut.clone(), open_popup),
+                    (s.queue_shortcut.clone(), toggle_paste_queue),
+                    (s.queue_paste_shortcut.clone(), queue::paste_next),
+                ]
             };
-
-            let app_handle = handle.clone();
-            if let Ok(shortcut) = shortcut_str.parse::<tauri_plugin_global_shortcut::Shortcut>() {
-                let _ = app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, event| {
-                    if event.state == ShortcutState::Pressed {
-                        let _ = create_popup_window(&app_handle);
-                    }
-                });
-            } else {
-                log::warn!("Failed to parse global shortcut: {}", shortcut_str);
+            for (shortcut, action) in shortcuts {
+                bind_shortcut(handle, &shortcut, action);
             }
 
             Ok(())
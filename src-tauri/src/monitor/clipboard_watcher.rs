@@ -1,15 +1,29 @@
 use crate::config::AppSettings;
 use crate::storage::file_manager::{FileManager, ScreenshotInfo};
-use crate::storage::hash::sha256_hash;
-use crate::storage::text_history::{TextEntry, TextHistory};
+use crate::storage::duplicates;
+use crate::storage::hash::{dhash, pixel_hash, sha256_hash};
+use crate::storage::hash_index::HashIndex;
+use crate::storage::paths;
+use crate::monitor::paste_queue::{PasteQueue, QueueItem};
+use crate::monitor::secrets;
+use crate::storage::text_history::{hash_input, EntryOptions, TextEntry, TextHistory};
 use arboard::Clipboard;
-use image::ImageFormat;
+use chrono::Local;
 use log::{error, info};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 
+/// Result of `ClipboardWatcher::save_image`.
+pub(crate) enum Saved {
+    New(ScreenshotInfo),
+    /// The same pixels are already saved
+    Duplicate,
+    /// Looks like a saved screenshot, so it was skipped
+    Similar,
+}
+
 pub struct ClipboardWatcher {
     running: Arc<AtomicBool>,
 }
@@ -30,6 +44,8 @@ impl ClipboardWatcher {
         app_handle: AppHandle,
         settings: Arc<Mutex<AppSettings>>,
         text_history: Arc<Mutex<TextHistory>>,
+        paste_queue: Arc<Mutex<PasteQueue>>,
+        hash_index: Arc<Mutex<HashIndex>>,
     ) {
         if self.running.load(Ordering::Relaxed) {
             return;
@@ -45,7 +61,7 @@ impl ClipboardWatcher {
 
             // Initialize last_text_hash from most recent entry
             if let Ok(th) = text_history.lock() {
-                last_text_hash = th.last_hash().map(|s| s.to_string());
+                last_text_hash = th.last_hash();
             }
 
             while running.load(Ordering::Relaxed) {
@@ -64,59 +80,77 @@ impl ClipboardWatcher {
                     error!("Failed to create directories: {}", e);
                 }
 
-                // Try image first
-                match Self::check_clipboard_image(
+                // Image and text are read from the same clipboard contents, so
+                // text copied together with a new image is linked to it
+                let image = Self::check_clipboard_image(
                     &mut last_image_hash,
                     &file_manager,
+                    &text_history,
+                    &hash_index,
                     &current_settings,
-                ) {
+                );
+                let screenshot = match &image {
                     Ok(Some(info)) => {
                         info!("New screenshot saved: {}", info.filename);
-                        let _ = app_handle.emit("new-screenshot", &info);
-                    }
-                    Ok(None) => {
-                        // No new image — check text if enabled
-                        if current_settings.capture_text {
-                            match Self::check_clipboard_text(
-                                &mut last_text_hash,
-                                &text_history,
-                                &current_settings,
-                            ) {
-                                Ok(Some(entry)) => {
-                                    info!("New text entry: {}", entry.id);
-                                    let _ = app_handle.emit("new-text-entry", &entry);
-                                }
-                                Ok(None) => {}
-                                Err(e) => {
-                                    if !e.contains("no text") && !e.contains("No text") {
-                                        error!("Text check error: {}", e);
-                                    }
-                                }
-                            }
-                        }
+                        Some(info.filename.clone())
                     }
+                    Ok(None) => None,
                     Err(e) => {
                         if !e.contains("no image") && !e.contains("No image") {
                             error!("Clipboard check error: {}", e);
                         }
-                        // Even if image check errors, try text
-                        if current_settings.capture_text {
-                            match Self::check_clipboard_text(
-                                &mut last_text_hash,
-                                &text_history,
-                                &current_settings,
-                            ) {
-                                Ok(Some(entry)) => {
-                                    info!("New text entry: {}", entry.id);
-                                    let _ = app_handle.emit("new-text-entry", &entry);
-                                }
-                                Ok(None) => {}
-                                Err(_) => {}
+                        None
+                    }
+                };
+
+                let mut text_entry = None;
+                if current_settings.capture_text {
+                    match Self::check_clipboard_text(
+                        &mut last_text_hash,
+                        &text_history,
+                        &current_settings,
+                        screenshot,
+                    ) {
+                        Ok(Some(entry)) => {
+                            info!("New text entry: {}", entry.id);
+                            let _ = app_handle.emit("new-text-entry", &entry);
+                            text_entry = Some(entry);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            if !e.contains("no text") && !e.contains("No text") {
+                                error!("Text check error: {}", e);
                             }
                         }
                     }
                 }
 
+                // In queue mode every capture is also lined up for pasting
+                let queued = match (&text_entry, &image) {
+                    (Some(entry), _) => Some(QueueItem::from_entry(entry)),
+                    (None, Ok(Some(info))) => Some(QueueItem::from_screenshot(info)),
+                    _ => None,
+                };
+                let mut queue_active = false;
+                if let Ok(mut queue) = paste_queue.lock() {
+                    queue_active = queue.is_active();
+                    if queued.is_some_and(|item| queue.push(item)) {
+                        let _ = app_handle.emit("paste-queue-changed", queue.state());
+                    }
+                }
+
+                if let Ok(Some(info)) = &image {
+                    Self::after_screenshot(info, &file_manager, &current_settings, queue_active);
+                    if current_settings.max_screenshots > 0 {
+                        if let (Ok(mut th), Ok(on_disk)) =
+                            (text_history.lock(), file_manager.scan_screenshots())
+                        {
+                            th.sync_screenshots(&on_disk);
+                        }
+                    }
+                    let _ = app_handle.emit("new-screenshot", info);
+                }
+
                 std::thread::sleep(interval);
             }
 
@@ -130,22 +164,36 @@ impl ClipboardWatcher {
 
     fn format_path(path: &str, path_format: &str) -> String {
         match path_format {
-            "wsl" => {
-                if path.len() >= 3 && path.as_bytes()[1] == b':' {
-                    let drive = (path.as_bytes()[0] as char).to_ascii_lowercase();
-                    let rest = &path[2..].replace('\\', "/");
-                    format!("/mnt/{}{}", drive, rest)
-                } else {
-                    path.replace('\\', "/")
-                }
-            }
+            "wsl" => paths::windows_to_wsl(path).unwrap_or_else(|| path.replace('\\', "/")),
             _ => path.to_string(),
         }
     }
 
+    /// Plain text for HTML copied without a text alternative.
+    fn html_to_text(html: &str) -> String {
+        let mut text = String::with_capacity(html.len());
+        let mut in_tag = false;
+        for c in html.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' if in_tag => in_tag = false,
+                _ if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+        text.replace("&nbsp;", " ")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&")
+    }
+
     fn check_clipboard_image(
         last_hash: &mut Option<String>,
         file_manager: &FileManager,
+        text_history: &Arc<Mutex<TextHistory>>,
+        hash_index: &Arc<Mutex<HashIndex>>,
         settings: &AppSettings,
     ) -> Result<Option<ScreenshotInfo>, String> {
         let mut clipboard =
@@ -162,13 +210,9 @@ impl ClipboardWatcher {
         )
         .ok_or_else(|| "Failed to create image from clipboard data".to_string())?;
 
-        let mut png_data = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut png_data);
-        rgba_image
-            .write_to(&mut cursor, ImageFormat::Png)
-            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
-
-        let hash = sha256_hash(&png_data);
+        // The pixels are hashed directly; encoding happens only once the
+        // image is known to be new
+        let hash = pixel_hash(&rgba_image);
 
         if let Some(ref last) = last_hash {
             if *last == hash {
@@ -176,12 +220,82 @@ impl ClipboardWatcher {
             }
         }
 
-        *last_hash = Some(hash);
+        *last_hash = Some(hash.clone());
+
+        // Also catches the image left on the clipboard from before a restart
+        match Self::save_image(
+            rgba_image,
+            hash,
+            file_manager,
+            text_history,
+            hash_index,
+            settings,
+        )? {
+            Saved::New(info) => Ok(Some(info)),
+            Saved::Duplicate | Saved::Similar => Ok(None),
+        }
+    }
 
-        let info = file_manager.save_screenshot(&png_data)?;
+    /// Save an image unless it, or one that looks the same, is already in the
+    /// library. `hash` is its `pixel_hash`. Shared with folder imports.
+    pub(crate) fn save_image(
+        rgba_image: image::RgbaImage,
+        hash: String,
+        file_manager: &FileManager,
+        text_history: &Arc<Mutex<TextHistory>>,
+        hash_index: &Arc<Mutex<HashIndex>>,
+        settings: &AppSettings,
+    ) -> Result<Saved, String> {
+        let mut index = hash_index
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        match index.refresh(file_manager, text_history) {
+            Ok(unhashed) => HashIndex::hash_in_background(hash_index, unhashed),
+            Err(e) => error!("Failed to refresh hash index: {}", e),
+        }
+        if let Some(existing) = index.find(&hash) {
+            info!("Capture is already saved as {}; not saved", existing);
+            return Ok(Saved::Duplicate);
+        }
+
+        let image = image::DynamicImage::ImageRgba8(rgba_image);
+        let phash = dhash(&image);
+        if settings.skip_near_duplicates {
+            let hashes = text_history
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?
+                .screenshot_phashes();
+            if let Some(existing) =
+                duplicates::find_near_duplicate(&hashes, phash, settings.duplicate_threshold)
+            {
+                info!("Capture looks like {}; not saved", existing);
+                return Ok(Saved::Similar);
+            }
+        }
 
-        // Auto-copy path to clipboard if enabled
-        if settings.auto_copy_path {
+        let info = file_manager.save_screenshot(&image)?;
+        if let Err(e) = index.insert(&info, hash.clone()) {
+            error!("Failed to update hash index: {}", e);
+        }
+        if let Ok(mut th) = text_history.lock() {
+            th.record_screenshot(&info);
+            th.set_screenshot_phash(&info.filename, phash);
+            th.set_screenshot_pixel_hash(&info.filename, &hash);
+        }
+        Ok(Saved::New(info))
+    }
+
+    /// Runs once any text copied with the image has been read, since copying
+    /// the path replaces the clipboard contents.
+    pub(crate) fn after_screenshot(
+        info: &ScreenshotInfo,
+        file_manager: &FileManager,
+        settings: &AppSettings,
+        queue_active: bool,
+    ) {
+        // Auto-copy path to clipboard if enabled; not in queue mode, where the
+        // path would be captured and queued after the image
+        if settings.auto_copy_path && !queue_active {
             if let Ok(mut cb) = Clipboard::new() {
                 let formatted = Self::format_path(&info.path, &settings.path_format);
                 let _ = cb.set_text(&formatted);
@@ -192,41 +306,115 @@ impl ClipboardWatcher {
         if settings.max_screenshots > 0 {
             let _ = file_manager.cleanup_old(settings.max_screenshots);
         }
-
-        Ok(Some(info))
     }
 
     fn check_clipboard_text(
         last_text_hash: &mut Option<String>,
         text_history: &Arc<Mutex<TextHistory>>,
         settings: &AppSettings,
+        screenshot: Option<String>,
     ) -> Result<Option<TextEntry>, String> {
         let mut clipboard =
             Clipboard::new().map_err(|e| format!("Clipboard error: {}", e))?;
 
-        let text = clipboard
-            .get_text()
-            .map_err(|e| format!("No text: {}", e))?;
+        // Files copied in a file manager (CF_HDROP, text/uri-list) come first,
+        // then HTML (text/html) with its plain-text alternative
+        let files: Vec<String> = clipboard
+            .get()
+            .file_list()
+            .map(|list| {
+                list.iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let (text, html) = if files.is_empty() {
+            let html = clipboard.get().html().ok().filter(|h| !h.trim().is_empty());
+            let text = match (clipboard.get_text(), &html) {
+                (Ok(text), _) => text,
+                (Err(_), Some(html)) => Self::html_to_text(html),
+                (Err(e), None) => return Err(format!("No text: {}", e)),
+            };
+            (text, html)
+        } else {
+            (files.join("\n"), None)
+        };
 
         // Skip empty or whitespace-only
         if text.trim().is_empty() {
             return Ok(None);
         }
 
-        let hash = sha256_hash(text.as_bytes());
-
-        if let Some(ref last) = last_text_hash {
-            if *last == hash {
-                return Ok(None);
-            }
-        }
-
-        *last_text_hash = Some(hash);
+        let format = match (files.is_empty(), &html) {
+            (false, _) => "files",
+            (true, Some(_)) => "html",
+            (true, None) => "text",
+        };
 
         let mut th = text_history
             .lock()
             .map_err(|e| format!("Lock error: {}", e))?;
         th.set_max_entries(settings.max_text_entries as usize);
-        Ok(th.add_entry(&text))
+        th.set_encrypt(settings.encrypt_at_rest);
+        th.set_size_limits(settings.max_inline_text_kb, settings.max_text_size_mb);
+
+        // Hashed as the history stores it, so the text left on the clipboard
+        // from before a restart matches `last_hash`. While the store is locked
+        // nothing can be recorded, and the plain hash still tells changes apart.
+        let hash = th
+            .capture_hash(format, &text, html.as_deref())
+            .unwrap_or_else(|_| sha256_hash(&hash_input(format, &text, html.as_deref())));
+
+        // Unchanged text still gets linked to a new image that came with it
+        if screenshot.is_none() && last_text_hash.as_deref() == Some(hash.as_str()) {
+            return Ok(None);
+        }
+
+        *last_text_hash = Some(hash);
+
+        let matches = if settings.secret_action == "off" {
+            Vec::new()
+        } else {
+            secrets::detect(&text)
+        };
+        let options = EntryOptions {
+            html,
+            files: !files.is_empty(),
+            screenshot: screenshot.filter(|_| files.is_empty()),
+            ..EntryOptions::default()
+        };
+        if matches.is_empty() {
+            return Ok(th.add_entry_with(&text, options));
+        }
+
+        let kinds: Vec<&str> = matches.iter().map(|m| m.kind).collect();
+        info!("Secret detected in copied text: {}", kinds.join(", "));
+
+        let sensitive = EntryOptions {
+            sensitive: true,
+            ..options
+        };
+        match settings.secret_action.as_str() {
+            "skip" => Ok(None),
+            // The markup would still contain the secret, so only the redacted text is kept
+            "redact" => Ok(th.add_entry_with(
+                &secrets::redact(&text, &matches),
+                EntryOptions {
+                    html: None,
+                    ..sensitive
+                },
+            )),
+            "expire" => {
+                let ttl = settings.secret_expiry_minutes as i64 * 60;
+                Ok(th.add_entry_with(
+                    &text,
+                    EntryOptions {
+                        expires_at: Some(Local::now().timestamp() + ttl),
+                        ..sensitive
+                    },
+                ))
+            }
+            _ => Ok(th.add_entry_with(&text, sensitive)),
+        }
     }
 }
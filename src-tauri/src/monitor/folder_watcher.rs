@@ -0,0 +1,234 @@
+use crate::config::AppSettings;
+use crate::storage::file_manager::{FileManager, ScreenshotInfo};
+use crate::storage::hash_index::HashIndex;
+use crate::storage::screenshot_index::ScreenshotIndex;
+use crate::storage::text_history::TextHistory;
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How often the running flag and the save directory setting are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Quiet time after a change before rescanning, so a burst of events from
+/// one copy or delete is handled once
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+/// Rescan interval when the folder cannot be watched, e.g. on some network drives
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Watches the save directory for screenshots added, changed or removed by
+/// other programs and brings the listing, the history database, the hash
+/// index and the thumbnail cache up to date. Emits `screenshot-added` with
+/// the screenshot's info and `screenshot-removed` with its filename.
+pub struct FolderWatcher {
+    running: Arc<AtomicBool>,
+}
+
+impl FolderWatcher {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn start(
+        &self,
+        app_handle: AppHandle,
+        settings: Arc<Mutex<AppSettings>>,
+        text_history: Arc<Mutex<TextHistory>>,
+        screenshot_index: Arc<Mutex<ScreenshotIndex>>,
+        hash_index: Arc<Mutex<HashIndex>>,
+    ) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            info!("Folder watcher started");
+            let (tx, rx) = mpsc::channel();
+            let mut watcher: Option<RecommendedWatcher> = None;
+            let mut dir = PathBuf::new();
+            // Size and modification time of each screenshot last seen
+            let mut known: HashMap<String, (u64, String)> = HashMap::new();
+            // Nothing is reported for the first scan of a folder
+            let mut primed = false;
+            let mut dirty = false;
+            let mut last_scan = Instant::now();
+
+            while running.load(Ordering::Relaxed) {
+                let current_settings = match settings.lock() {
+                    Ok(s) => s.clone(),
+                    Err(_) => {
+                        std::thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+                };
+                let file_manager = FileManager::new(&current_settings);
+
+                if file_manager.save_dir() != dir {
+                    dir = file_manager.save_dir().to_path_buf();
+                    if let Err(e) = file_manager.ensure_directories() {
+                        error!("Failed to create directories: {}", e);
+                    }
+                    // Dropping the old watcher stops watching the old folder
+                    watcher = Self::watch(&dir, tx.clone());
+                    // The frontend reloads the listing itself when the folder changes
+                    known.clear();
+                    primed = false;
+                    dirty = true;
+                }
+
+                match rx.recv_timeout(POLL_INTERVAL) {
+                    Ok(event) => {
+                        dirty |= Self::is_relevant(&dir, event);
+                        std::thread::sleep(SETTLE_TIME);
+                        for event in rx.try_iter() {
+                            dirty |= Self::is_relevant(&dir, event);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if watcher.is_none() && last_scan.elapsed() >= FALLBACK_INTERVAL {
+                    dirty = true;
+                }
+                if !dirty {
+                    continue;
+                }
+
+                dirty = false;
+                last_scan = Instant::now();
+                match Self::reconcile(
+                    &file_manager,
+                    &mut known,
+                    &text_history,
+                    &screenshot_index,
+                    &hash_index,
+                ) {
+                    Ok((added, removed)) => {
+                        if primed {
+                            for info in &added {
+                                let _ = app_handle.emit("screenshot-added", info);
+                            }
+                            for filename in &removed {
+                                let _ = app_handle.emit("screenshot-removed", filename);
+                            }
+                        }
+                        primed = true;
+                    }
+                    Err(e) => error!("Failed to rescan {}: {}", dir.display(), e),
+                }
+            }
+            info!("Folder watcher stopped");
+        });
+    }
+
+    fn watch(dir: &Path, tx: mpsc::Sender<notify::Result<Event>>) -> Option<RecommendedWatcher> {
+        let result = notify::recommended_watcher(tx).and_then(|mut watcher| {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+            Ok(watcher)
+        });
+        match result {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!(
+                    "Cannot watch {}; rescanning every {} seconds: {}",
+                    dir.display(),
+                    FALLBACK_INTERVAL.as_secs(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Whether an event may have added, changed or removed a screenshot.
+    /// The app's own files in hidden folders, such as thumbnails, are ignored.
+    fn is_relevant(dir: &Path, event: notify::Result<Event>) -> bool {
+        let event = match event {
+            Ok(event) => event,
+            // Events may have been dropped, so look at everything again
+            Err(_) => return true,
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        event.paths.is_empty()
+            || event.paths.iter().any(|path| {
+                path.strip_prefix(dir).is_ok_and(|rel| {
+                    !rel.components()
+                        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+                })
+            })
+    }
+
+    /// Compare the save directory with what was seen last and update
+    /// everything derived from it. Returns the screenshots added and the
+    /// filenames removed.
+    fn reconcile(
+        file_manager: &FileManager,
+        known: &mut HashMap<String, (u64, String)>,
+        text_history: &Arc<Mutex<TextHistory>>,
+        screenshot_index: &Arc<Mutex<ScreenshotIndex>>,
+        hash_index: &Arc<Mutex<HashIndex>>,
+    ) -> Result<(Vec<ScreenshotInfo>, Vec<String>), String> {
+        // Empty files are names reserved by a save still being written
+        let on_disk: Vec<ScreenshotInfo> = file_manager
+            .scan_screenshots()?
+            .into_iter()
+            .filter(|s| s.size_bytes > 0)
+            .collect();
+
+        let mut added = Vec::new();
+        let mut changed = false;
+        for info in &on_disk {
+            let stamp = (info.size_bytes, info.created_at.clone());
+            match known.get(&info.filename) {
+                None => added.push(info.clone()),
+                Some(seen) if *seen != stamp => {
+                    file_manager.remove_thumbnail(&info.filename);
+                    changed = true;
+                }
+                Some(_) => {}
+            }
+        }
+        let names: HashSet<String> = on_disk.iter().map(|s| s.filename.clone()).collect();
+        let removed: Vec<String> = known
+            .keys()
+            .filter(|name| !names.contains(*name))
+            .cloned()
+            .collect();
+        *known = on_disk
+            .iter()
+            .map(|s| (s.filename.clone(), (s.size_bytes, s.created_at.clone())))
+            .collect();
+
+        let orphans = file_manager.remove_orphan_thumbnails(&names);
+        if orphans > 0 {
+            info!("Removed {} orphaned thumbnails", orphans);
+        }
+        if added.is_empty() && removed.is_empty() && !changed {
+            return Ok((added, removed));
+        }
+
+        if let Ok(mut index) = screenshot_index.lock() {
+            index.invalidate();
+        }
+        if let Ok(mut th) = text_history.lock() {
+            th.sync_screenshots(&on_disk);
+        }
+        if let Ok(mut index) = hash_index.lock() {
+            match index.refresh(file_manager, text_history) {
+                Ok(unhashed) => HashIndex::hash_in_background(hash_index, unhashed),
+                Err(e) => error!("Failed to refresh hash index: {}", e),
+            }
+        }
+        Ok((added, removed))
+    }
+}
@@ -0,0 +1,279 @@
+use crate::config::{AppSettings, ImportFolder};
+use crate::monitor::clipboard_watcher::{ClipboardWatcher, Saved};
+use crate::monitor::paste_queue::PasteQueue;
+use crate::storage::file_manager::FileManager;
+use crate::storage::hash::pixel_hash;
+use crate::storage::hash_index::HashIndex;
+use crate::storage::text_history::TextHistory;
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Files changed more recently than this may still be being written
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+/// Folders are also rescanned this often, for missed events and folders
+/// that could not be watched
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+/// Times an image that fails to load is retried before it is skipped
+const MAX_ATTEMPTS: u32 = 5;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp", "gif"];
+
+/// Imports images other screenshot tools save into the configured import
+/// folders, the same way as clipboard captures. In "copy" mode images already
+/// there when watching starts are left alone; in "move" mode everything in the
+/// folder is imported and the originals are deleted.
+pub struct ImportWatcher {
+    running: Arc<AtomicBool>,
+}
+
+impl ImportWatcher {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn start(
+        &self,
+        app_handle: AppHandle,
+        settings: Arc<Mutex<AppSettings>>,
+        text_history: Arc<Mutex<TextHistory>>,
+        paste_queue: Arc<Mutex<PasteQueue>>,
+        hash_index: Arc<Mutex<HashIndex>>,
+    ) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            info!("Import watcher started");
+            let (tx, rx) = mpsc::channel();
+            // Only kept alive; its events arrive on `rx`
+            let mut _watcher: Option<RecommendedWatcher> = None;
+            let mut folders: Vec<ImportFolder> = Vec::new();
+            // Modification time of each file already imported or skipped
+            let mut seen: HashMap<PathBuf, SystemTime> = HashMap::new();
+            let mut failures: HashMap<PathBuf, u32> = HashMap::new();
+            let mut dirty = false;
+            let mut last_scan = Instant::now();
+
+            while running.load(Ordering::Relaxed) {
+                let current_settings = match settings.lock() {
+                    Ok(s) => s.clone(),
+                    Err(_) => {
+                        std::thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+                };
+
+                if current_settings.import_folders != folders {
+                    for folder in &current_settings.import_folders {
+                        let added = !folders.iter().any(|f| f.path == folder.path);
+                        if added && folder.mode == "copy" {
+                            for (path, modified) in Self::images_in(Path::new(&folder.path)) {
+                                seen.insert(path, modified);
+                            }
+                        }
+                    }
+                    folders = current_settings.import_folders.clone();
+                    _watcher = Self::watch(&folders, tx.clone());
+                    dirty = true;
+                }
+                if folders.is_empty() {
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+
+                match rx.recv_timeout(POLL_INTERVAL) {
+                    Ok(event) => {
+                        dirty |= Self::is_relevant(event);
+                        for event in rx.try_iter() {
+                            dirty |= Self::is_relevant(event);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if last_scan.elapsed() >= RESCAN_INTERVAL {
+                    dirty = true;
+                }
+                if !dirty {
+                    continue;
+                }
+
+                last_scan = Instant::now();
+                let file_manager = FileManager::new(&current_settings);
+                if let Err(e) = file_manager.ensure_directories() {
+                    error!("Failed to create directories: {}", e);
+                    continue;
+                }
+                dirty = false;
+                let mut present = HashSet::new();
+                for folder in &folders {
+                    for (path, modified) in Self::images_in(Path::new(&folder.path)) {
+                        present.insert(path.clone());
+                        if seen.get(&path) == Some(&modified) {
+                            continue;
+                        }
+                        // Wait until the tool has finished writing the file
+                        if modified.elapsed().unwrap_or_default() < SETTLE_TIME {
+                            dirty = true;
+                            continue;
+                        }
+                        match Self::import(
+                            &path,
+                            folder,
+                            &app_handle,
+                            &file_manager,
+                            &current_settings,
+                            &text_history,
+                            &paste_queue,
+                            &hash_index,
+                        ) {
+                            Ok(()) => {
+                                failures.remove(&path);
+                                seen.insert(path, modified);
+                            }
+                            Err(e) => {
+                                let attempts = failures.entry(path.clone()).or_insert(0);
+                                *attempts += 1;
+                                if *attempts >= MAX_ATTEMPTS {
+                                    warn!("Skipping {}: {}", path.display(), e);
+                                    failures.remove(&path);
+                                    seen.insert(path, modified);
+                                } else {
+                                    dirty = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                seen.retain(|path, _| present.contains(path));
+                failures.retain(|path, _| present.contains(path));
+            }
+            info!("Import watcher stopped");
+        });
+    }
+
+    fn watch(
+        folders: &[ImportFolder],
+        tx: mpsc::Sender<notify::Result<Event>>,
+    ) -> Option<RecommendedWatcher> {
+        if folders.is_empty() {
+            return None;
+        }
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!(
+                    "Cannot watch import folders; rescanning them instead: {}",
+                    e
+                );
+                return None;
+            }
+        };
+        for folder in folders {
+            if let Err(e) = watcher.watch(Path::new(&folder.path), RecursiveMode::NonRecursive) {
+                warn!(
+                    "Cannot watch import folder {}; rescanning it instead: {}",
+                    folder.path, e
+                );
+            }
+        }
+        Some(watcher)
+    }
+
+    fn is_relevant(event: notify::Result<Event>) -> bool {
+        !matches!(
+            event,
+            Ok(Event {
+                kind: EventKind::Access(_),
+                ..
+            })
+        )
+    }
+
+    /// Images directly in `dir` with their modification times.
+    fn images_in(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_lowercase();
+                !name.starts_with('.')
+                    && name
+                        .rsplit_once('.')
+                        .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext))
+            })
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                if !meta.is_file() || meta.len() == 0 {
+                    return None;
+                }
+                Some((entry.path(), meta.modified().ok()?))
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn import(
+        path: &Path,
+        folder: &ImportFolder,
+        app_handle: &AppHandle,
+        file_manager: &FileManager,
+        settings: &AppSettings,
+        text_history: &Arc<Mutex<TextHistory>>,
+        paste_queue: &Arc<Mutex<PasteQueue>>,
+        hash_index: &Arc<Mutex<HashIndex>>,
+    ) -> Result<(), String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to load image: {}", e))?
+            .into_rgba8();
+        let hash = pixel_hash(&image);
+        let saved = ClipboardWatcher::save_image(
+            image,
+            hash,
+            file_manager,
+            text_history,
+            hash_index,
+            settings,
+        )?;
+
+        let keep_original = match &saved {
+            Saved::New(info) => {
+                info!("Imported {} as {}", path.display(), info.filename);
+                let queue_active = paste_queue.lock().map(|q| q.is_active()).unwrap_or(false);
+                ClipboardWatcher::after_screenshot(info, file_manager, settings, queue_active);
+                if settings.max_screenshots > 0 {
+                    if let (Ok(mut th), Ok(on_disk)) =
+                        (text_history.lock(), file_manager.scan_screenshots())
+                    {
+                        th.sync_screenshots(&on_disk);
+                    }
+                }
+                let _ = app_handle.emit("new-screenshot", info);
+                false
+            }
+            Saved::Duplicate => false,
+            // Only similar, so the original is not thrown away
+            Saved::Similar => true,
+        };
+
+        if folder.mode == "move" && !keep_original {
+            fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove imported {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
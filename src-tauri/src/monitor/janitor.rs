@@ -0,0 +1,118 @@
+use crate::config::AppSettings;
+use crate::storage::file_manager::FileManager;
+use crate::storage::text_history::TextHistory;
+use crate::storage::trash;
+use log::{error, info};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const JANITOR_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionSummary {
+    pub text_entries_removed: u32,
+    pub screenshots_removed: u32,
+    pub bytes_freed: u64,
+    /// Items deleted for good from the trash, when expired or to stay under
+    /// the size limit
+    pub trash_purged: u32,
+}
+
+/// Periodically applies the retention settings to text history and screenshots,
+/// independent of new captures, and empties the trash of expired items.
+pub struct Janitor {
+    running: Arc<AtomicBool>,
+}
+
+impl Janitor {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn start(
+        &self,
+        app_handle: AppHandle,
+        settings: Arc<Mutex<AppSettings>>,
+        text_history: Arc<Mutex<TextHistory>>,
+    ) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            info!("Retention janitor started");
+            while running.load(Ordering::Relaxed) {
+                let current_settings = match settings.lock() {
+                    Ok(s) => s.clone(),
+                    Err(_) => {
+                        std::thread::sleep(JANITOR_INTERVAL);
+                        continue;
+                    }
+                };
+
+                match Self::run_once(&current_settings, &text_history) {
+                    Ok(summary) => {
+                        if summary.text_entries_removed > 0
+                            || summary.screenshots_removed > 0
+                            || summary.trash_purged > 0
+                        {
+                            info!(
+                                "Retention cleanup trashed {} text entries, {} screenshots; freed {} bytes and purged {} from trash",
+                                summary.text_entries_removed,
+                                summary.screenshots_removed,
+                                summary.bytes_freed,
+                                summary.trash_purged
+                            );
+                            let _ = app_handle.emit("retention-cleanup", &summary);
+                        }
+                    }
+                    Err(e) => error!("Retention cleanup failed: {}", e),
+                }
+
+                std::thread::sleep(JANITOR_INTERVAL);
+            }
+            info!("Retention janitor stopped");
+        });
+    }
+
+    fn run_once(
+        settings: &AppSettings,
+        text_history: &Arc<Mutex<TextHistory>>,
+    ) -> Result<RetentionSummary, String> {
+        let file_manager = FileManager::new(settings);
+        let screenshots = file_manager.enforce_retention(
+            settings.max_screenshots,
+            settings.screenshot_max_age_days,
+            settings.screenshot_max_total_mb * 1024 * 1024,
+        )?;
+
+        let mut th = text_history
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        th.set_max_entries(settings.max_text_entries as usize);
+        let text_removed = th.apply_retention(settings.text_max_age_days);
+
+        let cutoff = trash::cutoff(settings.trash_retention_days.max(1));
+        let mut trash_purged = screenshots.trash_purged + th.purge_trash(Some(cutoff)) as u32;
+        trash_purged += file_manager.purge_trash(Some(cutoff))?;
+
+        if screenshots.removed > 0 {
+            if let Ok(on_disk) = file_manager.scan_screenshots() {
+                th.sync_screenshots(&on_disk);
+            }
+        }
+
+        Ok(RetentionSummary {
+            text_entries_removed: text_removed as u32,
+            screenshots_removed: screenshots.removed,
+            bytes_freed: screenshots.bytes_freed,
+            trash_purged,
+        })
+    }
+}
@@ -1 +1,8 @@
 pub mod clipboard_watcher;
+pub mod folder_watcher;
+pub mod import_watcher;
+pub mod janitor;
+pub mod paste_queue;
+pub mod reencoder;
+pub mod secrets;
+pub mod sync_worker;
@@ -0,0 +1,121 @@
+use crate::storage::file_manager::ScreenshotInfo;
+use crate::storage::text_history::TextEntry;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// A captured clip waiting in the queue, in the form `paste_from_history` takes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueItem {
+    pub entry_type: String,
+    /// Text entry id; unset for images
+    pub id: Option<String>,
+    /// Text, or the file path of an image
+    pub content: String,
+    pub preview: String,
+}
+
+impl QueueItem {
+    pub fn from_entry(entry: &TextEntry) -> Self {
+        let entry_type = match entry.screenshot {
+            Some(_) => "multi".to_string(),
+            None => entry.format.clone(),
+        };
+        Self {
+            entry_type,
+            id: Some(entry.id.clone()),
+            content: entry.content.clone(),
+            preview: entry.preview.clone(),
+        }
+    }
+
+    pub fn from_screenshot(info: &ScreenshotInfo) -> Self {
+        Self {
+            entry_type: "image".to_string(),
+            id: None,
+            content: info.path.clone(),
+            preview: info.filename.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PasteQueueState {
+    pub active: bool,
+    pub items: Vec<QueueItem>,
+}
+
+/// First-in, first-out list of clips copied while queue mode is on, pasted
+/// back one at a time in the order they were copied.
+#[derive(Default)]
+pub struct PasteQueue {
+    active: bool,
+    items: VecDeque<QueueItem>,
+    /// Item the queue itself put on the clipboard; the watcher sees it as a
+    /// new capture, which must not be queued again
+    loaded: Option<QueueItem>,
+}
+
+impl PasteQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Turning queue mode off drops whatever is still queued.
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+        if !active {
+            self.items.clear();
+            self.loaded = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.loaded = None;
+    }
+
+    /// Queue a capture from the watcher. Returns false when queue mode is off
+    /// or the capture is the queue's own clipboard load.
+    pub fn push(&mut self, item: QueueItem) -> bool {
+        if !self.active {
+            return false;
+        }
+        if let Some(loaded) = self.loaded.take() {
+            // Images are saved under a new filename when seen again
+            let echo = match (&loaded.id, &item.id) {
+                (Some(a), Some(b)) => a == b,
+                (None, None) => loaded.entry_type == item.entry_type,
+                _ => false,
+            };
+            if echo {
+                return false;
+            }
+        }
+        self.items.push_back(item);
+        true
+    }
+
+    pub fn front(&self) -> Option<&QueueItem> {
+        self.items.front()
+    }
+
+    pub fn pop(&mut self) -> Option<QueueItem> {
+        self.items.pop_front()
+    }
+
+    /// Note that `item` is about to be put on the clipboard.
+    pub fn mark_loaded(&mut self, item: &QueueItem) {
+        self.loaded = Some(item.clone());
+    }
+
+    pub fn state(&self) -> PasteQueueState {
+        PasteQueueState {
+            active: self.active,
+            items: self.items.iter().cloned().collect(),
+        }
+    }
+}
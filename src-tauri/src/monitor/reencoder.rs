@@ -0,0 +1,166 @@
+use crate::config::AppSettings;
+use crate::storage::file_manager::FileManager;
+use crate::storage::hash::sha256_hash;
+use crate::storage::hash_index::HashIndex;
+use crate::storage::image_codec::{self, EncodeOptions};
+use crate::storage::screenshot_index::ScreenshotIndex;
+use crate::storage::text_history::TextHistory;
+use crate::storage::{crypto, sync};
+use log::{error, info};
+use serde::Serialize;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReencodeProgress {
+    pub done: u32,
+    pub total: u32,
+    pub reencoded: u32,
+    /// Encrypted screenshots left alone because the store is locked
+    pub skipped: u32,
+    pub failed: u32,
+    /// Stored size before minus after; negative if files grew
+    pub bytes_saved: i64,
+}
+
+enum Outcome {
+    Locked,
+    Unchanged,
+    Replaced,
+}
+
+/// Rewrites the screenshot library in the configured output format on a
+/// background thread, one pass at a time.
+pub struct Reencoder {
+    running: Arc<AtomicBool>,
+}
+
+impl Reencoder {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start a pass over every stored screenshot. Emits `reencode-progress`
+    /// after each file and `reencode-complete` at the end.
+    pub fn start(
+        &self,
+        app_handle: AppHandle,
+        settings: AppSettings,
+        text_history: Arc<Mutex<TextHistory>>,
+        screenshot_index: Arc<Mutex<ScreenshotIndex>>,
+        hash_index: Arc<Mutex<HashIndex>>,
+    ) -> Result<(), String> {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return Err("Screenshots are already being re-encoded".to_string());
+        }
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            let file_manager = FileManager::new(&settings);
+            let options = EncodeOptions::from_settings(&settings);
+            let screenshots = file_manager.scan_screenshots().unwrap_or_else(|e| {
+                error!("Cannot list screenshots to re-encode: {}", e);
+                Vec::new()
+            });
+            info!(
+                "Re-encoding {} screenshots as {}",
+                screenshots.len(),
+                settings.image_format
+            );
+
+            let mut progress = ReencodeProgress {
+                total: screenshots.len() as u32,
+                ..Default::default()
+            };
+            for shot in &screenshots {
+                match Self::reencode_one(
+                    &file_manager,
+                    &options,
+                    &settings,
+                    &text_history,
+                    &hash_index,
+                    &shot.filename,
+                ) {
+                    Ok((Outcome::Replaced, saved)) => {
+                        progress.reencoded += 1;
+                        progress.bytes_saved += saved;
+                    }
+                    Ok((Outcome::Unchanged, _)) => {}
+                    Ok((Outcome::Locked, _)) => progress.skipped += 1,
+                    Err(e) => {
+                        error!("Failed to re-encode {}: {}", shot.filename, e);
+                        progress.failed += 1;
+                    }
+                }
+                progress.done += 1;
+                let _ = app_handle.emit("reencode-progress", &progress);
+            }
+
+            if let Ok(mut index) = screenshot_index.lock() {
+                index.invalidate();
+            }
+            info!(
+                "Re-encoded {} screenshots, {} bytes saved",
+                progress.reencoded, progress.bytes_saved
+            );
+            let _ = app_handle.emit("reencode-complete", &progress);
+            running.store(false, Ordering::Relaxed);
+        });
+        Ok(())
+    }
+
+    /// Re-encode one screenshot, keeping the result only when the format
+    /// changes, the image was scaled down, or the file gets smaller. Files
+    /// already in a lossy target format are not recompressed, which would only
+    /// lose more detail. Returns the outcome and the bytes saved.
+    fn reencode_one(
+        file_manager: &FileManager,
+        options: &EncodeOptions,
+        settings: &AppSettings,
+        text_history: &Arc<Mutex<TextHistory>>,
+        hash_index: &Arc<Mutex<HashIndex>>,
+        filename: &str,
+    ) -> Result<(Outcome, i64), String> {
+        let path = file_manager.resolve(filename)?;
+        let raw = fs::read(&path).map_err(|e| format!("Failed to read image: {}", e))?;
+        if crypto::is_encrypted(&raw) && !crypto::is_unlocked() {
+            return Ok((Outcome::Locked, 0));
+        }
+        let stored_len = raw.len() as i64;
+        let data = crypto::decrypt_if_needed(raw)?;
+        let image = image_codec::decode(&data)?;
+
+        let extension = options.format.extension();
+        let same_format = filename.ends_with(&format!(".{}", extension));
+        let downscaled = image_codec::downscale(&image, options.max_dimension).is_some();
+        if same_format && !downscaled && options.format.is_lossy() {
+            return Ok((Outcome::Unchanged, 0));
+        }
+        let encoded = image_codec::encode(&image, options)?;
+        if same_format && !downscaled && encoded.len() >= data.len() {
+            return Ok((Outcome::Unchanged, 0));
+        }
+
+        let new_name = file_manager.replace_screenshot(filename, &encoded, extension)?;
+        let info = file_manager.get_screenshot_info(&new_name)?;
+        if let Ok(mut th) = text_history.lock() {
+            th.rename_screenshot(filename, &info);
+        }
+        if let Ok(mut index) = hash_index.lock() {
+            if let Err(e) = index.rename(filename, &info) {
+                error!("Failed to update hash index: {}", e);
+            }
+        }
+        // Synced machines see the old content as deleted and pick up the new file
+        sync::note_screenshot_deleted(settings, sha256_hash(&data));
+        Ok((Outcome::Replaced, stored_len - info.size_bytes as i64))
+    }
+}
@@ -0,0 +1,61 @@
+use crate::config::AppSettings;
+use crate::storage::sync;
+use crate::storage::text_history::TextHistory;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Merges history with the shared sync folder in the background while one is
+/// configured.
+pub struct SyncWorker {
+    running: Arc<AtomicBool>,
+}
+
+impl SyncWorker {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn start(
+        &self,
+        app_handle: AppHandle,
+        settings: Arc<Mutex<AppSettings>>,
+        text_history: Arc<Mutex<TextHistory>>,
+    ) {
+        if self.running.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let running = self.running.clone();
+
+        std::thread::spawn(move || {
+            info!("Sync worker started");
+            while running.load(Ordering::Relaxed) {
+                let current_settings = settings.lock().map(|s| s.clone());
+                if let Ok(current_settings) = current_settings {
+                    if !current_settings.sync_folder.trim().is_empty() {
+                        match sync::run(&current_settings, &text_history) {
+                            Ok(report) => {
+                                if report.applied > 0 || report.conflicts > 0 {
+                                    info!(
+                                        "Sync applied {} changes, published {}, {} conflicts",
+                                        report.applied, report.published, report.conflicts
+                                    );
+                                    let _ = app_handle.emit("sync-complete", &report);
+                                }
+                            }
+                            Err(e) => error!("Sync failed: {}", e),
+                        }
+                    }
+                }
+                std::thread::sleep(SYNC_INTERVAL);
+            }
+            info!("Sync worker stopped");
+        });
+    }
+}
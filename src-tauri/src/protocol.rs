@@ -0,0 +1,377 @@
+use crate::state::AppState;
+use crate::storage::crypto;
+use crate::storage::file_manager::FileManager;
+use crate::storage::percent;
+use log::warn;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::http::{header, Method, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager};
+
+/// Scheme the webview loads screenshots from. Paths are `/image/<filename>`
+/// for the full image and `/thumb/<filename>` for its thumbnail.
+pub const SCHEME: &str = "buddy";
+
+const CACHE_MAX_AGE: &str = "private, max-age=86400";
+
+pub fn handle(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let state = app.state::<AppState>();
+    let file_manager = match state.settings.lock() {
+        Ok(settings) => FileManager::new(&settings),
+        Err(e) => {
+            warn!("Lock error: {}", e);
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    respond(&file_manager, request)
+}
+
+/// Serve a screenshot or thumbnail from the store managed by `file_manager`.
+pub fn respond(file_manager: &FileManager, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let method = request.method();
+    if method != Method::GET && method != Method::HEAD {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    // The webview escapes the whole path, slashes included
+    let Ok(path) = percent::decode(request.uri().path()) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let Some((kind, filename)) = path.trim_start_matches('/').split_once('/') else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    if kind != "image" && kind != "thumb" {
+        return status(StatusCode::NOT_FOUND);
+    }
+    let Ok(image_path) = file_manager.resolve(filename) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+
+    // Decrypted images must not end up in the webview's disk cache
+    let encrypted = crypto::is_encrypted_file(&image_path);
+    if encrypted && !crypto::is_unlocked() {
+        return status(StatusCode::LOCKED);
+    }
+    let etag = etag(kind, &image_path);
+    if let Some(etag) = etag.as_deref().filter(|_| !encrypted) {
+        if if_none_match(request) == Some(etag) {
+            return Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(header::ETAG, etag)
+                .header(header::CACHE_CONTROL, CACHE_MAX_AGE)
+                .body(Vec::new())
+                .unwrap_or_default();
+        }
+    }
+
+    let data = if kind == "image" {
+        fs::read(&image_path)
+            .map_err(|e| e.to_string())
+            .and_then(crypto::decrypt_if_needed)
+            .map(Some)
+    } else {
+        file_manager.thumbnail(filename)
+    };
+    let data = match data {
+        Ok(Some(data)) => data,
+        Ok(None) => return status(StatusCode::LOCKED),
+        Err(e) => {
+            warn!("Failed to serve {}: {}", path, e);
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let len = data.len();
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(Ok(None), |v| parse_range(v, len));
+    // Thumbnails are always PNG
+    let content_type = if kind == "thumb" {
+        "image/png"
+    } else {
+        mime_type(filename)
+    };
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ACCEPT_RANGES, "bytes");
+    builder = match (&etag, encrypted) {
+        (Some(etag), false) => builder
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, CACHE_MAX_AGE),
+        _ => builder.header(header::CACHE_CONTROL, "no-store"),
+    };
+    let (builder, body) = match range {
+        Ok(Some((start, end))) => (
+            builder.status(StatusCode::PARTIAL_CONTENT).header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, len),
+            ),
+            data[start..=end].to_vec(),
+        ),
+        Ok(None) => (builder.status(StatusCode::OK), data),
+        Err(()) => (
+            builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len)),
+            Vec::new(),
+        ),
+    };
+    let body = if method == Method::HEAD {
+        Vec::new()
+    } else {
+        body
+    };
+    builder
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(body)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(code)
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+/// Validator from the stored file's size and modification time; a thumbnail
+/// changes only when its screenshot does.
+fn etag(kind: &str, path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!(
+        "\"{}-{:x}-{:x}\"",
+        kind,
+        metadata.len(),
+        modified.as_nanos()
+    ))
+}
+
+fn if_none_match(request: &Request<Vec<u8>>) -> Option<&str> {
+    request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+}
+
+/// Inclusive byte range requested by a single-range `Range` header over a
+/// body of `len` bytes. `Ok(None)` serves the whole body, which is also the
+/// answer to multiple or malformed ranges; `Err` means it cannot be satisfied.
+fn parse_range(value: &str, len: usize) -> Result<Option<(usize, usize)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return Ok(None);
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last `end` bytes
+        let Ok(suffix) = end.parse::<usize>() else {
+            return Ok(None);
+        };
+        if suffix == 0 || len == 0 {
+            return Err(());
+        }
+        return Ok(Some((len.saturating_sub(suffix), len - 1)));
+    }
+
+    let Ok(start) = start.parse::<usize>() else {
+        return Ok(None);
+    };
+    let end = if end.is_empty() {
+        usize::MAX
+    } else {
+        match end.parse::<usize>() {
+            Ok(end) if end >= start => end,
+            _ => return Ok(None),
+        }
+    };
+    if start >= len {
+        return Err(());
+    }
+    Ok(Some((start, end.min(len - 1))))
+}
+
+fn mime_type(filename: &str) -> &'static str {
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        _ => "image/png",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppSettings;
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_range("bytes=0-9", 100), Ok(Some((0, 9))));
+        assert_eq!(parse_range("bytes=10-10", 100), Ok(Some((10, 10))));
+        assert_eq!(parse_range(" bytes=20- ", 100), Ok(Some((20, 99))));
+        assert_eq!(parse_range("bytes=-30", 100), Ok(Some((70, 99))));
+        // Ends past the body are clamped, as are suffixes longer than it
+        assert_eq!(parse_range("bytes=90-500", 100), Ok(Some((90, 99))));
+        assert_eq!(parse_range("bytes=-500", 100), Ok(Some((0, 99))));
+    }
+
+    #[test]
+    fn ignores_unsupported_ranges() {
+        assert_eq!(parse_range("bytes=9-0", 100), Ok(None));
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), Ok(None));
+        assert_eq!(parse_range("bytes=a-b", 100), Ok(None));
+        assert_eq!(parse_range("bytes=-", 100), Ok(None));
+        assert_eq!(parse_range("items=0-9", 100), Ok(None));
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=100-", 100), Err(()));
+        assert_eq!(parse_range("bytes=150-200", 100), Err(()));
+        assert_eq!(parse_range("bytes=-0", 100), Err(()));
+        assert_eq!(parse_range("bytes=0-", 0), Err(()));
+        assert_eq!(parse_range("bytes=-5", 0), Err(()));
+    }
+
+    fn store(name: &str) -> (FileManager, String, Vec<u8>) {
+        let dir = std::env::temp_dir().join(format!(
+            "cli-buddy-protocol-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        let settings = AppSettings {
+            save_directory: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let file_manager = FileManager::new(&settings);
+        let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            8,
+            8,
+            image::Rgba([9, 8, 7, 255]),
+        ));
+        let info = file_manager.save_screenshot(&image).unwrap();
+        let data = fs::read(&info.path).unwrap();
+        (file_manager, info.filename, data)
+    }
+
+    fn get(file_manager: &FileManager, uri: &str, headers: &[(&str, &str)]) -> Response<Vec<u8>> {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        respond(file_manager, &builder.body(Vec::new()).unwrap())
+    }
+
+    fn header_of(response: &Response<Vec<u8>>, name: header::HeaderName) -> &str {
+        response.headers()[name].to_str().unwrap()
+    }
+
+    #[test]
+    fn serves_ranges() {
+        let (file_manager, filename, data) = store("ranges");
+        let uri = format!("{}://localhost/image/{}", SCHEME, filename);
+        let len = data.len();
+
+        let response = get(&file_manager, &uri, &[]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), &data);
+        assert_eq!(header_of(&response, header::CONTENT_TYPE), "image/png");
+
+        let response = get(&file_manager, &uri, &[("range", "bytes=2-5")]);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.body(), &data[2..=5]);
+        assert_eq!(
+            header_of(&response, header::CONTENT_RANGE),
+            format!("bytes 2-5/{}", len)
+        );
+        assert_eq!(header_of(&response, header::CONTENT_LENGTH), "4");
+
+        let response = get(&file_manager, &uri, &[("range", "bytes=-3")]);
+        assert_eq!(response.body(), &data[len - 3..]);
+
+        let response = get(
+            &file_manager,
+            &uri,
+            &[("range", &format!("bytes={}-", len))],
+        );
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert!(response.body().is_empty());
+        assert_eq!(
+            header_of(&response, header::CONTENT_RANGE),
+            format!("bytes */{}", len)
+        );
+
+        let response = get(&file_manager, &uri, &[("range", "bytes=0-1,4-5")]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), &data);
+    }
+
+    #[test]
+    fn revalidates_with_etag() {
+        let (file_manager, filename, _) = store("etag");
+        let uri = format!("{}://localhost/image/{}", SCHEME, filename);
+
+        let response = get(&file_manager, &uri, &[]);
+        let etag = header_of(&response, header::ETAG).to_string();
+        assert_eq!(header_of(&response, header::CACHE_CONTROL), CACHE_MAX_AGE);
+
+        let response = get(&file_manager, &uri, &[("if-none-match", &etag)]);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response.body().is_empty());
+        assert_eq!(header_of(&response, header::ETAG), etag);
+
+        let response = get(&file_manager, &uri, &[("if-none-match", "\"stale\"")]);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The thumbnail has its own validator
+        let thumb = format!("{}://localhost/thumb/{}", SCHEME, filename);
+        let response = get(&file_manager, &thumb, &[("if-none-match", &etag)]);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(header_of(&response, header::ETAG), etag);
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let (file_manager, filename, _) = store("errors");
+        let missing = format!("{}://localhost/image/missing.png", SCHEME);
+        assert_eq!(
+            get(&file_manager, &missing, &[]).status(),
+            StatusCode::NOT_FOUND
+        );
+        let other = format!("{}://localhost/other/{}", SCHEME, filename);
+        assert_eq!(
+            get(&file_manager, &other, &[]).status(),
+            StatusCode::NOT_FOUND
+        );
+        let escaped = format!("{}://localhost/image/..%2F..%2Fsecret.png", SCHEME);
+        assert_eq!(
+            get(&file_manager, &escaped, &[]).status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("{}://localhost/image/{}", SCHEME, filename))
+            .body(Vec::new())
+            .unwrap();
+        assert_eq!(
+            respond(&file_manager, &request).status(),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+}
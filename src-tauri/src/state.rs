@@ -1,22 +1,53 @@
 use crate::config::AppSettings;
 use crate::monitor::clipboard_watcher::ClipboardWatcher;
+use crate::monitor::folder_watcher::FolderWatcher;
+use crate::monitor::import_watcher::ImportWatcher;
+use crate::monitor::janitor::Janitor;
+use crate::monitor::paste_queue::PasteQueue;
+use crate::monitor::reencoder::Reencoder;
+use crate::monitor::sync_worker::SyncWorker;
+use crate::storage::file_manager::FileManager;
+use crate::storage::hash_index::HashIndex;
+use crate::storage::screenshot_index::ScreenshotIndex;
+use crate::storage::snippets::SnippetStore;
 use crate::storage::text_history::TextHistory;
 use std::sync::{Arc, Mutex};
 
 pub struct AppState {
     pub settings: Arc<Mutex<AppSettings>>,
     pub watcher: ClipboardWatcher,
+    pub janitor: Janitor,
+    pub sync: SyncWorker,
+    pub folder_watcher: FolderWatcher,
+    pub imports: ImportWatcher,
+    pub reencoder: Reencoder,
     pub text_history: Arc<Mutex<TextHistory>>,
+    pub snippets: Arc<Mutex<SnippetStore>>,
+    pub paste_queue: Arc<Mutex<PasteQueue>>,
+    pub screenshot_index: Arc<Mutex<ScreenshotIndex>>,
+    pub hash_index: Arc<Mutex<HashIndex>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         let settings = AppSettings::load();
-        let text_history = TextHistory::new(settings.max_text_entries as usize);
+        let mut text_history = TextHistory::new(settings.max_text_entries as usize);
+        if let Ok(screenshots) = FileManager::new(&settings).scan_screenshots() {
+            text_history.sync_screenshots(&screenshots);
+        }
         Self {
             settings: Arc::new(Mutex::new(settings)),
             watcher: ClipboardWatcher::new(),
+            janitor: Janitor::new(),
+            sync: SyncWorker::new(),
+            folder_watcher: FolderWatcher::new(),
+            imports: ImportWatcher::new(),
+            reencoder: Reencoder::new(),
             text_history: Arc::new(Mutex::new(text_history)),
+            snippets: Arc::new(Mutex::new(SnippetStore::new())),
+            paste_queue: Arc::new(Mutex::new(PasteQueue::new())),
+            screenshot_index: Arc::new(Mutex::new(ScreenshotIndex::new())),
+            hash_index: Arc::new(Mutex::new(HashIndex::new())),
         }
     }
 }
@@ -0,0 +1,170 @@
+use crate::storage::file_manager::FileManager;
+use crate::storage::hash::sha256_hash;
+use crate::storage::text_history::{TextEntry, TextHistory};
+use chrono::Local;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Layout version of `manifest.json`; archives from newer versions are refused.
+const ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+const SCREENSHOT_DIR: &str = "screenshots";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    exported_at: String,
+    text_entries: Vec<TextEntry>,
+    screenshots: Vec<ArchivedScreenshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedScreenshot {
+    filename: String,
+    created_at: String,
+    size_bytes: u64,
+    /// SHA-256 of the image as stored in the archive (always unencrypted)
+    sha256: String,
+    #[serde(default)]
+    pinned: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveSummary {
+    pub text_entries: u32,
+    pub screenshots: u32,
+    /// Entries left out on import because the same content already exists
+    pub skipped: u32,
+}
+
+fn zip_err(e: zip::result::ZipError) -> String {
+    format!("Archive error: {}", e)
+}
+
+/// Write all text history and screenshots, decrypted, to a zip at `path`.
+pub fn export(
+    path: &Path,
+    file_manager: &FileManager,
+    history: &TextHistory,
+) -> Result<ArchiveSummary, String> {
+    let text_entries = history.export_entries()?;
+
+    // Build next to the target and move into place once complete
+    let tmp_path = path.with_extension("zip.tmp");
+    let result = (|| -> Result<ArchiveSummary, String> {
+        let file = fs::File::create(&tmp_path).map_err(|e| e.to_string())?;
+        let mut zip = ZipWriter::new(file);
+        // PNGs are already compressed
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated =
+            SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut screenshots = Vec::new();
+        for info in file_manager.scan_screenshots()? {
+            let data = file_manager.get_image_data(&info.filename)?;
+            zip.start_file(format!("{}/{}", SCREENSHOT_DIR, info.filename), stored)
+                .map_err(zip_err)?;
+            zip.write_all(&data).map_err(|e| e.to_string())?;
+            screenshots.push(ArchivedScreenshot {
+                filename: info.filename,
+                created_at: info.created_at,
+                size_bytes: data.len() as u64,
+                sha256: sha256_hash(&data),
+                pinned: info.pinned,
+            });
+        }
+
+        let summary = ArchiveSummary {
+            text_entries: text_entries.len() as u32,
+            screenshots: screenshots.len() as u32,
+            skipped: 0,
+        };
+        let manifest = Manifest {
+            version: ARCHIVE_VERSION,
+            exported_at: Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string(),
+            text_entries,
+            screenshots,
+        };
+        zip.start_file(MANIFEST_NAME, deflated).map_err(zip_err)?;
+        serde_json::to_writer_pretty(&mut zip, &manifest).map_err(|e| e.to_string())?;
+
+        let file = zip.finish().map_err(zip_err)?;
+        file.sync_all().map_err(|e| e.to_string())?;
+        fs::rename(&tmp_path, path).map_err(|e| e.to_string())?;
+        Ok(summary)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.map_err(|e| format!("Failed to export history: {}", e))
+}
+
+/// Merge an archive written by `export` into local history. Text entries and
+/// screenshots whose SHA-256 is already present are skipped, so importing the
+/// same archive twice adds nothing.
+pub fn import(
+    path: &Path,
+    file_manager: &FileManager,
+    history: &mut TextHistory,
+) -> Result<ArchiveSummary, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut zip = ZipArchive::new(file).map_err(zip_err)?;
+
+    let manifest: Manifest = {
+        let entry = zip.by_name(MANIFEST_NAME).map_err(zip_err)?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid manifest: {}", e))?
+    };
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than this app supports",
+            manifest.version
+        ));
+    }
+
+    let mut summary = ArchiveSummary::default();
+
+    let since_seq = history.last_seq();
+    for entry in &manifest.text_entries {
+        if history.import_entry(entry)? {
+            summary.text_entries += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    history.finish_import(since_seq)?;
+
+    let mut known = file_manager.content_hashes()?;
+    for shot in &manifest.screenshots {
+        let mut data = Vec::new();
+        zip.by_name(&format!("{}/{}", SCREENSHOT_DIR, shot.filename))
+            .map_err(zip_err)?
+            .read_to_end(&mut data)
+            .map_err(|e| e.to_string())?;
+
+        // Dedupe on the actual bytes rather than trusting the manifest
+        let hash = sha256_hash(&data);
+        if hash != shot.sha256 {
+            warn!("Checksum mismatch for {} in archive", shot.filename);
+        }
+        if !known.insert(hash) {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let mut info = file_manager.import_screenshot(&shot.filename, &data)?;
+        if shot.pinned {
+            file_manager.set_pinned(&info.filename, true)?;
+            info.pinned = true;
+        }
+        history.record_screenshot(&info);
+        summary.screenshots += 1;
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,65 @@
+use crate::storage::safe_file;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// Content-addressed files for clips too large to keep in the database.
+/// Each blob is stored as `<dir>/<first two hex chars>/<hash>`.
+pub struct BlobStore {
+    dir: PathBuf,
+}
+
+fn is_valid_hash(hash: &str) -> bool {
+    hash.len() >= 8 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl BlobStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path_for(&self, hash: &str) -> Result<PathBuf, String> {
+        if !is_valid_hash(hash) {
+            return Err(format!("Invalid blob hash: {}", hash));
+        }
+        Ok(self.dir.join(&hash[..2]).join(hash))
+    }
+
+    /// Store `data` under `hash`. Existing blobs are left as they are.
+    pub fn put(&self, hash: &str, data: &[u8]) -> Result<(), String> {
+        let path = self.path_for(hash)?;
+        if path.exists() {
+            return Ok(());
+        }
+        safe_file::write_atomic(&path, data)
+    }
+
+    pub fn get(&self, hash: &str) -> Result<Vec<u8>, String> {
+        fs::read(self.path_for(hash)?).map_err(|e| format!("Failed to read blob {}: {}", hash, e))
+    }
+
+    /// Delete every blob not in `referenced`. Returns the number removed.
+    pub fn remove_unreferenced(&self, referenced: &HashSet<String>) -> usize {
+        let Ok(shards) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for shard in shards.flatten() {
+            let Ok(files) = fs::read_dir(shard.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let name = file.file_name().to_string_lossy().to_string();
+                // Leave temp files from in-flight writes and anything we did not create
+                if !is_valid_hash(&name) || referenced.contains(&name) {
+                    continue;
+                }
+                if fs::remove_file(file.path()).is_ok() {
+                    removed += 1;
+                }
+            }
+            let _ = fs::remove_dir(shard.path()); // only succeeds once empty
+        }
+        removed
+    }
+}
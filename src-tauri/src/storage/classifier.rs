@@ -0,0 +1,328 @@
+use crate::storage::paths::{windows_to_wsl, wsl_to_windows};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Kinds a text entry can be classified as, stored in `text_entries.kind`.
+pub const KINDS: &[&str] = &[
+    "url",
+    "windows_path",
+    "posix_path",
+    "json",
+    "shell",
+    "stack_trace",
+    "git_sha",
+    "uuid",
+    "color",
+    "code",
+    "prose",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Classification {
+    pub kind: String,
+    /// Kind-specific details, e.g. `host` for URLs or `language` for code
+    pub meta: BTreeMap<String, String>,
+}
+
+impl Classification {
+    fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            meta: BTreeMap::new(),
+        }
+    }
+
+    fn with(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.meta.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+struct Patterns {
+    url: Regex,
+    uuid: Regex,
+    hex_color: Regex,
+    func_color: Regex,
+    windows_path: Regex,
+    unc_path: Regex,
+    posix_path: Regex,
+    shell: Regex,
+    stack_traces: Vec<(&'static str, Regex)>,
+    languages: Vec<(&'static str, Vec<Regex>)>,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let re = |p: &str| Regex::new(p).expect("valid classifier pattern");
+        let lang = |name: &'static str, ps: &[&str]| (name, ps.iter().map(|p| re(p)).collect());
+        Patterns {
+            url: re(r"^(?i)(?:(https?|ftp|file|ssh|wss?)://([^/\s?#]*)|(www\.[^/\s?#]+\.[a-z]{2,}))\S*$"),
+            uuid: re(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-([0-9a-fA-F])[0-9a-fA-F]{3}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"),
+            hex_color: re(r"^#(?:[0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$"),
+            func_color: re(r"^(?i)(rgba?|hsla?)\(\s*[\d.]+%?\s*[,\s]\s*[\d.]+%?\s*[,\s]\s*[\d.]+%?\s*(?:[,/]\s*[\d.]+%?\s*)?\)$"),
+            windows_path: re(r"^[A-Za-z]:(?:[\\/][^<>:\x22|?*\r\n]*)?$"),
+            unc_path: re(r"^\\\\[^\\/\s]+[\\/][^<>:\x22|?*\r\n]+$"),
+            posix_path: re(r"^(?:~|\.{1,2})?/[^\s:*?\x22<>|]*(?:[ ][^\s:*?\x22<>|]+)*$"),
+            shell: re(r"^(?:\$ |sudo |(?:git|cd|ls|ll|cat|echo|grep|rg|find|rm|mv|cp|mkdir|chmod|chown|curl|wget|ssh|scp|tar|npm|npx|pnpm|yarn|node|cargo|rustup|python3?|pip3?|uv|go|make|docker|kubectl|helm|apt|apt-get|brew|wsl|code|claude|export|source|systemctl|journalctl|tail|head|less|touch|ln|ps|kill|pkill|tmux)(?:\s|$))"),
+            stack_traces: vec![
+                ("python", re(r"(?m)^Traceback \(most recent call last\):")),
+                ("rust", re(r"thread '[^']*' panicked at")),
+                ("go", re(r"(?m)^goroutine \d+ \[")),
+                ("java", re(r"(?m)^\s+at [\w$.]+\([\w$]+\.(?:java|kt|scala):\d+\)")),
+                ("javascript", re(r"(?m)^\s+at (?:.+ \()?(?:file://)?[^\s()]+:\d+:\d+\)?$")),
+                ("csharp", re(r"(?m)^\s+at [\w.<>`]+\(.*\) in .+:line \d+")),
+            ],
+            languages: vec![
+                lang("rust", &[r"\bfn \w+", r"\blet mut\b", r"\bimpl\b", r"\bpub (?:fn|struct|enum|mod)\b", r"\buse \w+::", r"&mut |&self\b"]),
+                lang("python", &[r"(?m)^\s*def \w+\(.*\):", r"(?m)^\s*(?:from \w+ )?import \w+", r"\bself\.", r"(?m)^\s*(?:elif|except)\b", r"(?m)^\s*class \w+.*:$"]),
+                lang("typescript", &[r":\s*(?:string|number|boolean)\b", r"\binterface \w+\s*\{", r"\bexport (?:type|interface)\b", r"\bas const\b"]),
+                lang("javascript", &[r"\b(?:const|let) \w+\s*=", r"=>", r"\bfunction\s*\w*\(", r"\bconsole\.log\(", r"\brequire\(|\bimport .+ from "]),
+                lang("go", &[r"(?m)^package \w+", r"\bfunc (?:\(\w+ \*?\w+\) )?\w+\(", r":=", r"\bfmt\.\w+\("]),
+                lang("java", &[r"\bpublic (?:static )?(?:class|void)\b", r"\bSystem\.out\.", r"\bprivate final\b"]),
+                lang("c", &[r"(?m)^#include\s*[<\x22]", r"\bint main\s*\(", r"\bprintf\("]),
+                lang("cpp", &[r"\bstd::", r"(?m)^#include\s*<\w+>$", r"\btemplate\s*<"]),
+                lang("sql", &[r"(?i)\bselect\b.+\bfrom\b", r"(?i)\binsert into\b", r"(?i)\bcreate table\b", r"(?i)\bwhere\b.+="]),
+                lang("html", &[r"<(?:div|span|html|body|head|p|a|ul|li|script)\b[^>]*>", r"</\w+>"]),
+                lang("css", &[r"(?m)^[.#]?[\w-]+(?:[ ,>:.#][\w-]+)*\s*\{", r"(?m)^\s*[a-z-]+:\s*[^;]+;\s*$"]),
+            ],
+        }
+    })
+}
+
+fn is_git_sha(s: &str) -> bool {
+    matches!(s.len(), 7..=12 | 40 | 64)
+        && s.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+        && s.chars().any(|c| c.is_ascii_digit())
+        && s.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn normalize_hex_color(hex: &str) -> String {
+    let digits = &hex[1..];
+    let expanded: String = if digits.len() <= 4 {
+        digits.chars().flat_map(|c| [c, c]).collect()
+    } else {
+        digits.to_string()
+    };
+    format!("#{}", expanded.to_ascii_lowercase())
+}
+
+fn guess_language(text: &str) -> Option<&'static str> {
+    let mut best: Option<(&str, usize)> = None;
+    for (name, signals) in &patterns().languages {
+        let score = signals.iter().filter(|re| re.is_match(text)).count();
+        if score > best.map_or(0, |(_, s)| s) {
+            best = Some((name, score));
+        }
+    }
+    best.filter(|(_, score)| *score >= 2).map(|(name, _)| name)
+}
+
+/// Lines that end like statements or blocks in brace/semicolon languages.
+fn looks_structured(text: &str) -> bool {
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if lines.len() < 2 {
+        return false;
+    }
+    let structured = lines
+        .iter()
+        .filter(|l| l.ends_with([';', '{', '}', ')', ',']) || l.starts_with(['}', '#', '/']))
+        .count();
+    structured * 2 >= lines.len()
+}
+
+fn classify_line(line: &str) -> Option<Classification> {
+    let p = patterns();
+
+    if let Some(caps) = p.url.captures(line) {
+        let scheme = caps.get(1).map_or("https", |m| m.as_str());
+        // Drop credentials and port from the authority
+        let authority = caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str());
+        let host = authority.rsplit('@').next().unwrap_or_default();
+        let host = host.split(':').next().unwrap_or_default();
+        let mut c = Classification::new("url").with("scheme", scheme.to_ascii_lowercase());
+        if !host.is_empty() {
+            c = c.with("host", host.to_ascii_lowercase());
+        }
+        return Some(c);
+    }
+    if let Some(caps) = p.uuid.captures(line) {
+        return Some(Classification::new("uuid").with("version", &caps[1]));
+    }
+    if is_git_sha(line) {
+        return Some(Classification::new("git_sha").with("short", &line[..7]));
+    }
+    if p.hex_color.is_match(line) {
+        return Some(
+            Classification::new("color")
+                .with("format", "hex")
+                .with("hex", normalize_hex_color(line)),
+        );
+    }
+    if let Some(caps) = p.func_color.captures(line) {
+        return Some(Classification::new("color").with("format", caps[1].to_ascii_lowercase()));
+    }
+
+    let unquoted = line
+        .strip_prefix('"')
+        .and_then(|l| l.strip_suffix('"'))
+        .unwrap_or(line);
+    if p.windows_path.is_match(unquoted) {
+        let mut c = Classification::new("windows_path");
+        if let Some(wsl) = windows_to_wsl(unquoted) {
+            c = c.with("wsl_path", wsl);
+        }
+        return Some(c);
+    }
+    if p.unc_path.is_match(unquoted) {
+        let c = Classification::new("windows_path").with("unc", "true");
+        // \\wsl$\<distro>\... and \\wsl.localhost\<distro>\... point into a WSL filesystem
+        let lower = unquoted.to_ascii_lowercase();
+        for prefix in ["\\\\wsl$\\", "\\\\wsl.localhost\\"] {
+            if lower.starts_with(prefix) {
+                let inner = &unquoted[prefix.len()..];
+                let distro = inner.split('\\').next().unwrap_or(inner);
+                let wsl = windows_to_wsl(unquoted).unwrap_or_default();
+                return Some(c.with("wsl_distro", distro).with("wsl_path", wsl));
+            }
+        }
+        return Some(c);
+    }
+    if p.posix_path.is_match(unquoted) && unquoted.len() > 1 {
+        let mut c = Classification::new("posix_path");
+        if let Some(win) = wsl_to_windows(unquoted) {
+            c = c.with("windows_path", win);
+        }
+        return Some(c);
+    }
+    if p.shell.is_match(line) || line.contains(" | ") || line.contains(" && ") {
+        let command = line.trim_start_matches("$ ").trim_start_matches("sudo ");
+        let program = command.split_whitespace().next().unwrap_or_default();
+        return Some(Classification::new("shell").with("program", program));
+    }
+    None
+}
+
+/// Classify copied text by its shape. Falls back to "prose".
+pub fn classify(text: &str) -> Classification {
+    let trimmed = text.trim();
+    let p = patterns();
+
+    if trimmed.starts_with(['{', '[']) {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            let root = if value.is_array() { "array" } else { "object" };
+            return Classification::new("json").with("root", root);
+        }
+    }
+
+    if !trimmed.contains('\n') {
+        if let Some(c) = classify_line(trimmed) {
+            return c;
+        }
+    }
+
+    for (language, re) in &p.stack_traces {
+        if re.is_match(trimmed) {
+            return Classification::new("stack_trace").with("language", *language);
+        }
+    }
+
+    if trimmed.starts_with("#!") {
+        let interpreter = trimmed.lines().next().unwrap_or_default();
+        if interpreter.contains("sh") {
+            return Classification::new("shell").with("program", interpreter.trim_start_matches("#!"));
+        }
+    }
+
+    if let Some(language) = guess_language(trimmed) {
+        return Classification::new("code").with("language", language);
+    }
+    if looks_structured(trimmed) {
+        return Classification::new("code").with("language", "unknown");
+    }
+
+    let words = trimmed.split_whitespace().count();
+    Classification::new("prose").with("words", words.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(text: &str) -> String {
+        classify(text).kind
+    }
+
+    #[test]
+    fn classifies_single_lines() {
+        for (text, expected) in [
+            ("https://user:pw@Example.com:8080/a?b=1", "url"),
+            ("www.example.com", "url"),
+            ("550e8400-e29b-41d4-a716-446655440000", "uuid"),
+            ("4ee62d4", "git_sha"),
+            ("#0aF", "color"),
+            ("hsla(120, 50%, 50%, 0.3)", "color"),
+            ("C:\\Users\\kim", "windows_path"),
+            ("\\\\server\\share\\file.txt", "windows_path"),
+            ("./src/main.rs", "posix_path"),
+            ("$ cargo build --release", "shell"),
+            ("make test && make install", "shell"),
+        ] {
+            assert_eq!(kind(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn extracts_meta() {
+        let c = classify("https://user:pw@Example.com:8080/a?b=1");
+        assert_eq!(c.meta["host"], "example.com");
+        assert_eq!(c.meta["scheme"], "https");
+        assert_eq!(
+            classify("550e8400-e29b-41d4-a716-446655440000").meta["version"],
+            "4"
+        );
+        assert_eq!(classify("#0aF").meta["hex"], "#00aaff");
+        assert_eq!(classify("sudo apt install jq").meta["program"], "apt");
+        assert_eq!(classify("/mnt/d").meta["windows_path"], "D:\\");
+        let c = classify("\\\\wsl.localhost\\Debian\\etc\\hosts");
+        assert_eq!(c.meta["wsl_distro"], "Debian");
+        assert_eq!(c.meta["wsl_path"], "/etc/hosts");
+    }
+
+    #[test]
+    fn classifies_multiline() {
+        assert_eq!(classify("[1, 2, 3]").meta["root"], "array");
+        assert_eq!(
+            classify("goroutine 1 [running]:\nmain.main()").meta["language"],
+            "go"
+        );
+        assert_eq!(
+            classify("#!/bin/bash\nset -e\necho hi").meta["program"],
+            "/bin/bash"
+        );
+        let go = "package main\n\nfunc main() {\n\tx := 1\n}";
+        assert_eq!(classify(go).meta["language"], "go");
+        let unknown = "foo(bar);\nbaz(qux);\n}";
+        assert_eq!(classify(unknown).meta["language"], "unknown");
+    }
+
+    #[test]
+    fn falls_back_to_prose() {
+        for text in [
+            "Let me know if that works for you.",
+            "deadbeef",
+            "1234567",
+            "#12345",
+            "rgb(10, 20)",
+            "{not json}",
+            "/",
+            "Meeting at 12:30, room 4",
+            "Go home\nand rest",
+        ] {
+            assert_eq!(kind(text), "prose", "{}", text);
+        }
+        assert_eq!(classify("two words").meta["words"], "2");
+    }
+}
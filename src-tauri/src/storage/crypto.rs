@@ -0,0 +1,183 @@
+use crate::storage::safe_file;
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Header written in front of every encrypted blob, followed by the nonce.
+const MAGIC: &[u8] = b"CBENC1";
+const NONCE_LEN: usize = 24;
+const SALT_LEN: usize = 16;
+const CHECK_PLAINTEXT: &[u8] = b"cli-buddy-vault";
+
+// The derived key is held for the lifetime of the process once unlocked,
+// so every FileManager/TextHistory instance can use it without re-prompting.
+static SESSION_KEY: Mutex<Option<Key>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    salt: String,
+    check: String,
+}
+
+fn vault_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cli-buddy")
+        .join("vault.json")
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn session_key() -> Result<Key, String> {
+    SESSION_KEY
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .ok_or_else(|| "Encryption is locked".to_string())
+}
+
+fn encrypt_with(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_with(key: &Key, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_encrypted(data) || data.len() < MAGIC.len() + NONCE_LEN {
+        return Err("Not an encrypted blob".to_string());
+    }
+    let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed: wrong passphrase or corrupted data".to_string())
+}
+
+pub fn is_configured() -> bool {
+    vault_path().exists()
+}
+
+pub fn is_unlocked() -> bool {
+    SESSION_KEY.lock().map(|k| k.is_some()).unwrap_or(false)
+}
+
+/// Create the vault for a new passphrase and unlock it for this session.
+pub fn setup(passphrase: &str) -> Result<(), String> {
+    if is_configured() {
+        return Err("Encryption is already set up".to_string());
+    }
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let vault = VaultFile {
+        salt: b64().encode(salt),
+        check: b64().encode(encrypt_with(&key, CHECK_PLAINTEXT)?),
+    };
+
+    let content = serde_json::to_string_pretty(&vault).map_err(|e| e.to_string())?;
+    safe_file::write_atomic(&vault_path(), content.as_bytes())
+        .map_err(|e| format!("Failed to save vault: {}", e))?;
+
+    *SESSION_KEY.lock().map_err(|e| format!("Lock error: {}", e))? = Some(key);
+    Ok(())
+}
+
+pub fn unlock(passphrase: &str) -> Result<(), String> {
+    let content = fs::read_to_string(vault_path())
+        .map_err(|_| "Encryption has not been set up".to_string())?;
+    let vault: VaultFile =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid vault file: {}", e))?;
+    let salt = b64().decode(&vault.salt).map_err(|e| e.to_string())?;
+    let check = b64().decode(&vault.check).map_err(|e| e.to_string())?;
+
+    let key = derive_key(passphrase, &salt)?;
+    if decrypt_with(&key, &check).ok().as_deref() != Some(CHECK_PLAINTEXT) {
+        return Err("Wrong passphrase".to_string());
+    }
+
+    *SESSION_KEY.lock().map_err(|e| format!("Lock error: {}", e))? = Some(key);
+    Ok(())
+}
+
+pub fn lock() {
+    if let Ok(mut key) = SESSION_KEY.lock() {
+        *key = None;
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Check a file for the encryption header without reading all of it.
+pub fn is_encrypted_file(path: &Path) -> bool {
+    let mut header = [0u8; MAGIC.len()];
+    fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map(|_| header == MAGIC)
+        .unwrap_or(false)
+}
+
+pub fn encrypt(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    encrypt_with(&session_key()?, plaintext)
+}
+
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    decrypt_with(&session_key()?, data)
+}
+
+/// Decrypt `data` if it carries the encryption header, otherwise return it unchanged.
+pub fn decrypt_if_needed(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if is_encrypted(&data) {
+        decrypt(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+pub fn encrypt_string(plaintext: &str) -> Result<String, String> {
+    Ok(b64().encode(encrypt(plaintext.as_bytes())?))
+}
+
+pub fn decrypt_string(encoded: &str) -> Result<String, String> {
+    let data = b64().decode(encoded).map_err(|e| e.to_string())?;
+    String::from_utf8(decrypt(&data)?).map_err(|e| e.to_string())
+}
+
+/// Hash keyed with the session key, so stored hashes cannot be used to
+/// confirm guesses of encrypted content.
+pub fn keyed_hash(data: &[u8]) -> Result<String, String> {
+    let key = session_key()?;
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(data);
+    Ok(format!("{:x}", hasher.finalize()))
+}
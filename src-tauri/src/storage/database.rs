@@ -0,0 +1,421 @@
+use crate::storage::safe_file;
+use crate::storage::text_history::TextEntry;
+use log::{info, warn};
+use rusqlite::{params, Connection, ErrorCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// `search_index` is a single FTS5 table covering both text content and
+// screenshot filenames so results from both can be ranked together.
+// The trigram tokenizer gives substring matches, which works for Korean
+// text, paths and identifiers where word tokenizers fall short.
+const SCHEMA_V1: &str = "
+CREATE TABLE IF NOT EXISTS text_entries (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    preview TEXT NOT NULL,
+    hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    char_count INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_text_entries_id ON text_entries(id);
+
+CREATE TABLE IF NOT EXISTS screenshots (
+    filename TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+    kind UNINDEXED,
+    ref_id UNINDEXED,
+    created_at UNINDEXED,
+    body,
+    tokenize = 'trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS text_entries_ai AFTER INSERT ON text_entries BEGIN
+    INSERT INTO search_index(kind, ref_id, created_at, body)
+    VALUES ('text', CAST(new.seq AS TEXT), new.created_at, new.content);
+END;
+CREATE TRIGGER IF NOT EXISTS text_entries_ad AFTER DELETE ON text_entries BEGIN
+    DELETE FROM search_index WHERE kind = 'text' AND ref_id = CAST(old.seq AS TEXT);
+END;
+
+CREATE TRIGGER IF NOT EXISTS screenshots_ai AFTER INSERT ON screenshots BEGIN
+    INSERT INTO search_index(kind, ref_id, created_at, body)
+    VALUES ('image', new.filename, new.created_at, new.filename);
+END;
+CREATE TRIGGER IF NOT EXISTS screenshots_ad AFTER DELETE ON screenshots BEGIN
+    DELETE FROM search_index WHERE kind = 'image' AND ref_id = old.filename;
+END;
+";
+
+const MIGRATION_V2: &str = "
+ALTER TABLE text_entries ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+";
+
+// Encrypted rows keep ciphertext in `content`/`preview` and stay out of the
+// full-text index so no plaintext reaches disk.
+const MIGRATION_V3: &str = "
+ALTER TABLE text_entries ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0;
+DROP TRIGGER IF EXISTS text_entries_ai;
+CREATE TRIGGER text_entries_ai AFTER INSERT ON text_entries WHEN new.encrypted = 0 BEGIN
+    INSERT INTO search_index(kind, ref_id, created_at, body)
+    VALUES ('text', CAST(new.seq AS TEXT), new.created_at, new.content);
+END;
+";
+
+// `expires_at` is a unix timestamp so it compares correctly across timezones.
+const MIGRATION_V4: &str = "
+ALTER TABLE text_entries ADD COLUMN sensitive INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE text_entries ADD COLUMN expires_at INTEGER;
+CREATE INDEX IF NOT EXISTS idx_text_entries_expires_at ON text_entries(expires_at);
+";
+
+// Large clips keep only their beginning in `content`; `blob` names the full text
+const MIGRATION_V5: &str = "
+ALTER TABLE text_entries ADD COLUMN blob TEXT;
+";
+
+// NULL kind marks rows captured before classification existed
+const MIGRATION_V6: &str = "
+ALTER TABLE text_entries ADD COLUMN kind TEXT;
+ALTER TABLE text_entries ADD COLUMN kind_meta TEXT;
+CREATE INDEX IF NOT EXISTS idx_text_entries_kind ON text_entries(kind);
+";
+
+// Re-copied or pasted entries move to the top by taking a new seq
+const MIGRATION_V7: &str = "
+ALTER TABLE text_entries ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE text_entries ADD COLUMN last_used_at TEXT;
+ALTER TABLE screenshots ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE screenshots ADD COLUMN last_used_at TEXT;
+
+CREATE TRIGGER IF NOT EXISTS text_entries_au_seq AFTER UPDATE OF seq ON text_entries BEGIN
+    UPDATE search_index SET ref_id = CAST(new.seq AS TEXT)
+    WHERE kind = 'text' AND ref_id = CAST(old.seq AS TEXT);
+END;
+";
+
+// Entries captured as HTML or a file list; `content` holds the plain-text form
+const MIGRATION_V8: &str = "
+ALTER TABLE text_entries ADD COLUMN format TEXT NOT NULL DEFAULT 'text';
+ALTER TABLE text_entries ADD COLUMN html TEXT;
+";
+
+// Screenshot captured in the same clipboard change as an entry's text
+const MIGRATION_V9: &str = "
+ALTER TABLE text_entries ADD COLUMN screenshot TEXT;
+";
+
+// 64-bit dHash of the image, stored as its signed bit pattern; NULL until computed
+const MIGRATION_V10: &str = "
+ALTER TABLE screenshots ADD COLUMN phash INTEGER;
+";
+
+// Ids used to have one-second resolution; rename repeats so each is unique
+const MIGRATION_V11: &str = "
+UPDATE text_entries SET id = id || '_' || seq
+WHERE seq NOT IN (SELECT MIN(seq) FROM text_entries GROUP BY id);
+DROP INDEX IF EXISTS idx_text_entries_id;
+CREATE UNIQUE INDEX idx_text_entries_id ON text_entries(id);
+";
+
+// Deleted entries are kept here, with all their columns, until the trash is
+// emptied; `batch` groups those deleted together
+const MIGRATION_V12: &str = "
+CREATE TABLE trash_text_entries AS SELECT * FROM text_entries WHERE 0;
+ALTER TABLE trash_text_entries ADD COLUMN batch INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE trash_text_entries ADD COLUMN reason TEXT NOT NULL DEFAULT 'deleted';
+CREATE INDEX idx_trash_text_entries_batch ON trash_text_entries(batch);
+";
+
+// `pixel_hash` of the image as captured, before any lossy encoding or scaling;
+// NULL for files added to the folder directly
+const MIGRATION_V13: &str = "
+ALTER TABLE screenshots ADD COLUMN pixel_hash TEXT;
+";
+
+/// Schema steps in order; each runs in its own transaction together with the
+/// `user_version` bump, so an interrupted upgrade leaves the previous version intact.
+const MIGRATIONS: &[(i32, &str)] = &[
+    (1, SCHEMA_V1),
+    (2, MIGRATION_V2),
+    (3, MIGRATION_V3),
+    (4, MIGRATION_V4),
+    (5, MIGRATION_V5),
+    (6, MIGRATION_V6),
+    (7, MIGRATION_V7),
+    (8, MIGRATION_V8),
+    (9, MIGRATION_V9),
+    (10, MIGRATION_V10),
+    (11, MIGRATION_V11),
+    (12, MIGRATION_V12),
+    (13, MIGRATION_V13),
+];
+
+/// Open (or create) the history database. A file SQLite reports as corrupt is
+/// moved aside and replaced with an empty database.
+pub fn open(path: &Path) -> Result<Connection, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    match open_and_migrate(path) {
+        Ok(conn) => Ok(conn),
+        Err(e) if is_corruption(&e) => {
+            let backup = safe_file::backup_corrupt(path)?;
+            warn!("History database is corrupt ({}); moved to {}", e, backup.display());
+            for suffix in ["-wal", "-shm"] {
+                let mut name = path.as_os_str().to_owned();
+                name.push(suffix);
+                let _ = fs::remove_file(name);
+            }
+            open_and_migrate(path).map_err(|e| format!("Failed to open database: {}", e))
+        }
+        Err(e) => Err(format!("Failed to open database: {}", e)),
+    }
+}
+
+fn open_and_migrate(path: &Path) -> rusqlite::Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    // Overwrite freed pages so deleted or re-encrypted content does not linger
+    conn.execute_batch("PRAGMA secure_delete = ON")?;
+
+    let check: String = conn.query_row("PRAGMA quick_check(1)", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+            Some(check),
+        ));
+    }
+
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn is_corruption(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt) | Some(ErrorCode::NotADatabase)
+    )
+}
+
+pub fn open_in_memory() -> Connection {
+    let mut conn = Connection::open_in_memory().expect("in-memory database");
+    if let Err(e) = migrate(&mut conn) {
+        warn!("Failed to initialize in-memory database: {}", e);
+    }
+    conn
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    let latest = MIGRATIONS.last().map(|(v, _)| *v).unwrap_or(0);
+    if version > latest {
+        warn!(
+            "History database schema v{} is newer than this build (v{})",
+            version, latest
+        );
+        return Ok(());
+    }
+
+    for (target, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", target)?;
+        tx.commit()?;
+        info!("Migrated history database to schema v{}", target);
+    }
+    Ok(())
+}
+
+/// Import the pre-database `clipboard_history.json` once, then rename it so
+/// it is not imported again.
+pub fn import_legacy_json(conn: &mut Connection, json_path: &Path) {
+    if !json_path.exists() {
+        return;
+    }
+
+    let entries = match fs::read_to_string(json_path)
+        .map_err(|e| e.to_string())
+        .and_then(|c| serde_json::from_str::<Vec<TextEntry>>(&c).map_err(|e| e.to_string()))
+    {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Skipping legacy history import: {}", e);
+            return;
+        }
+    };
+
+    let result = (|| -> rusqlite::Result<usize> {
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO text_entries (id, content, preview, hash, created_at, char_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            // Ids there have one-second resolution, so repeats get a suffix
+            let mut seen: HashMap<&str, u32> = HashMap::new();
+            // JSON history is newest first; insert oldest first so `seq` keeps the order
+            for e in entries.iter().rev() {
+                let n = seen.entry(e.id.as_str()).or_insert(0);
+                *n += 1;
+                let id = match *n {
+                    1 => e.id.clone(),
+                    n => format!("{}_{}", e.id, n),
+                };
+                imported += stmt.execute(params![
+                    id,
+                    e.content,
+                    e.preview,
+                    e.hash,
+                    e.created_at,
+                    e.char_count as i64
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    })();
+
+    match result {
+        Ok(count) => {
+            info!("Imported {} entries from legacy clipboard history", count);
+            let _ = fs::rename(json_path, json_path.with_extension("json.imported"));
+        }
+        Err(e) => warn!("Legacy history import failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_ids_matching(conn: &Connection, query: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.id FROM search_index s
+                 JOIN text_entries t ON s.ref_id = CAST(t.seq AS TEXT)
+                 WHERE s.kind = 'text' AND search_index MATCH ?1
+                 ORDER BY t.seq",
+            )
+            .unwrap();
+        let rows = stmt.query_map(params![query], |row| row.get(0)).unwrap();
+        rows.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn migrates_v1_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        for (id, content) in [
+            ("text_1", "alpha one"),
+            ("text_1", "alpha two"),
+            ("text_2", "beta"),
+        ] {
+            conn.execute(
+                "INSERT INTO text_entries (id, content, preview, hash, created_at, char_count)
+                 VALUES (?1, ?2, ?2, ?2, '2024-01-01T00:00:00+0900', 9)",
+                params![id, content],
+            )
+            .unwrap();
+        }
+
+        migrate(&mut conn).unwrap();
+        let version: i32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.last().unwrap().0);
+
+        // The first of each id keeps it and later ones are renamed
+        assert_eq!(text_ids_matching(&conn, "alpha"), ["text_1", "text_1_2"]);
+        let duplicate = conn.execute(
+            "INSERT INTO text_entries (id, content, preview, hash, created_at, char_count)
+             VALUES ('text_2', 'gamma', 'gamma', 'gamma', '2024-01-01T00:00:00+0900', 5)",
+            [],
+        );
+        assert!(duplicate.is_err());
+
+        // Moving an entry to the top keeps it searchable
+        conn.execute("UPDATE text_entries SET seq = 10 WHERE id = 'text_1'", [])
+            .unwrap();
+        assert_eq!(text_ids_matching(&conn, "alpha"), ["text_1_2", "text_1"]);
+
+        // Encrypted rows stay out of the index, deleted ones leave it
+        conn.execute(
+            "INSERT INTO text_entries (id, content, preview, hash, created_at, char_count, encrypted)
+             VALUES ('text_3', 'alpha secret', '', 'h3', '2024-01-01T00:00:00+0900', 12, 1)",
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM text_entries WHERE id = 'text_1_2'", [])
+            .unwrap();
+        assert_eq!(text_ids_matching(&conn, "alpha"), ["text_1"]);
+        let indexed: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM search_index WHERE kind = 'text'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(indexed, 2);
+    }
+
+    #[test]
+    fn imports_legacy_json_once() {
+        let dir = std::env::temp_dir().join(format!("cli-buddy-legacy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("clipboard_history.json");
+        // Newest first, with a repeated one-second id
+        let json = r#"[
+            {"id": "text_5", "content": "newest", "preview": "newest", "hash": "h3",
+             "created_at": "2024-01-01T00:00:05+0900", "char_count": 6},
+            {"id": "text_5", "content": "middle", "preview": "middle", "hash": "h2",
+             "created_at": "2024-01-01T00:00:05+0900", "char_count": 6},
+            {"id": "text_1", "content": "oldest", "preview": "oldest", "hash": "h1",
+             "created_at": "2024-01-01T00:00:01+0900", "char_count": 6}
+        ]"#;
+        fs::write(&json_path, json).unwrap();
+
+        let mut conn = open_in_memory();
+        import_legacy_json(&mut conn, &json_path);
+        let rows: Vec<(String, String)> = {
+            let mut stmt = conn
+                .prepare("SELECT id, content FROM text_entries ORDER BY seq")
+                .unwrap();
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap();
+            rows.collect::<rusqlite::Result<_>>().unwrap()
+        };
+        let expected = [
+            ("text_1", "oldest"),
+            ("text_5", "middle"),
+            ("text_5_2", "newest"),
+        ];
+        assert_eq!(
+            rows,
+            expected.map(|(id, content)| (id.to_string(), content.to_string()))
+        );
+        assert_eq!(text_ids_matching(&conn, "middle"), ["text_5"]);
+        assert!(!json_path.exists());
+        assert!(json_path.with_extension("json.imported").exists());
+
+        // Already renamed, so nothing is imported twice
+        import_legacy_json(&mut conn, &json_path);
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM text_entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
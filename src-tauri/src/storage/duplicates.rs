@@ -0,0 +1,97 @@
+use crate::storage::file_manager::ScreenshotInfo;
+use crate::storage::hash::{dhash, hash_distance};
+use crate::storage::text_history::TextHistory;
+use crate::storage::{crypto, image_codec};
+use log::warn;
+use std::collections::HashMap;
+
+/// Filename of a stored screenshot whose hash is within `threshold` bits of `phash`.
+pub fn find_near_duplicate(
+    hashes: &HashMap<String, u64>,
+    phash: u64,
+    threshold: u32,
+) -> Option<&str> {
+    hashes
+        .iter()
+        .find(|(_, &h)| hash_distance(h, phash) <= threshold)
+        .map(|(name, _)| name.as_str())
+}
+
+/// Perceptual hashes of `screenshots`, computing and storing the ones captured
+/// before hashing existed. Encrypted screenshots are skipped while locked.
+pub fn fill_missing_hashes(
+    history: &mut TextHistory,
+    screenshots: &[ScreenshotInfo],
+) -> HashMap<String, u64> {
+    history.sync_screenshots(screenshots);
+    let mut hashes = history.screenshot_phashes();
+    for info in screenshots {
+        if hashes.contains_key(&info.filename) {
+            continue;
+        }
+        let Ok(raw) = std::fs::read(&info.path) else {
+            continue;
+        };
+        if crypto::is_encrypted(&raw) && !crypto::is_unlocked() {
+            continue;
+        }
+        let image = crypto::decrypt_if_needed(raw).and_then(|data| image_codec::decode(&data));
+        match image {
+            Ok(image) => {
+                let phash = dhash(&image);
+                history.set_screenshot_phash(&info.filename, phash);
+                hashes.insert(info.filename.clone(), phash);
+            }
+            Err(e) => warn!("Cannot hash {}: {}", info.filename, e),
+        }
+    }
+    hashes
+}
+
+/// Sets of screenshots within `threshold` bits of each other, chained so that
+/// A~B and B~C put all three together. Order follows `screenshots`; those
+/// without a near-duplicate are left out.
+pub fn group(
+    screenshots: &[ScreenshotInfo],
+    hashes: &HashMap<String, u64>,
+    threshold: u32,
+) -> Vec<Vec<ScreenshotInfo>> {
+    let hashed: Vec<(&ScreenshotInfo, u64)> = screenshots
+        .iter()
+        .filter_map(|s| hashes.get(&s.filename).map(|&h| (s, h)))
+        .collect();
+
+    // Union-find over positions in `hashed`
+    let mut parent: Vec<usize> = (0..hashed.len()).collect();
+    for i in 0..hashed.len() {
+        for j in i + 1..hashed.len() {
+            if hash_distance(hashed[i].1, hashed[j].1) <= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                if a != b {
+                    parent[b.max(a)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<ScreenshotInfo>> = Vec::new();
+    let mut group_of: HashMap<usize, usize> = HashMap::new();
+    for (i, (info, _)) in hashed.iter().enumerate() {
+        let r = root(&mut parent, i);
+        let g = *group_of.entry(r).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[g].push((*info).clone());
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
@@ -1,32 +1,77 @@
 use crate::config::AppSettings;
-use base64::Engine;
+use crate::storage::hash::sha256_hash;
+use crate::storage::image_codec::{self, EncodeOptions};
+use crate::storage::naming::{self, NameContext};
+use crate::storage::trash::{self, TrashedScreenshot, REASON_RETENTION, REASON_SIZE_LIMIT};
+use crate::storage::{crypto, safe_file};
 use chrono::Local;
 use image::imageops::FilterType;
-use image::ImageFormat;
+use image::DynamicImage;
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScreenshotInfo {
     pub filename: String,
     pub path: String,
-    pub thumbnail: String,
     pub created_at: String,
     pub size_bytes: u64,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Usage is tracked in the history database and filled in by the commands
+    #[serde(default)]
+    pub use_count: u32,
+    #[serde(default)]
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionResult {
+    pub removed: u32,
+    /// Freed on disk; screenshots moved to the trash still take up space
+    pub bytes_freed: u64,
+    /// Trashed screenshots deleted to get under the size limit
+    pub trash_purged: u32,
+}
+
+/// SHA-256 of a screenshot file's contents, with the size and modification
+/// time it was read at.
+struct CachedHash {
+    hash: String,
+    size_bytes: u64,
+    created_at: String,
 }
 
 pub struct FileManager {
     save_dir: PathBuf,
     thumbnail_size: u32,
+    encrypt: bool,
+    encode: EncodeOptions,
+    filename_template: String,
+    subfolders: String,
 }
 
+/// Names tried for one screenshot before giving up.
+const MAX_NAME_ATTEMPTS: u32 = 10_000;
+/// Share of the unpinned library, by size, that one pass may evict to get
+/// under the size limit
+const MAX_SIZE_EVICTION_PERCENT: u64 = 50;
+
 impl FileManager {
     pub fn new(settings: &AppSettings) -> Self {
         let save_dir = PathBuf::from(&settings.save_directory);
         Self {
             save_dir,
             thumbnail_size: settings.thumbnail_size,
+            encrypt: settings.encrypt_at_rest,
+            encode: EncodeOptions::from_settings(settings),
+            filename_template: settings.filename_template.clone(),
+            subfolders: settings.screenshot_subfolders.clone(),
         }
     }
 
@@ -36,154 +81,878 @@ impl FileManager {
         Ok(())
     }
 
+    pub fn save_dir(&self) -> &Path {
+        &self.save_dir
+    }
+
     fn thumbnails_dir(&self) -> PathBuf {
         self.save_dir.join(".thumbnails")
     }
 
-    pub fn save_screenshot(&self, png_data: &[u8]) -> Result<ScreenshotInfo, String> {
-        self.ensure_directories()?;
+    fn pins_path(&self) -> PathBuf {
+        self.save_dir.join(".pinned.json")
+    }
+
+    fn trash_dir(&self) -> PathBuf {
+        self.save_dir.join(".trash")
+    }
 
-        let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-        let filename = format!("screenshot_{}.png", timestamp);
-        let filepath = self.save_dir.join(&filename);
+    fn trash_manifest_path(&self) -> PathBuf {
+        self.trash_dir().join("trash.json")
+    }
 
-        // Save full image
-        fs::write(&filepath, png_data).map_err(|e| format!("Failed to save screenshot: {}", e))?;
+    /// Where a screenshot deleted in `batch` is kept: `.trash/<batch>/<filename>`.
+    fn trashed_path(&self, batch: i64, filename: &str) -> PathBuf {
+        Self::under(&self.trash_dir().join(batch.to_string()), filename)
+    }
 
-        // Update latest.png
-        let latest_path = self.save_dir.join("latest.png");
-        let _ = fs::copy(&filepath, &latest_path);
+    /// `base` joined with a screenshot name that uses `/` between folders.
+    fn under(base: &Path, filename: &str) -> PathBuf {
+        filename
+            .split('/')
+            .fold(base.to_path_buf(), |path, part| path.join(part))
+    }
 
-        // Generate thumbnail
-        let thumbnail = self.generate_thumbnail(png_data, &filename)?;
+    fn path_of(&self, filename: &str) -> PathBuf {
+        Self::under(&self.save_dir, filename)
+    }
 
-        let size_bytes = png_data.len() as u64;
-        let created_at = Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string();
+    fn thumbnail_path(&self, filename: &str) -> PathBuf {
+        Self::under(&self.thumbnails_dir(), filename)
+    }
+
+    fn load_pins(&self) -> HashSet<String> {
+        let path = self.pins_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return HashSet::new();
+        };
+        match serde_json::from_str::<Vec<String>>(&content) {
+            Ok(names) => names.into_iter().collect(),
+            Err(e) => {
+                // Keep the unreadable file around instead of dropping every pin silently
+                match safe_file::backup_corrupt(&path) {
+                    Ok(backup) => warn!("Pin list is corrupt ({}); moved to {}", e, backup.display()),
+                    Err(backup_err) => warn!("Pin list is corrupt ({}): {}", e, backup_err),
+                }
+                HashSet::new()
+            }
+        }
+    }
+
+    fn save_pins(&self, pins: &HashSet<String>) -> Result<(), String> {
+        let mut names: Vec<&String> = pins.iter().collect();
+        names.sort();
+        let content = serde_json::to_string_pretty(&names).map_err(|e| e.to_string())?;
+        safe_file::write_atomic(&self.pins_path(), content.as_bytes())
+            .map_err(|e| format!("Failed to save pins: {}", e))
+    }
+
+    /// Encrypt `data` for storage when encryption at rest is enabled.
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if self.encrypt {
+            crypto::encrypt(data)
+        } else {
+            Ok(data.to_vec())
+        }
+    }
+
+    pub fn set_pinned(&self, filename: &str, pinned: bool) -> Result<bool, String> {
+        if !self.path_of(filename).exists() {
+            return Err(format!("Screenshot not found: {}", filename));
+        }
+        let mut pins = self.load_pins();
+        let changed = if pinned {
+            pins.insert(filename.to_string())
+        } else {
+            pins.remove(filename)
+        };
+        if changed {
+            self.save_pins(&pins)?;
+        }
+        Ok(changed)
+    }
+
+    /// Encode and store a captured image in the configured format, named by
+    /// the filename template.
+    pub fn save_screenshot(&self, image: &DynamicImage) -> Result<ScreenshotInfo, String> {
+        self.ensure_directories()?;
+
+        let now = Local::now();
+        let extension = self.encode.format.extension();
+        let encoded = image_codec::encode(image, &self.encode)?;
+        let hash = sha256_hash(&encoded);
+        // Finding the project reads the session folders, so only when asked for
+        let project = if self.filename_template.contains("{project}") {
+            crate::commands::system::active_project()
+        } else {
+            None
+        };
+        let ctx = NameContext {
+            now,
+            hash: &hash,
+            project: project.as_deref(),
+        };
+        // Sealed before a name is reserved, so a locked store leaves nothing behind
+        let stored = self.seal(&encoded)?;
+        let folder = naming::subfolder(&self.subfolders, &now);
+        let filename = self.reserve(
+            folder.as_deref(),
+            extension,
+            naming::uses_seq(&self.filename_template),
+            |seq| naming::render(&self.filename_template, &ctx, seq),
+        )?;
+        let filepath = self.path_of(&filename);
+
+        // Save full image over the reserved placeholder, which is removed
+        // again if anything fails
+        let saved = (|| -> Result<(), String> {
+            safe_file::write_atomic(&filepath, &stored)
+                .map_err(|e| format!("Failed to save screenshot: {}", e))?;
+
+            // Update latest.<ext>, dropping one left in another format
+            for ext in image_codec::EXTENSIONS.iter().filter(|&&e| e != extension) {
+                let _ = fs::remove_file(self.save_dir.join(format!("latest.{}", ext)));
+            }
+            let latest_path = self.save_dir.join(format!("latest.{}", extension));
+            let _ = safe_file::write_atomic(&latest_path, &stored);
+
+            // Generate thumbnail
+            self.generate_thumbnail(image, &filename)?;
+            Ok(())
+        })();
+        if let Err(e) = saved {
+            let _ = fs::remove_file(&filepath);
+            Self::remove_empty_parents(&filepath, &self.save_dir);
+            return Err(e);
+        }
+
+        let size_bytes = stored.len() as u64;
+        let created_at = now.format("%Y-%m-%dT%H:%M:%S%z").to_string();
 
         Ok(ScreenshotInfo {
             filename,
             path: filepath.to_string_lossy().to_string(),
-            thumbnail,
             created_at,
             size_bytes,
+            pinned: false,
+            use_count: 0,
+            last_used_at: None,
         })
     }
 
-    fn generate_thumbnail(&self, png_data: &[u8], filename: &str) -> Result<String, String> {
-        let img =
-            image::load_from_memory(png_data).map_err(|e| format!("Failed to load image: {}", e))?;
+    /// Claim a free filename in `folder` by creating it empty, so two saves
+    /// in the same second cannot overwrite each other. `stem(n)` names attempt
+    /// `n`, counting from 1; unless `numbered`, later attempts append `_n`.
+    fn reserve(
+        &self,
+        folder: Option<&str>,
+        extension: &str,
+        numbered: bool,
+        stem: impl Fn(u32) -> String,
+    ) -> Result<String, String> {
+        for n in 1..=MAX_NAME_ATTEMPTS {
+            let stem = match n {
+                1 => stem(1),
+                _ if numbered => stem(n),
+                _ => format!("{}_{}", stem(1), n),
+            };
+            let filename = match folder {
+                Some(folder) => format!("{}/{}.{}", folder, stem, extension),
+                None => format!("{}.{}", stem, extension),
+            };
+            // e.g. a template that renders to `latest`
+            if !Self::is_screenshot_name(&filename) {
+                continue;
+            }
+            let path = self.path_of(&filename);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(filename),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+            }
+        }
+        Err(format!("No free filename for {}", stem(1)))
+    }
+
+    /// Replace a stored screenshot with `data`, already encoded, renaming it
+    /// when the extension changes. Pins, the thumbnail and the modification
+    /// time follow the file. Returns the new filename.
+    pub fn replace_screenshot(
+        &self,
+        filename: &str,
+        data: &[u8],
+        extension: &str,
+    ) -> Result<String, String> {
+        let old_path = self.resolve(filename)?;
+        let modified = fs::metadata(&old_path).and_then(|m| m.modified()).ok();
+        let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+        let new_name = if filename.ends_with(&format!(".{}", extension)) {
+            filename.to_string()
+        } else {
+            let (folder, stem) = match stem.rsplit_once('/') {
+                Some((folder, stem)) => (Some(folder), stem),
+                None => (None, stem),
+            };
+            self.reserve(folder, extension, false, |_| stem.to_string())?
+        };
+
+        let new_path = self.path_of(&new_name);
+        // Keep the original's encryption state rather than the current setting
+        let stored = if crypto::is_encrypted_file(&old_path) {
+            crypto::encrypt(data)?
+        } else {
+            data.to_vec()
+        };
+        if let Err(e) = safe_file::write_atomic(&new_path, &stored) {
+            if new_name != filename {
+                let _ = fs::remove_file(&new_path);
+            }
+            return Err(format!("Failed to save screenshot: {}", e));
+        }
+        // Listings are ordered by modification time
+        if let Some(modified) = modified {
+            let _ = fs::File::options()
+                .write(true)
+                .open(&new_path)
+                .and_then(|f| f.set_modified(modified));
+        }
+        if new_name == filename {
+            return Ok(new_name);
+        }
 
-        let thumbnail = img.resize(
+        fs::remove_file(&old_path).map_err(|e| e.to_string())?;
+        let old_thumb = self.thumbnail_path(filename);
+        if old_thumb.exists() {
+            let _ = fs::rename(&old_thumb, self.thumbnail_path(&new_name));
+        }
+        let mut pins = self.load_pins();
+        if pins.remove(filename) {
+            pins.insert(new_name.clone());
+            self.save_pins(&pins)?;
+        }
+        Ok(new_name)
+    }
+
+    fn generate_thumbnail(&self, image: &DynamicImage, filename: &str) -> Result<Vec<u8>, String> {
+        let thumbnail = image.resize(
             self.thumbnail_size,
             self.thumbnail_size,
             FilterType::Lanczos3,
         );
+        let buf = image_codec::encode_png(&thumbnail, false)?;
 
         // Save thumbnail file
-        let thumb_path = self.thumbnails_dir().join(filename);
-        thumbnail
-            .save_with_format(&thumb_path, ImageFormat::Png)
+        let thumb_path = self.thumbnail_path(filename);
+        safe_file::write_atomic(&thumb_path, &self.seal(&buf)?)
             .map_err(|e| format!("Failed to save thumbnail: {}", e))?;
 
-        // Return base64 encoded thumbnail
-        let mut buf = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut buf);
-        thumbnail
-            .write_to(&mut cursor, ImageFormat::Png)
-            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+        Ok(buf)
+    }
+
+    /// Whether `name`, relative to the save directory with `/` between
+    /// folders, is a screenshot file this store manages: an image outside
+    /// hidden folders, other than `latest.<ext>`.
+    pub fn is_screenshot_name(name: &str) -> bool {
+        let parts: Vec<&str> = name.split('/').collect();
+        let Some((file, folders)) = parts.split_last() else {
+            return false;
+        };
+        if name.contains('\\') || parts.iter().any(|p| p.is_empty() || p.starts_with('.')) {
+            return false;
+        }
+        match file.rsplit_once('.') {
+            Some((stem, ext)) => {
+                image_codec::EXTENSIONS.contains(&ext) && !(folders.is_empty() && stem == "latest")
+            }
+            None => false,
+        }
+    }
+
+    /// Path of a screenshot, refusing anything that would resolve outside
+    /// the save directory.
+    pub fn resolve(&self, filename: &str) -> Result<PathBuf, String> {
+        if !Self::is_screenshot_name(filename) {
+            return Err(format!("Invalid screenshot name: {}", filename));
+        }
+        let path = self
+            .path_of(filename)
+            .canonicalize()
+            .map_err(|_| format!("Screenshot not found: {}", filename))?;
+        let root = self.save_dir.canonicalize().map_err(|e| e.to_string())?;
+        // A symlink in the store may still point elsewhere
+        if !path.starts_with(&root) || !path.is_file() {
+            return Err(format!("Screenshot not found: {}", filename));
+        }
+        Ok(path)
+    }
 
-        let b64 = base64::engine::general_purpose::STANDARD.encode(&buf);
-        Ok(format!("data:image/png;base64,{}", b64))
+    /// Name of the screenshot at `path`, relative to the save directory as
+    /// `resolve` takes it, e.g. `2024-03-09/x.png`. None for files outside it.
+    pub fn filename_of(&self, path: &Path) -> Option<String> {
+        let root = self.save_dir.canonicalize().ok()?;
+        let path = path.canonicalize().ok()?;
+        let parts = path
+            .strip_prefix(&root)
+            .ok()?
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        let filename = parts.join("/");
+        Self::is_screenshot_name(&filename).then_some(filename)
     }
 
-    pub fn get_screenshot_list(&self) -> Result<Vec<ScreenshotInfo>, String> {
+    /// List screenshots in the save directory and its subfolders, newest
+    /// first, without loading thumbnails.
+    pub fn scan_screenshots(&self) -> Result<Vec<ScreenshotInfo>, String> {
         if !self.save_dir.exists() {
             return Ok(Vec::new());
         }
 
         let mut screenshots: Vec<ScreenshotInfo> = Vec::new();
+        let pins = self.load_pins();
+        self.scan_dir(&self.save_dir, "", &pins, &mut screenshots)?;
+
+        // Newest first; names break ties within the same second
+        screenshots.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.filename.cmp(&a.filename))
+        });
+
+        Ok(screenshots)
+    }
 
-        let entries =
-            fs::read_dir(&self.save_dir).map_err(|e| format!("Failed to read dir: {}", e))?;
+    /// Collect screenshots under `dir`, named `prefix/<name>`. Hidden folders
+    /// such as `.thumbnails` are skipped, and symlinked folders not followed.
+    fn scan_dir(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        pins: &HashSet<String>,
+        screenshots: &mut Vec<ScreenshotInfo>,
+    ) -> Result<(), String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read dir: {}", e))?;
 
         for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with("screenshot_") && name.ends_with(".png") {
-                    let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
-                    let thumbnail = self.get_or_create_thumbnail(&path, name)?;
-
-                    let created_at = metadata
-                        .created()
-                        .or_else(|_| metadata.modified())
-                        .map(|t| {
-                            let datetime: chrono::DateTime<Local> = t.into();
-                            datetime.format("%Y-%m-%dT%H:%M:%S%z").to_string()
-                        })
-                        .unwrap_or_default();
-
-                    screenshots.push(ScreenshotInfo {
-                        filename: name.to_string(),
-                        path: path.to_string_lossy().to_string(),
-                        thumbnail,
-                        created_at,
-                        size_bytes: metadata.len(),
-                    });
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+            let filename = if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            };
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                self.scan_dir(&entry.path(), &filename, pins, screenshots)?;
+            } else if Self::is_screenshot_name(&filename) {
+                let mut info = Self::read_info(&entry.path(), &filename)?;
+                info.pinned = pins.contains(&filename);
+                screenshots.push(info);
+            }
+        }
+        Ok(())
+    }
+
+    /// Subfolders of the save directory, which listings rescan when any of
+    /// them changes.
+    pub fn folders(&self) -> Vec<PathBuf> {
+        let mut folders = vec![self.save_dir.clone()];
+        let mut i = 0;
+        while i < folders.len() {
+            if let Ok(entries) = fs::read_dir(&folders[i]) {
+                for entry in entries.flatten() {
+                    let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+                        folders.push(entry.path());
+                    }
                 }
             }
+            i += 1;
         }
+        folders
+    }
 
-        // Sort by filename descending (newest first)
-        screenshots.sort_by(|a, b| b.filename.cmp(&a.filename));
+    /// Delete the thumbnail of a screenshot changed outside the app, so it is
+    /// made again from the new contents.
+    pub fn remove_thumbnail(&self, filename: &str) {
+        let thumb_path = self.thumbnail_path(filename);
+        if fs::remove_file(&thumb_path).is_ok() {
+            Self::remove_empty_parents(&thumb_path, &self.thumbnails_dir());
+        }
+    }
 
-        Ok(screenshots)
+    /// Delete thumbnails whose screenshot is not in `screenshots`, as left
+    /// behind when files are removed outside the app. Returns the number removed.
+    pub fn remove_orphan_thumbnails(&self, screenshots: &HashSet<String>) -> u32 {
+        let root = self.thumbnails_dir();
+        let mut removed = 0;
+        let mut dirs = vec![(root.clone(), String::new())];
+        while let Some((dir, prefix)) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                let filename = if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", prefix, name)
+                };
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    dirs.push((entry.path(), filename));
+                } else if Self::is_screenshot_name(&filename)
+                    && !screenshots.contains(&filename)
+                    && fs::remove_file(entry.path()).is_ok()
+                {
+                    Self::remove_empty_parents(&entry.path(), &root);
+                    removed += 1;
+                }
+            }
+        }
+        removed
     }
 
-    fn get_or_create_thumbnail(&self, image_path: &Path, filename: &str) -> Result<String, String> {
-        let thumb_path = self.thumbnails_dir().join(filename);
+    /// PNG thumbnail of one screenshot, created on first request. `None`
+    /// while the screenshot is encrypted and locked.
+    pub fn thumbnail(&self, filename: &str) -> Result<Option<Vec<u8>>, String> {
+        let image_path = self.resolve(filename)?;
+        let thumb_path = self.thumbnail_path(filename);
 
         if thumb_path.exists() {
             let data = fs::read(&thumb_path).map_err(|e| e.to_string())?;
-            let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
-            return Ok(format!("data:image/png;base64,{}", b64));
+            if crypto::is_encrypted(&data) && !crypto::is_unlocked() {
+                return Ok(None);
+            }
+            return crypto::decrypt_if_needed(data).map(Some);
         }
 
         // Generate from original
-        let png_data = fs::read(image_path).map_err(|e| e.to_string())?;
-        self.generate_thumbnail(&png_data, filename)
+        let data = fs::read(&image_path).map_err(|e| e.to_string())?;
+        if crypto::is_encrypted(&data) && !crypto::is_unlocked() {
+            return Ok(None);
+        }
+        let image = image_codec::decode(&crypto::decrypt_if_needed(data)?)?;
+        self.generate_thumbnail(&image, filename).map(Some)
+    }
+
+    /// Load a single screenshot's info.
+    pub fn get_screenshot_info(&self, filename: &str) -> Result<ScreenshotInfo, String> {
+        let path = self.path_of(filename);
+        let mut info = Self::read_info(&path, filename)?;
+        info.pinned = self.load_pins().contains(filename);
+        Ok(info)
+    }
+
+    fn read_info(path: &Path, filename: &str) -> Result<ScreenshotInfo, String> {
+        let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+
+        // Modification time survives re-encoding, which writes a new file
+        let created_at = metadata
+            .modified()
+            .or_else(|_| metadata.created())
+            .map(|t| {
+                let datetime: chrono::DateTime<Local> = t.into();
+                datetime.format("%Y-%m-%dT%H:%M:%S%z").to_string()
+            })
+            .unwrap_or_default();
+
+        Ok(ScreenshotInfo {
+            filename: filename.to_string(),
+            path: path.to_string_lossy().to_string(),
+            created_at,
+            size_bytes: metadata.len(),
+            pinned: false,
+            use_count: 0,
+            last_used_at: None,
+        })
     }
 
-    pub fn delete_screenshot(&self, filename: &str) -> Result<(), String> {
-        let filepath = self.save_dir.join(filename);
+    /// Move a screenshot to the trash as part of `batch`. Its thumbnail is
+    /// dropped and made again if the screenshot is restored.
+    pub fn delete_screenshot(
+        &self,
+        filename: &str,
+        batch: i64,
+        reason: &str,
+    ) -> Result<(), String> {
+        let filepath = self.path_of(filename);
+        let mut pins = self.load_pins();
+        let pinned = pins.remove(filename);
         if filepath.exists() {
-            fs::remove_file(&filepath).map_err(|e| e.to_string())?;
+            let _guard = trash::lock()?;
+            let trashed = self.trashed_path(batch, filename);
+            if let Some(parent) = trashed.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::rename(&filepath, &trashed)
+                .map_err(|e| format!("Failed to move {} to trash: {}", filename, e))?;
+            let manifest_path = self.trash_manifest_path();
+            let mut items = trash::load_manifest(&manifest_path);
+            items.push(TrashedScreenshot {
+                filename: filename.to_string(),
+                batch,
+                reason: reason.to_string(),
+                pinned,
+            });
+            trash::save_manifest(&manifest_path, &items)?;
         }
-        let thumb_path = self.thumbnails_dir().join(filename);
+        let thumb_path = self.thumbnail_path(filename);
         if thumb_path.exists() {
             let _ = fs::remove_file(&thumb_path);
         }
+        // Drop day or month folders left empty; remove_dir refuses non-empty ones
+        let thumbs = self.thumbnails_dir();
+        for (path, root) in [(&filepath, &self.save_dir), (&thumb_path, &thumbs)] {
+            Self::remove_empty_parents(path, root);
+        }
+        if pinned {
+            self.save_pins(&pins)?;
+        }
         Ok(())
     }
 
-    pub fn cleanup_old(&self, max_count: u32) -> Result<u32, String> {
-        let mut screenshots = self.get_screenshot_list()?;
-        if screenshots.len() <= max_count as usize {
+    fn remove_empty_parents(path: &Path, root: &Path) {
+        let mut dir = path.parent();
+        while let Some(d) = dir.filter(|d| *d != root && d.starts_with(root)) {
+            if fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+
+    /// Screenshots in the trash, oldest deletion first.
+    pub fn trashed_screenshots(&self) -> Vec<TrashedScreenshot> {
+        trash::load_manifest(&self.trash_manifest_path())
+    }
+
+    /// Put a trashed screenshot back under its old name, or a free one next
+    /// to it if that has been taken since.
+    pub fn restore_screenshot(&self, batch: i64, filename: &str) -> Result<ScreenshotInfo, String> {
+        let _guard = trash::lock()?;
+        let manifest_path = self.trash_manifest_path();
+        let mut items = trash::load_manifest(&manifest_path);
+        let position = items
+            .iter()
+            .position(|item| item.batch == batch && item.filename == filename)
+            .ok_or_else(|| format!("Not in trash: {}", filename))?;
+        let trashed = self.trashed_path(batch, filename);
+        if !trashed.is_file() {
+            items.remove(position);
+            trash::save_manifest(&manifest_path, &items)?;
+            return Err(format!("Trashed file is missing: {}", filename));
+        }
+
+        let (stem, extension) = filename.rsplit_once('.').unwrap_or((filename, "png"));
+        let (folder, stem) = match stem.rsplit_once('/') {
+            Some((folder, stem)) => (Some(folder), stem),
+            None => (None, stem),
+        };
+        let restored = self.reserve(folder, extension, false, |_| stem.to_string())?;
+        let restored_path = self.path_of(&restored);
+        if let Err(e) = fs::rename(&trashed, &restored_path) {
+            let _ = fs::remove_file(&restored_path);
+            return Err(format!("Failed to restore {}: {}", filename, e));
+        }
+        Self::remove_empty_parents(&trashed, &self.trash_dir());
+
+        let item = items.remove(position);
+        trash::save_manifest(&manifest_path, &items)?;
+        if item.pinned {
+            let mut pins = self.load_pins();
+            pins.insert(restored.clone());
+            self.save_pins(&pins)?;
+        }
+        self.get_screenshot_info(&restored)
+    }
+
+    /// Permanently delete trashed screenshots from batches before `before`,
+    /// or all of them. Returns the number deleted.
+    pub fn purge_trash(&self, before: Option<i64>) -> Result<u32, String> {
+        self.purge_trashed(|item| before.is_none_or(|before| item.batch < before))
+    }
+
+    /// Permanently delete the trashed screenshots `purge` picks. Returns the
+    /// number deleted.
+    fn purge_trashed(&self, purge: impl Fn(&TrashedScreenshot) -> bool) -> Result<u32, String> {
+        let _guard = trash::lock()?;
+        let manifest_path = self.trash_manifest_path();
+        let items = trash::load_manifest(&manifest_path);
+        let (purged, kept): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| purge(item));
+        if purged.is_empty() {
             return Ok(0);
         }
+        for item in &purged {
+            let path = self.trashed_path(item.batch, &item.filename);
+            match fs::remove_file(&path) {
+                Ok(()) => Self::remove_empty_parents(&path, &self.trash_dir()),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => warn!("Failed to delete {}: {}", path.display(), e),
+            }
+        }
+        trash::save_manifest(&manifest_path, &kept)?;
+        Ok(purged.len() as u32)
+    }
 
-        // Screenshots are sorted newest first, remove from the end
-        let to_remove = screenshots.split_off(max_count as usize);
-        let count = to_remove.len() as u32;
+    /// Move the oldest unpinned screenshots beyond `max_count` to the trash.
+    /// Pinned screenshots are never removed and do not count toward the limit.
+    pub fn cleanup_old(&self, max_count: u32) -> Result<u32, String> {
+        Ok(self.enforce_retention(max_count, 0, 0)?.removed)
+    }
 
-        for info in to_remove {
-            let _ = self.delete_screenshot(&info.filename);
+    /// Apply count, age and total-size limits; a limit of 0 disables it.
+    /// Evicted screenshots go to the trash, so they can be restored until the
+    /// trash retention window passes. Pinned screenshots are never removed.
+    pub fn enforce_retention(
+        &self,
+        max_count: u32,
+        max_age_days: u32,
+        max_total_bytes: u64,
+    ) -> Result<RetentionResult, String> {
+        let screenshots = self.scan_screenshots()?;
+        let pinned_bytes: u64 = screenshots
+            .iter()
+            .filter(|s| s.pinned)
+            .map(|s| s.size_bytes)
+            .sum();
+        let cutoff = Local::now() - chrono::Duration::days(max_age_days as i64);
+        let mut result = RetentionResult::default();
+
+        // Sorted newest first; walk oldest first so eviction order is stable
+        let mut unpinned: Vec<ScreenshotInfo> =
+            screenshots.into_iter().filter(|s| !s.pinned).collect();
+        let mut remaining = unpinned.len();
+        let mut kept = Vec::with_capacity(remaining);
+        let batch = trash::new_batch();
+
+        while let Some(info) = unpinned.pop() {
+            let over_count = max_count > 0 && remaining > max_count as usize;
+            let too_old = max_age_days > 0
+                && chrono::DateTime::parse_from_str(&info.created_at, "%Y-%m-%dT%H:%M:%S%z")
+                    .map(|t| t < cutoff)
+                    .unwrap_or(false);
+            if (over_count || too_old)
+                && self
+                    .delete_screenshot(&info.filename, batch, REASON_RETENTION)
+                    .is_ok()
+            {
+                remaining -= 1;
+                result.removed += 1;
+            } else {
+                kept.push(info);
+            }
         }
 
-        Ok(count)
+        if max_total_bytes > 0 {
+            self.enforce_size_limit(max_total_bytes, pinned_bytes, &kept, &mut result)?;
+        }
+        Ok(result)
     }
 
+    /// Get the library and the trash under `max_total_bytes`, deleting the
+    /// oldest trashed screenshots before moving any of `unpinned`, oldest
+    /// first, to the trash. Screenshots already trashed for size are left to
+    /// the trash retention window and do not count. A limit that would evict
+    /// more than `MAX_SIZE_EVICTION_PERCENT` of the unpinned library is taken
+    /// as mis-set and nothing is removed.
+    fn enforce_size_limit(
+        &self,
+        max_total_bytes: u64,
+        pinned_bytes: u64,
+        unpinned: &[ScreenshotInfo],
+        result: &mut RetentionResult,
+    ) -> Result<(), String> {
+        let unpinned_bytes: u64 = unpinned.iter().map(|s| s.size_bytes).sum();
+        // The manifest lists oldest deletions first
+        let trashed: Vec<(TrashedScreenshot, u64)> = self
+            .trashed_screenshots()
+            .into_iter()
+            .filter(|item| item.reason != REASON_SIZE_LIMIT)
+            .map(|item| {
+                let size = fs::metadata(self.trashed_path(item.batch, &item.filename))
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                (item, size)
+            })
+            .collect();
+        let trashed_bytes: u64 = trashed.iter().map(|(_, size)| size).sum();
+        let mut total_bytes = pinned_bytes + unpinned_bytes + trashed_bytes;
+        if total_bytes <= max_total_bytes {
+            return Ok(());
+        }
+
+        let excess = (total_bytes - max_total_bytes).saturating_sub(trashed_bytes);
+        if excess > unpinned_bytes * MAX_SIZE_EVICTION_PERCENT / 100 {
+            warn!(
+                "Ignoring the {} byte size limit: it would evict {} of {} bytes of unpinned screenshots",
+                max_total_bytes, excess, unpinned_bytes
+            );
+            return Ok(());
+        }
+
+        let mut purge = HashSet::new();
+        for (item, size) in &trashed {
+            if total_bytes <= max_total_bytes {
+                break;
+            }
+            purge.insert((item.batch, item.filename.clone()));
+            total_bytes -= size;
+            result.bytes_freed += size;
+        }
+        if !purge.is_empty() {
+            result.trash_purged =
+                self.purge_trashed(|item| purge.contains(&(item.batch, item.filename.clone())))?;
+        }
+
+        let batch = trash::new_batch();
+        for info in unpinned {
+            if total_bytes <= max_total_bytes {
+                break;
+            }
+            if self
+                .delete_screenshot(&info.filename, batch, REASON_SIZE_LIMIT)
+                .is_ok()
+            {
+                total_bytes = total_bytes.saturating_sub(info.size_bytes);
+                result.removed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of every screenshot's decrypted contents.
+    pub fn content_hashes(&self) -> Result<HashSet<String>, String> {
+        Ok(self.hashed_screenshots()?.into_keys().collect())
+    }
+
+    /// Screenshots keyed by the SHA-256 of their decrypted image data. Files
+    /// whose size and modification time are unchanged since the last call are
+    /// not read again.
+    pub fn hashed_screenshots(&self) -> Result<HashMap<String, ScreenshotInfo>, String> {
+        static CONTENT_HASHES: OnceLock<Mutex<HashMap<String, CachedHash>>> = OnceLock::new();
+        let mut cache = CONTENT_HASHES
+            .get_or_init(Default::default)
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+
+        let mut screenshots = HashMap::new();
+        let mut present = HashSet::new();
+        for info in self.scan_screenshots()? {
+            let cached = cache.get(&info.path).filter(|cached| {
+                cached.size_bytes == info.size_bytes && cached.created_at == info.created_at
+            });
+            let hash = match cached {
+                Some(cached) => cached.hash.clone(),
+                None => {
+                    let hash = sha256_hash(&self.get_image_data(&info.filename)?);
+                    cache.insert(
+                        info.path.clone(),
+                        CachedHash {
+                            hash: hash.clone(),
+                            size_bytes: info.size_bytes,
+                            created_at: info.created_at.clone(),
+                        },
+                    );
+                    hash
+                }
+            };
+            present.insert(info.path.clone());
+            screenshots.insert(hash, info);
+        }
+        // Forget files removed from this library; other save directories keep theirs
+        cache.retain(|path, _| {
+            !Path::new(path).starts_with(&self.save_dir) || present.contains(path)
+        });
+        Ok(screenshots)
+    }
+
+    /// Store a screenshot brought in from an archive or another machine,
+    /// keeping its name and folder unless taken. The thumbnail is created on
+    /// first listing.
+    pub fn import_screenshot(&self, filename: &str, data: &[u8]) -> Result<ScreenshotInfo, String> {
+        self.ensure_directories()?;
+
+        // Older archives name files without their folder
+        let name = Some(filename)
+            .filter(|n| Self::is_screenshot_name(n))
+            .or_else(|| Path::new(filename).file_name().and_then(|n| n.to_str()))
+            .filter(|n| Self::is_screenshot_name(n))
+            .ok_or_else(|| format!("Invalid screenshot name: {}", filename))?;
+        let (stem, extension) = name.rsplit_once('.').unwrap_or((name, "png"));
+        let (folder, stem) = match stem.rsplit_once('/') {
+            Some((folder, stem)) => (Some(folder), stem),
+            None => (None, stem),
+        };
+        let filename = self.reserve(folder, extension, false, |_| stem.to_string())?;
+
+        let filepath = self.path_of(&filename);
+        if let Err(e) = safe_file::write_atomic(&filepath, &self.seal(data)?) {
+            let _ = fs::remove_file(&filepath);
+            return Err(format!("Failed to save screenshot: {}", e));
+        }
+        Self::read_info(&filepath, &filename)
+    }
+
+    /// Decrypted contents of a screenshot. Names that lead outside the save
+    /// directory are refused.
     pub fn get_image_data(&self, filename: &str) -> Result<Vec<u8>, String> {
-        let filepath = self.save_dir.join(filename);
-        fs::read(&filepath).map_err(|e| format!("Failed to read image: {}", e))
+        let filepath = self.resolve(filename)?;
+        let data = fs::read(&filepath).map_err(|e| format!("Failed to read image: {}", e))?;
+        crypto::decrypt_if_needed(data)
+    }
+
+    /// Encrypt screenshots, thumbnails, `latest.<ext>` and the trash where
+    /// still plaintext. Returns the number of screenshots encrypted.
+    pub fn encrypt_existing(&self) -> Result<u32, String> {
+        let mut count = 0;
+        for info in self.scan_screenshots()? {
+            if Self::encrypt_file_in_place(Path::new(&info.path))? {
+                count += 1;
+            }
+            let thumb_path = self.thumbnail_path(&info.filename);
+            if thumb_path.exists() {
+                Self::encrypt_file_in_place(&thumb_path)?;
+            }
+        }
+
+        for ext in image_codec::EXTENSIONS {
+            let latest_path = self.save_dir.join(format!("latest.{}", ext));
+            if latest_path.exists() {
+                Self::encrypt_file_in_place(&latest_path)?;
+            }
+        }
+        let _guard = trash::lock()?;
+        for item in self.trashed_screenshots() {
+            let path = self.trashed_path(item.batch, &item.filename);
+            if path.exists() {
+                Self::encrypt_file_in_place(&path)?;
+            }
+        }
+        Ok(count)
+    }
+
+    fn encrypt_file_in_place(path: &Path) -> Result<bool, String> {
+        let data = fs::read(path).map_err(|e| e.to_string())?;
+        if crypto::is_encrypted(&data) {
+            return Ok(false);
+        }
+        safe_file::write_atomic(path, &crypto::encrypt(&data)?)
+            .map_err(|e| format!("Failed to encrypt {}: {}", path.display(), e))?;
+        Ok(true)
     }
 }
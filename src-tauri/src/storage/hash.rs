@@ -1,3 +1,5 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
 use sha2::{Digest, Sha256};
 
 pub fn sha256_hash(data: &[u8]) -> String {
@@ -5,3 +7,33 @@ pub fn sha256_hash(data: &[u8]) -> String {
     hasher.update(data);
     format!("{:x}", hasher.finalize())
 }
+
+/// Perceptual difference hash: one bit per pair of neighbouring pixels in a
+/// 9x8 grayscale copy, set when the left one is brighter. Small changes such
+/// as a blinking cursor leave most bits alone.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// Number of bits two perceptual hashes differ in, from 0 to 64.
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// SHA-256 of an image's size and RGBA pixels, which stays the same however
+/// the image is encoded or compressed losslessly.
+pub fn pixel_hash(image: &RgbaImage) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_raw());
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,333 @@
+use crate::storage::file_manager::{FileManager, ScreenshotInfo};
+use crate::storage::hash::pixel_hash;
+use crate::storage::text_history::TextHistory;
+use crate::storage::{crypto, image_codec, safe_file};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    hash: String,
+    size_bytes: u64,
+    created_at: String,
+}
+
+/// Pixel hashes of the stored screenshots, kept in `.hashes.json` in the save
+/// directory so an image already in the library is not saved again after a
+/// restart. Files added, changed or removed outside the app are picked up on
+/// the next refresh.
+#[derive(Default)]
+pub struct HashIndex {
+    dir: PathBuf,
+    files: HashMap<String, IndexedFile>,
+    by_hash: HashMap<String, String>,
+    /// Files without a known hash are being decoded by `hash_in_background`
+    hashing: bool,
+}
+
+impl HashIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index_path(dir: &Path) -> PathBuf {
+        dir.join(".hashes.json")
+    }
+
+    fn load(dir: &Path) -> HashMap<String, IndexedFile> {
+        let path = Self::index_path(dir);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return HashMap::new();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            // Rebuilt from the files, so the broken copy is only kept for reference
+            match safe_file::backup_corrupt(&path) {
+                Ok(backup) => warn!(
+                    "Hash index is corrupt ({}); moved to {}",
+                    e,
+                    backup.display()
+                ),
+                Err(backup_err) => warn!("Hash index is corrupt ({}): {}", e, backup_err),
+            }
+            HashMap::new()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string(&self.files).map_err(|e| e.to_string())?;
+        safe_file::write_atomic(&Self::index_path(&self.dir), content.as_bytes())
+            .map_err(|e| format!("Failed to save hash index: {}", e))
+    }
+
+    fn rebuild_lookup(&mut self) {
+        self.by_hash = self
+            .files
+            .iter()
+            .map(|(filename, file)| (file.hash.clone(), filename.clone()))
+            .collect();
+    }
+
+    /// Bring the index in line with the files on disk. Screenshots the index
+    /// has no current hash for take the one recorded in `text_history` when
+    /// they were captured, since decoding a lossy or scaled file gives another.
+    /// Returns the files with neither, to pass to `hash_in_background`; none
+    /// while an earlier batch is still being hashed.
+    pub fn refresh(
+        &mut self,
+        file_manager: &FileManager,
+        text_history: &Arc<Mutex<TextHistory>>,
+    ) -> Result<Vec<ScreenshotInfo>, String> {
+        let dir = file_manager.save_dir().to_path_buf();
+        if dir != self.dir {
+            self.files = Self::load(&dir);
+            self.dir = dir;
+        }
+
+        let mut captured: Option<HashMap<String, String>> = None;
+        let mut changed = false;
+        let mut files = HashMap::new();
+        let mut unhashed = Vec::new();
+        for info in file_manager.scan_screenshots()? {
+            let known = self.files.remove(&info.filename).filter(|file| {
+                file.size_bytes == info.size_bytes && file.created_at == info.created_at
+            });
+            let file = match known {
+                Some(file) => file,
+                None => {
+                    let captured = captured.get_or_insert_with(|| {
+                        text_history
+                            .lock()
+                            .map(|th| th.screenshot_pixel_hashes())
+                            .unwrap_or_default()
+                    });
+                    match captured.remove(&info.filename) {
+                        Some(hash) => {
+                            changed = true;
+                            Self::entry(&info, hash)
+                        }
+                        None => {
+                            unhashed.push(info);
+                            continue;
+                        }
+                    }
+                }
+            };
+            files.insert(info.filename, file);
+        }
+        // Anything left over was removed from disk
+        changed |= !self.files.is_empty();
+        self.files = files;
+        self.rebuild_lookup();
+
+        if changed {
+            self.save()?;
+        }
+        if self.hashing {
+            unhashed.clear();
+        }
+        self.hashing |= !unhashed.is_empty();
+        Ok(unhashed)
+    }
+
+    /// Decode and hash `files` on a separate thread, as a library added to
+    /// the folder by hand can take minutes; they are matched once it is done.
+    /// Encrypted files are left out while the store is locked.
+    pub fn hash_in_background(index: &Arc<Mutex<HashIndex>>, files: Vec<ScreenshotInfo>) {
+        if files.is_empty() {
+            return;
+        }
+        let index = index.clone();
+        std::thread::spawn(move || {
+            let mut hashed = Vec::new();
+            for info in files {
+                match Self::hash_file(&info) {
+                    Ok(Some(hash)) => hashed.push((info, hash)),
+                    Ok(None) => {}
+                    Err(e) => warn!("Cannot hash {}: {}", info.filename, e),
+                }
+            }
+
+            let Ok(mut index) = index.lock() else {
+                return;
+            };
+            index.hashing = false;
+            // Files removed or changed since are checked again on the next refresh
+            let mut added = false;
+            for (info, hash) in hashed {
+                if Path::new(&info.path).starts_with(&index.dir)
+                    && !index.files.contains_key(&info.filename)
+                {
+                    index.by_hash.insert(hash.clone(), info.filename.clone());
+                    index
+                        .files
+                        .insert(info.filename.clone(), Self::entry(&info, hash));
+                    added = true;
+                }
+            }
+            if added {
+                if let Err(e) = index.save() {
+                    error!("{}", e);
+                }
+            }
+        });
+    }
+
+    fn hash_file(info: &ScreenshotInfo) -> Result<Option<String>, String> {
+        let data = fs::read(&info.path).map_err(|e| e.to_string())?;
+        if crypto::is_encrypted(&data) && !crypto::is_unlocked() {
+            return Ok(None);
+        }
+        let image = image_codec::decode(&crypto::decrypt_if_needed(data)?)?;
+        Ok(Some(pixel_hash(&image.to_rgba8())))
+    }
+
+    fn entry(info: &ScreenshotInfo, hash: String) -> IndexedFile {
+        IndexedFile {
+            hash,
+            size_bytes: info.size_bytes,
+            created_at: info.created_at.clone(),
+        }
+    }
+
+    /// The stored screenshot with these pixels, if any.
+    pub fn find(&self, hash: &str) -> Option<&str> {
+        self.by_hash.get(hash).map(String::as_str)
+    }
+
+    /// Record a screenshot just saved from an image with pixel hash `hash`.
+    /// The hash of the image as captured is kept, so lossy or scaled copies
+    /// still match it.
+    pub fn insert(&mut self, info: &ScreenshotInfo, hash: String) -> Result<(), String> {
+        self.by_hash.insert(hash.clone(), info.filename.clone());
+        self.files
+            .insert(info.filename.clone(), Self::entry(info, hash));
+        self.save()
+    }
+
+    /// Carry the hash of a re-encoded screenshot over to its new file.
+    pub fn rename(&mut self, old_filename: &str, info: &ScreenshotInfo) -> Result<(), String> {
+        let Some(file) = self.files.remove(old_filename) else {
+            return Ok(());
+        };
+        self.insert(info, file.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppSettings;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+    use std::time::{Duration, Instant};
+
+    fn store(name: &str) -> (PathBuf, FileManager, Arc<Mutex<TextHistory>>) {
+        let dir = std::env::temp_dir().join(format!(
+            "cli-buddy-hash-index-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        let settings = AppSettings {
+            save_directory: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let history = TextHistory::in_memory(dir.join(".blobs"));
+        (
+            dir,
+            FileManager::new(&settings),
+            Arc::new(Mutex::new(history)),
+        )
+    }
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(32, 32, |x, y| Rgba([(x * 8) as u8, (y * 8) as u8, 0, 255]))
+    }
+
+    #[test]
+    fn rebuilds_from_capture_hashes() {
+        let (dir, file_manager, text_history) = store("rebuild");
+        let image = gradient();
+        let hash = pixel_hash(&image);
+        let info = file_manager
+            .save_screenshot(&DynamicImage::ImageRgba8(image.clone()))
+            .unwrap();
+        {
+            let mut th = text_history.lock().unwrap();
+            th.record_screenshot(&info);
+            th.set_screenshot_pixel_hash(&info.filename, &hash);
+        }
+        // Scaled down since, as the re-encoder does with large captures
+        let smaller = DynamicImage::ImageRgba8(image).thumbnail(16, 16);
+        smaller
+            .save_with_format(&info.path, ImageFormat::Png)
+            .unwrap();
+        assert_ne!(HashIndex::hash_file(&info).unwrap(), Some(hash.clone()));
+
+        let mut index = HashIndex::new();
+        assert!(index
+            .refresh(&file_manager, &text_history)
+            .unwrap()
+            .is_empty());
+        assert_eq!(index.find(&hash), Some(info.filename.as_str()));
+
+        // Also when the index file is lost
+        fs::remove_file(HashIndex::index_path(&dir)).unwrap();
+        let mut index = HashIndex::new();
+        assert!(index
+            .refresh(&file_manager, &text_history)
+            .unwrap()
+            .is_empty());
+        assert_eq!(index.find(&hash), Some(info.filename.as_str()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hashes_unknown_files_in_background() {
+        let (dir, file_manager, text_history) = store("background");
+        let image = gradient();
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(image.clone())
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("outside.png"), &png).unwrap();
+
+        let index = Arc::new(Mutex::new(HashIndex::new()));
+        let unhashed = index
+            .lock()
+            .unwrap()
+            .refresh(&file_manager, &text_history)
+            .unwrap();
+        assert_eq!(unhashed.len(), 1);
+        // Not handed out twice while the first batch is pending
+        let again = index
+            .lock()
+            .unwrap()
+            .refresh(&file_manager, &text_history)
+            .unwrap();
+        assert!(again.is_empty());
+
+        HashIndex::hash_in_background(&index, unhashed);
+        let hash = pixel_hash(&image);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while index.lock().unwrap().find(&hash).is_none() {
+            assert!(
+                Instant::now() < deadline,
+                "background hashing did not finish"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let mut index = index.lock().unwrap();
+        assert_eq!(index.find(&hash), Some("outside.png"));
+        assert!(index
+            .refresh(&file_manager, &text_history)
+            .unwrap()
+            .is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::config::AppSettings;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use std::io::Cursor;
+
+/// Extensions a stored screenshot may have, one per output format.
+pub const EXTENSIONS: &[&str] = &["png", "webp", "jpg"];
+
+/// File format new screenshots are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    WebpLossless,
+    Webp,
+    Jpeg,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "png" => Ok(Self::Png),
+            "webp_lossless" => Ok(Self::WebpLossless),
+            "webp" => Ok(Self::Webp),
+            "jpeg" => Ok(Self::Jpeg),
+            _ => Err(format!("Unknown image format: {}", name)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::WebpLossless | Self::Webp => "webp",
+            Self::Jpeg => "jpg",
+        }
+    }
+
+    pub fn is_lossy(self) -> bool {
+        matches!(self, Self::Webp | Self::Jpeg)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    pub format: OutputFormat,
+    /// 1-100, used by lossy WebP and JPEG
+    pub quality: u8,
+    /// Recompress PNG output with oxipng; the pixels are unchanged
+    pub optimize_png: bool,
+    /// Longest side kept; larger images are scaled down. 0 keeps the original size
+    pub max_dimension: u32,
+}
+
+impl EncodeOptions {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            // A hand-edited config with an unknown name falls back to PNG
+            format: OutputFormat::parse(&settings.image_format).unwrap_or(OutputFormat::Png),
+            quality: settings.image_quality.clamp(1, 100) as u8,
+            optimize_png: settings.optimize_png,
+            max_dimension: settings.max_image_dimension,
+        }
+    }
+}
+
+/// `image` shrunk to fit within `max_dimension` on its longest side, keeping
+/// its aspect ratio. `None` when it already fits or there is no limit.
+pub fn downscale(image: &DynamicImage, max_dimension: u32) -> Option<DynamicImage> {
+    if max_dimension == 0 || image.width().max(image.height()) <= max_dimension {
+        return None;
+    }
+    Some(image.resize(max_dimension, max_dimension, FilterType::Lanczos3))
+}
+
+/// Encode `image` for storage, downscaling it first if it is too large.
+pub fn encode(image: &DynamicImage, options: &EncodeOptions) -> Result<Vec<u8>, String> {
+    let resized = downscale(image, options.max_dimension);
+    let image = resized.as_ref().unwrap_or(image);
+    match options.format {
+        OutputFormat::Png => encode_png(image, options.optimize_png),
+        OutputFormat::WebpLossless | OutputFormat::Webp => {
+            let rgba = image.to_rgba8();
+            let lossless = options.format == OutputFormat::WebpLossless;
+            webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height())
+                .encode_simple(lossless, options.quality as f32)
+                .map(|data| data.to_vec())
+                .map_err(|e| format!("Failed to encode WebP: {:?}", e))
+        }
+        OutputFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb = image.to_rgb8();
+            let mut buf = Vec::new();
+            JpegEncoder::new_with_quality(&mut buf, options.quality)
+                .encode_image(&rgb)
+                .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+            Ok(buf)
+        }
+    }
+}
+
+pub fn encode_png(image: &DynamicImage, optimize: bool) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    if optimize {
+        buf = oxipng::optimize_from_memory(&buf, &oxipng::Options::from_preset(2))
+            .map_err(|e| format!("Failed to optimize PNG: {}", e))?;
+    }
+    Ok(buf)
+}
+
+/// Decode a stored screenshot in any of the output formats.
+pub fn decode(data: &[u8]) -> Result<DynamicImage, String> {
+    image::load_from_memory(data).map_err(|e| format!("Failed to decode image: {}", e))
+}
@@ -1,3 +1,20 @@
+pub mod archive;
+pub mod blob_store;
+pub mod classifier;
+pub mod crypto;
+pub mod database;
+pub mod duplicates;
 pub mod file_manager;
+pub mod hash_index;
 pub mod hash;
+pub mod image_codec;
+pub mod naming;
+pub mod paths;
+pub mod percent;
+pub mod safe_file;
+pub mod screenshot_index;
+pub mod snippets;
+pub mod sync;
 pub mod text_history;
+pub mod transforms;
+pub mod trash;
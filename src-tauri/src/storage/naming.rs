@@ -0,0 +1,152 @@
+use chrono::{DateTime, Local};
+
+/// Template giving the names screenshots always had, `screenshot_<date>_<time>`.
+pub const DEFAULT_TEMPLATE: &str = "screenshot_{date}_{time}";
+
+const TOKENS: &[&str] = &["date", "time", "seq", "hash8", "project"];
+
+/// Values a filename template is filled in with for one capture.
+pub struct NameContext<'a> {
+    pub now: DateTime<Local>,
+    /// Hex SHA-256 of the encoded image; the first 8 digits fill `{hash8}`
+    pub hash: &'a str,
+    pub project: Option<&'a str>,
+}
+
+/// Check that `template` only uses known tokens and names a single file.
+pub fn validate(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Filename template is empty".to_string());
+    }
+    if template.contains(['/', '\\']) {
+        return Err("Filename template cannot contain folders".to_string());
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("Unclosed token in filename template: {}", template));
+        };
+        let token = &rest[start + 1..start + len];
+        if !TOKENS.contains(&token) {
+            return Err(format!("Unknown filename token: {{{}}}", token));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    Ok(())
+}
+
+pub fn uses_seq(template: &str) -> bool {
+    template.contains("{seq}")
+}
+
+/// File stem for a capture. `{seq}` becomes `seq` padded to three digits;
+/// characters Windows does not allow in filenames become `_`.
+pub fn render(template: &str, ctx: &NameContext, seq: u32) -> String {
+    let stem = template
+        .replace("{date}", &ctx.now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &ctx.now.format("%H-%M-%S").to_string())
+        .replace("{seq}", &format!("{:03}", seq))
+        .replace("{hash8}", ctx.hash.get(..8).unwrap_or(ctx.hash))
+        .replace("{project}", ctx.project.unwrap_or("unknown"));
+    sanitize(&stem)
+}
+
+fn sanitize(stem: &str) -> String {
+    let cleaned: String = stem
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Dotfiles are skipped by the scan, and Windows drops trailing dots and spaces
+    let cleaned = cleaned.trim_start_matches('.').trim_end_matches(['.', ' ']);
+    if cleaned.is_empty() {
+        "screenshot".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// Folder under the save directory a capture goes in: `"day"` gives
+/// `YYYY-MM-DD`, `"month"` gives `YYYY-MM`, anything else the save directory itself.
+pub fn subfolder(layout: &str, now: &DateTime<Local>) -> Option<String> {
+    match layout {
+        "day" => Some(now.format("%Y-%m-%d").to_string()),
+        "month" => Some(now.format("%Y-%m").to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ctx(project: Option<&str>) -> NameContext<'_> {
+        NameContext {
+            now: Local.with_ymd_and_hms(2024, 3, 9, 7, 5, 1).unwrap(),
+            hash: "0123456789abcdef",
+            project,
+        }
+    }
+
+    #[test]
+    fn validates_templates() {
+        assert!(validate(DEFAULT_TEMPLATE).is_ok());
+        assert!(validate("{project}-{seq}-{hash8}").is_ok());
+        assert!(validate("no tokens}").is_ok());
+        for bad in [
+            "",
+            "   ",
+            "shots/{date}",
+            "a\\b",
+            "{date",
+            "{date}_{time",
+            "{}",
+            "{Date}",
+            "{{date}}",
+            "{user}",
+        ] {
+            assert!(validate(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn renders_tokens() {
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &ctx(None), 0),
+            "screenshot_2024-03-09_07-05-01"
+        );
+        assert_eq!(
+            render("{project}_{seq}_{hash8}", &ctx(Some("app")), 7),
+            "app_007_01234567"
+        );
+        assert_eq!(render("{project}{seq}", &ctx(None), 1234), "unknown1234");
+        let short = NameContext {
+            hash: "abc",
+            ..ctx(None)
+        };
+        assert_eq!(render("{hash8}", &short, 0), "abc");
+        assert!(uses_seq("{seq}") && !uses_seq("seq"));
+    }
+
+    #[test]
+    fn sanitizes_names() {
+        assert_eq!(render("{project}", &ctx(Some("a/b:c*d")), 0), "a_b_c_d");
+        assert_eq!(render("x\ty?", &ctx(None), 0), "x_y_");
+        assert_eq!(render(".{project}. .", &ctx(Some("app")), 0), "app");
+        assert_eq!(render("{project}", &ctx(Some("..")), 0), "screenshot");
+        assert_eq!(render("{project}", &ctx(Some("")), 0), "screenshot");
+        assert_eq!(render("스크린샷 {seq}", &ctx(None), 2), "스크린샷 002");
+    }
+
+    #[test]
+    fn picks_subfolders() {
+        let now = ctx(None).now;
+        assert_eq!(subfolder("day", &now).as_deref(), Some("2024-03-09"));
+        assert_eq!(subfolder("month", &now).as_deref(), Some("2024-03"));
+        assert_eq!(subfolder("none", &now), None);
+    }
+}
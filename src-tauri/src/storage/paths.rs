@@ -0,0 +1,35 @@
+/// `C:\dir` -> `/mnt/c/dir`, and `\\wsl$\<distro>\dir` (or `\\wsl.localhost\...`)
+/// -> `/dir`. None for anything else.
+pub fn windows_to_wsl(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        let drive = (bytes[0] as char).to_ascii_lowercase();
+        return Some(format!("/mnt/{}{}", drive, path[2..].replace('\\', "/")));
+    }
+    let lower = path.to_ascii_lowercase();
+    for prefix in ["\\\\wsl$\\", "\\\\wsl.localhost\\"] {
+        if lower.starts_with(prefix) {
+            let inner = &path[prefix.len()..];
+            let (_distro, rest) = inner.split_once('\\').unwrap_or((inner, ""));
+            return Some(format!("/{}", rest.replace('\\', "/")));
+        }
+    }
+    None
+}
+
+/// `/mnt/c/dir` -> `C:\dir`. None for paths outside a mounted drive.
+pub fn wsl_to_windows(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/mnt/")?;
+    let mut chars = rest.chars();
+    let drive = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+    let tail = chars.as_str();
+    if !tail.is_empty() && !tail.starts_with('/') {
+        return None;
+    }
+    let tail = if tail.is_empty() {
+        "\\".to_string()
+    } else {
+        tail.replace('/', "\\")
+    };
+    Some(format!("{}:{}", drive.to_ascii_uppercase(), tail))
+}
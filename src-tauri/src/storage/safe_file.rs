@@ -0,0 +1,44 @@
+use chrono::Local;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn sibling_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Write `data` to a temp file next to `path`, flush it to disk and rename it
+/// over `path`, so a crash leaves either the old or the new contents.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let tmp_path = sibling_with_suffix(path, ".tmp");
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, path)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// Move an unreadable file aside as `<name>.corrupt-<timestamp>` so it can be
+/// recovered by hand instead of being overwritten.
+pub fn backup_corrupt(path: &Path) -> Result<PathBuf, String> {
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let backup = sibling_with_suffix(path, &format!(".corrupt-{}", timestamp));
+    fs::rename(path, &backup)
+        .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
@@ -0,0 +1,90 @@
+use crate::storage::file_manager::{FileManager, ScreenshotInfo};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Page size used when a listing command is called without a limit.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// One page of a listing. Pass `next_cursor` back to get the following page;
+/// it is unset on the last one.
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items in the whole listing
+    pub total: usize,
+}
+
+/// Screenshot metadata kept in memory, newest first, so listings do not stat
+/// every file. Rescanned when the save directory changes or any of its
+/// folders is modified, which adding, deleting or pinning a screenshot does.
+#[derive(Default)]
+pub struct ScreenshotIndex {
+    dir: PathBuf,
+    modified: Option<Vec<(PathBuf, SystemTime)>>,
+    screenshots: Vec<ScreenshotInfo>,
+}
+
+impl ScreenshotIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn refresh(&mut self, file_manager: &FileManager) -> Result<(), String> {
+        let dir = file_manager.save_dir().to_path_buf();
+        let modified = file_manager
+            .folders()
+            .into_iter()
+            .map(|folder| {
+                let time = fs::metadata(&folder).and_then(|m| m.modified())?;
+                Ok((folder, time))
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .ok();
+        if dir == self.dir && modified.is_some() && modified == self.modified {
+            return Ok(());
+        }
+        self.screenshots = file_manager.scan_screenshots()?;
+        self.dir = dir;
+        self.modified = modified;
+        Ok(())
+    }
+
+    /// Force a rescan on the next refresh.
+    pub fn invalidate(&mut self) {
+        self.modified = None;
+    }
+
+    /// Every screenshot, newest first.
+    pub fn all(&self) -> &[ScreenshotInfo] {
+        &self.screenshots
+    }
+
+    /// Up to `limit` screenshots older than the one `cursor` was taken at.
+    pub fn page(&self, cursor: Option<&str>, limit: usize) -> Page<ScreenshotInfo> {
+        // Sorted by time, then filename, descending; the cursor holds both so
+        // it stays valid when that screenshot is deleted
+        let start = cursor.map_or(0, |c| {
+            let (created_at, filename) = c.split_once('|').unwrap_or(("", c));
+            self.screenshots.partition_point(|s| {
+                (s.created_at.as_str(), s.filename.as_str()) >= (created_at, filename)
+            })
+        });
+        let items: Vec<ScreenshotInfo> = self.screenshots[start..]
+            .iter()
+            .take(limit)
+            .cloned()
+            .collect();
+        let next_cursor = items
+            .last()
+            .filter(|_| start + items.len() < self.screenshots.len())
+            .map(|s| format!("{}|{}", s.created_at, s.filename));
+        Page {
+            items,
+            next_cursor,
+            total: self.screenshots.len(),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::storage::safe_file;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::Local;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Layout version of snippets.json
+const SNIPPETS_VERSION: u32 = 1;
+
+/// Placeholders understood by `expand`, without braces.
+pub const PLACEHOLDERS: &[&str] = &[
+    "date",
+    "time",
+    "clipboard",
+    "latest_screenshot",
+    "latest_screenshot_wsl",
+    "uuid",
+];
+
+/// A named text template kept until deleted, independent of history retention.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub content: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub use_count: u32,
+    #[serde(default)]
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnippetsFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    snippets: Vec<Snippet>,
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string()
+}
+
+/// Random (version 4) UUID in its usual hyphenated form.
+pub fn new_uuid() -> String {
+    let mut b = [0u8; 16];
+    OsRng.fill_bytes(&mut b);
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let hex: String = b.iter().map(|x| format!("{:02x}", x)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Replace `{name}` placeholders using `resolve`. `{{` and `}}` produce literal
+/// braces; unknown or unresolvable placeholders are left as written.
+pub fn expand(template: &str, mut resolve: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        if rest.starts_with('{') {
+            if let Some(end) = rest.find('}') {
+                let name = &rest[1..end];
+                let known = PLACEHOLDERS.contains(&name);
+                if let Some(value) = known.then(|| resolve(name)).flatten() {
+                    out.push_str(&value);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        out.push_str(&rest[..1]);
+        rest = &rest[1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Snippets stored in `snippets.json` next to the settings. They are written
+/// by the user rather than captured, so they are not encrypted at rest.
+pub struct SnippetStore {
+    path: PathBuf,
+    snippets: Vec<Snippet>,
+}
+
+impl SnippetStore {
+    pub fn new() -> Self {
+        let path = dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cli-buddy")
+            .join("snippets.json");
+        let snippets = Self::load(&path);
+        Self { path, snippets }
+    }
+
+    fn load(path: &PathBuf) -> Vec<Snippet> {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Vec::new();
+        };
+        match serde_json::from_str::<SnippetsFile>(&content) {
+            Ok(file) => {
+                if file.version > SNIPPETS_VERSION {
+                    warn!(
+                        "Snippets were written by a newer version ({}); unknown fields are ignored",
+                        file.version
+                    );
+                }
+                file.snippets
+            }
+            Err(e) => {
+                match safe_file::backup_corrupt(path) {
+                    Ok(backup) => warn!(
+                        "Snippets file is corrupt ({}); moved to {}",
+                        e,
+                        backup.display()
+                    ),
+                    Err(backup_err) => error!("Snippets file is corrupt ({}): {}", e, backup_err),
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let file = SnippetsFile {
+            version: SNIPPETS_VERSION,
+            snippets: self.snippets.clone(),
+        };
+        let content = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        safe_file::write_atomic(&self.path, content.as_bytes())
+    }
+
+    /// Snippets sorted by name.
+    pub fn list(&self) -> Vec<Snippet> {
+        let mut snippets = self.snippets.clone();
+        snippets.sort_by_key(|s| s.name.to_lowercase());
+        snippets
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Snippet> {
+        self.snippets.iter().find(|s| s.id == id)
+    }
+
+    fn check_name(&self, name: &str, except_id: Option<&str>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Snippet name must not be empty".to_string());
+        }
+        let taken = self.snippets.iter().any(|s| {
+            Some(s.id.as_str()) != except_id && s.name.to_lowercase() == name.to_lowercase()
+        });
+        if taken {
+            return Err(format!("A snippet named \"{}\" already exists", name));
+        }
+        Ok(name.to_string())
+    }
+
+    pub fn create(&mut self, name: &str, content: &str) -> Result<Snippet, String> {
+        let name = self.check_name(name, None)?;
+        let created_at = now();
+        let snippet = Snippet {
+            id: new_uuid(),
+            name,
+            content: content.to_string(),
+            created_at: created_at.clone(),
+            updated_at: created_at,
+            use_count: 0,
+            last_used_at: None,
+        };
+        self.snippets.push(snippet.clone());
+        self.save()?;
+        Ok(snippet)
+    }
+
+    pub fn update(&mut self, id: &str, name: &str, content: &str) -> Result<Snippet, String> {
+        let name = self.check_name(name, Some(id))?;
+        let snippet = self
+            .snippets
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("Snippet not found: {}", id))?;
+        snippet.name = name;
+        snippet.content = content.to_string();
+        snippet.updated_at = now();
+        let snippet = snippet.clone();
+        self.save()?;
+        Ok(snippet)
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<bool, String> {
+        let before = self.snippets.len();
+        self.snippets.retain(|s| s.id != id);
+        if self.snippets.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn record_use(&mut self, id: &str) {
+        if let Some(snippet) = self.snippets.iter_mut().find(|s| s.id == id) {
+            snippet.use_count += 1;
+            snippet.last_used_at = Some(now());
+            if let Err(e) = self.save() {
+                error!("Failed to save snippet usage: {}", e);
+            }
+        }
+    }
+}
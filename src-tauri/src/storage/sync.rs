@@ -0,0 +1,813 @@
+use crate::config::AppSettings;
+use crate::storage::file_manager::{FileManager, ScreenshotInfo};
+use crate::storage::hash::sha256_hash;
+use crate::storage::safe_file;
+use crate::storage::text_history::{TextEntry, TextHistory};
+use crate::storage::trash;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::{DateTime, Duration, Local};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Layout of the shared folder:
+//   cli-buddy-sync/devices/<device id>/changes.jsonl  append-only, one writer each
+//   cli-buddy-sync/devices/<device id>/device.json    name and last sync time
+//   cli-buddy-sync/objects/<sha256>                   screenshot image data
+const SYNC_DIR: &str = "cli-buddy-sync";
+const LOG_NAME: &str = "changes.jsonl";
+const DEVICE_INFO_NAME: &str = "device.json";
+const MAX_CONFLICTS: usize = 50;
+/// Local deletions are remembered this long to win against older remote adds
+const TOMBSTONE_DAYS: i64 = 90;
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+
+/// Serializes access to the state file between the sync thread and commands.
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// One line of a device's change log. Every change is keyed by content hash,
+/// so applying the same change twice has no further effect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Change {
+    AddText {
+        hash: String,
+        at: String,
+        entry: Box<TextEntry>,
+    },
+    DeleteText {
+        hash: String,
+        at: String,
+    },
+    AddScreenshot {
+        hash: String,
+        at: String,
+        filename: String,
+    },
+    DeleteScreenshot {
+        hash: String,
+        at: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub hash: String,
+    /// Device whose change was not applied
+    pub device: String,
+    pub reason: String,
+    pub at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDevice {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub last_sync: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// Changes from other devices applied locally
+    pub applied: u32,
+    /// Local changes written to the shared folder
+    pub published: u32,
+    pub conflicts: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    pub folder: String,
+    pub device_id: String,
+    pub last_sync: Option<String>,
+    /// Local additions and deletions not yet written to the shared folder
+    pub pending_local: u32,
+    /// Changes from other devices not yet applied
+    pub pending_remote: u32,
+    pub devices: Vec<SyncDevice>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// Per-machine bookkeeping, kept next to settings rather than in the shared folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    #[serde(default)]
+    device_id: String,
+    /// Bytes of each remote log already applied
+    #[serde(default)]
+    offsets: HashMap<String, u64>,
+    /// Content present locally as of the last sync; anything new is published
+    #[serde(default)]
+    known_text: HashSet<String>,
+    #[serde(default)]
+    known_screenshots: HashSet<String>,
+    /// Deletions made by the user since the last sync
+    #[serde(default)]
+    pending_deletes: Vec<Change>,
+    /// hash -> when it was deleted here
+    #[serde(default)]
+    tombstones: HashMap<String, String>,
+    #[serde(default)]
+    conflicts: Vec<SyncConflict>,
+    #[serde(default)]
+    last_sync: Option<String>,
+    /// Where the state was loaded from and is saved back to
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+fn state_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("cli-buddy")
+        .join("sync_state.json")
+}
+
+fn now() -> String {
+    Local::now().format(TIME_FORMAT).to_string()
+}
+
+fn parse_time(s: &str) -> Option<DateTime<chrono::FixedOffset>> {
+    DateTime::parse_from_str(s, TIME_FORMAT).ok()
+}
+
+fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+impl SyncState {
+    fn load(path: &Path) -> Self {
+        let state = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                match safe_file::backup_corrupt(path) {
+                    Ok(backup) => warn!(
+                        "Sync state is corrupt ({}); moved to {}",
+                        e,
+                        backup.display()
+                    ),
+                    Err(backup_err) => error!("Sync state is corrupt ({}): {}", e, backup_err),
+                }
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        Self {
+            path: path.to_path_buf(),
+            ..state
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        let cutoff = Local::now() - Duration::days(TOMBSTONE_DAYS);
+        self.tombstones
+            .retain(|_, at| parse_time(at).is_some_and(|t| t > cutoff));
+        let overflow = self.conflicts.len().saturating_sub(MAX_CONFLICTS);
+        self.conflicts.drain(..overflow);
+
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        safe_file::write_atomic(&self.path, content.as_bytes())
+    }
+
+    fn device_id(&mut self) -> String {
+        if self.device_id.is_empty() {
+            let mut seed = [0u8; 16];
+            OsRng.fill_bytes(&mut seed);
+            self.device_id = sha256_hash(&seed)[..16].to_string();
+        }
+        self.device_id.clone()
+    }
+
+    /// Whether a remote add made at `at` lost to a later local delete.
+    fn deleted_after(&self, hash: &str, at: &str) -> bool {
+        match (
+            self.tombstones.get(hash).and_then(|t| parse_time(t)),
+            parse_time(at),
+        ) {
+            (Some(deleted), Some(added)) => deleted >= added,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+
+    fn conflict(&mut self, report: &mut SyncReport, hash: &str, device: &str, reason: &str) {
+        self.conflicts.push(SyncConflict {
+            hash: hash.to_string(),
+            device: device.to_string(),
+            reason: reason.to_string(),
+            at: now(),
+        });
+        report.conflicts += 1;
+    }
+}
+
+/// Root of the sync layout inside the configured folder. Fails when sync is
+/// off or would copy encrypted history out as plaintext.
+fn sync_root(settings: &AppSettings) -> Result<PathBuf, String> {
+    if settings.sync_folder.trim().is_empty() {
+        return Err("Sync folder is not configured".to_string());
+    }
+    if settings.encrypt_at_rest {
+        return Err("Sync is unavailable while encryption at rest is enabled".to_string());
+    }
+    let folder = PathBuf::from(settings.sync_folder.trim());
+    if !folder.is_dir() {
+        return Err(format!("Sync folder not found: {}", folder.display()));
+    }
+    Ok(folder.join(SYNC_DIR))
+}
+
+fn remote_devices(root: &Path, own_id: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join("devices")) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|id| id != own_id)
+        .collect();
+    devices.sort();
+    devices
+}
+
+/// Complete lines of `path` after `offset`, each with the offset just past it.
+/// A trailing line without a newline is still being written and is left alone.
+fn read_log(path: &Path, offset: u64) -> Result<Vec<(Option<Change>, u64)>, String> {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    // A log shorter than what we read was replaced; changes are idempotent so start over
+    let offset = if offset > len { 0 } else { offset };
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while let Some(pos) = data[start..].iter().position(|&b| b == b'\n') {
+        let line = &data[start..start + pos];
+        start += pos + 1;
+        let change = if line.iter().all(u8::is_ascii_whitespace) {
+            None
+        } else {
+            match serde_json::from_slice(line) {
+                Ok(change) => Some(change),
+                Err(e) => {
+                    warn!("Skipping unreadable line in {}: {}", path.display(), e);
+                    None
+                }
+            }
+        };
+        lines.push((change, offset + start as u64));
+    }
+    Ok(lines)
+}
+
+fn append_log(path: &Path, changes: &[Change]) -> Result<(), String> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let mut data = Vec::new();
+    for change in changes {
+        serde_json::to_writer(&mut data, change).map_err(|e| e.to_string())?;
+        data.push(b'\n');
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    file.write_all(&data)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Record deletions made by the user so other machines drop the same entries.
+/// Entries removed by retention limits are not passed here and stay elsewhere.
+pub fn note_text_deleted(settings: &AppSettings, hashes: Vec<String>) {
+    note_deleted(settings, hashes, |hash, at| Change::DeleteText { hash, at });
+}
+
+pub fn note_screenshot_deleted(settings: &AppSettings, hash: String) {
+    note_deleted(settings, vec![hash], |hash, at| Change::DeleteScreenshot {
+        hash,
+        at,
+    });
+}
+
+fn note_deleted(
+    settings: &AppSettings,
+    hashes: Vec<String>,
+    change: impl Fn(String, String) -> Change,
+) {
+    if settings.sync_folder.trim().is_empty() || hashes.is_empty() {
+        return;
+    }
+    let Ok(_guard) = STATE_LOCK.lock() else {
+        return;
+    };
+    let mut state = SyncState::load(&state_path());
+    let at = now();
+    for hash in hashes {
+        state.tombstones.insert(hash.clone(), at.clone());
+        state.pending_deletes.push(change(hash, at.clone()));
+    }
+    if let Err(e) = state.save() {
+        error!("Failed to record sync deletion: {}", e);
+    }
+}
+
+/// Apply other devices' changes, then publish local ones.
+pub fn run(
+    settings: &AppSettings,
+    text_history: &Mutex<TextHistory>,
+) -> Result<SyncReport, String> {
+    let root = sync_root(settings)?;
+    let _guard = STATE_LOCK
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let mut state = SyncState::load(&state_path());
+    exchange(&root, &mut state, &FileManager::new(settings), text_history)
+}
+
+/// Sync with the layout under `root`, saving `state` once done.
+fn exchange(
+    root: &Path,
+    state: &mut SyncState,
+    file_manager: &FileManager,
+    text_history: &Mutex<TextHistory>,
+) -> Result<SyncReport, String> {
+    let device_id = state.device_id();
+    let objects = root.join("objects");
+    let mut report = SyncReport::default();
+
+    // Hashing screenshots can take a while; do it before taking the history lock
+    let mut screenshots = file_manager.hashed_screenshots()?;
+    let mut th = text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let since_seq = th.last_seq();
+
+    for device in remote_devices(root, &device_id) {
+        let log_path = root.join("devices").join(&device).join(LOG_NAME);
+        let offset = state.offsets.get(&device).copied().unwrap_or(0);
+        let mut new_offset = offset;
+        for (change, end) in read_log(&log_path, offset)? {
+            if let Some(change) = change {
+                let ctx = ApplyContext {
+                    device: &device,
+                    objects: &objects,
+                    file_manager,
+                };
+                if !apply(&ctx, change, state, &mut th, &mut screenshots, &mut report)? {
+                    // Screenshot data has not arrived yet; retry from here next time
+                    break;
+                }
+            }
+            new_offset = end;
+        }
+        state.offsets.insert(device, new_offset);
+    }
+    if report.applied > 0 {
+        th.finish_import(since_seq)?;
+    }
+
+    let mut changes = Vec::new();
+    let current_text = th.shareable_hashes();
+    for hash in current_text.difference(&state.known_text) {
+        if let Some(entry) = th.export_entry(hash)? {
+            changes.push(Change::AddText {
+                hash: hash.clone(),
+                at: entry
+                    .last_used_at
+                    .clone()
+                    .unwrap_or_else(|| entry.created_at.clone()),
+                entry: Box::new(entry),
+            });
+        }
+    }
+    drop(th);
+
+    for (hash, info) in &screenshots {
+        if state.known_screenshots.contains(hash) {
+            continue;
+        }
+        // The object goes first so a logged add always has its data
+        let object = objects.join(hash);
+        if !object.exists() {
+            safe_file::write_atomic(&object, &file_manager.get_image_data(&info.filename)?)?;
+        }
+        changes.push(Change::AddScreenshot {
+            hash: hash.clone(),
+            at: info.created_at.clone(),
+            filename: info.filename.clone(),
+        });
+    }
+    changes.append(&mut state.pending_deletes);
+
+    let own_dir = root.join("devices").join(&device_id);
+    if let Err(e) = append_log(&own_dir.join(LOG_NAME), &changes) {
+        // Keep the deletions for the next attempt; additions are found again by diffing
+        state.pending_deletes = changes
+            .into_iter()
+            .filter(|c| {
+                matches!(
+                    c,
+                    Change::DeleteText { .. } | Change::DeleteScreenshot { .. }
+                )
+            })
+            .collect();
+        let _ = state.save();
+        return Err(e);
+    }
+    report.published = changes.len() as u32;
+
+    state.known_text = current_text;
+    state.known_screenshots = screenshots.into_keys().collect();
+    let synced_at = now();
+    state.last_sync = Some(synced_at.clone());
+    let info = SyncDevice {
+        id: device_id,
+        name: device_name(),
+        last_sync: Some(synced_at),
+    };
+    let info = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+    safe_file::write_atomic(&own_dir.join(DEVICE_INFO_NAME), info.as_bytes())?;
+    state.save()?;
+    Ok(report)
+}
+
+struct ApplyContext<'a> {
+    device: &'a str,
+    objects: &'a Path,
+    file_manager: &'a FileManager,
+}
+
+/// Apply one remote change. Returns false when it cannot be applied yet.
+fn apply(
+    ctx: &ApplyContext,
+    change: Change,
+    state: &mut SyncState,
+    th: &mut TextHistory,
+    screenshots: &mut HashMap<String, ScreenshotInfo>,
+    report: &mut SyncReport,
+) -> Result<bool, String> {
+    match change {
+        Change::AddText { hash, at, entry } => {
+            if state.deleted_after(&hash, &at) {
+                state.conflict(report, &hash, ctx.device, "added there, deleted here later");
+            } else if th.import_entry(&entry)? {
+                report.applied += 1;
+            }
+            state.known_text.insert(hash);
+        }
+        Change::DeleteText { hash, .. } => {
+            // A kept entry stays known so it is not published back as new
+            if th.pinned_by_hash(&hash) == Some(true) {
+                state.conflict(report, &hash, ctx.device, "deleted there, pinned here");
+            } else {
+                if th.delete_by_hash(&hash) {
+                    report.applied += 1;
+                }
+                state.known_text.remove(&hash);
+            }
+        }
+        Change::AddScreenshot { hash, at, filename } => {
+            if state.deleted_after(&hash, &at) {
+                state.conflict(report, &hash, ctx.device, "added there, deleted here later");
+            } else if !screenshots.contains_key(&hash) {
+                let data = match fs::read(ctx.objects.join(&hash)) {
+                    Ok(data) => data,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+                    Err(e) => return Err(format!("Failed to read synced screenshot: {}", e)),
+                };
+                if sha256_hash(&data) != hash {
+                    // Partially copied by the file sync tool; try again later
+                    return Ok(false);
+                }
+                let info = ctx.file_manager.import_screenshot(&filename, &data)?;
+                th.record_screenshot(&info);
+                screenshots.insert(hash.clone(), info);
+                report.applied += 1;
+            }
+            state.known_screenshots.insert(hash);
+        }
+        Change::DeleteScreenshot { hash, .. } => match screenshots.get(&hash) {
+            Some(info) if info.pinned => {
+                state.conflict(report, &hash, ctx.device, "deleted there, pinned here");
+            }
+            Some(info) => {
+                ctx.file_manager.delete_screenshot(
+                    &info.filename,
+                    trash::new_batch(),
+                    trash::REASON_SYNC,
+                )?;
+                th.forget_screenshot(&info.filename);
+                screenshots.remove(&hash);
+                state.known_screenshots.remove(&hash);
+                report.applied += 1;
+            }
+            None => {
+                state.known_screenshots.remove(&hash);
+            }
+        },
+    }
+    Ok(true)
+}
+
+/// Pending work in both directions plus recent conflicts.
+pub fn status(
+    settings: &AppSettings,
+    text_history: &Mutex<TextHistory>,
+) -> Result<SyncStatus, String> {
+    let mut status = SyncStatus {
+        folder: settings.sync_folder.clone(),
+        ..SyncStatus::default()
+    };
+    if settings.sync_folder.trim().is_empty() {
+        return Ok(status);
+    }
+    let root = sync_root(settings)?;
+    status.enabled = true;
+
+    let screenshots = FileManager::new(settings).hashed_screenshots()?;
+    let _guard = STATE_LOCK
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    let mut state = SyncState::load(&state_path());
+    if state.device_id.is_empty() {
+        state.device_id();
+        state.save()?;
+    }
+    status.device_id = state.device_id.clone();
+    status.last_sync = state.last_sync.clone();
+    status.conflicts = state.conflicts.clone();
+
+    let text = text_history
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .shareable_hashes();
+    let new_text = text.difference(&state.known_text).count();
+    let new_screenshots = screenshots
+        .keys()
+        .filter(|h| !state.known_screenshots.contains(*h))
+        .count();
+    status.pending_local = (new_text + new_screenshots + state.pending_deletes.len()) as u32;
+
+    for device in remote_devices(&root, &status.device_id) {
+        let dir = root.join("devices").join(&device);
+        let offset = state.offsets.get(&device).copied().unwrap_or(0);
+        let pending = read_log(&dir.join(LOG_NAME), offset)?
+            .iter()
+            .filter(|(change, _)| change.is_some())
+            .count();
+        status.pending_remote += pending as u32;
+
+        let info = fs::read_to_string(dir.join(DEVICE_INFO_NAME))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or(SyncDevice {
+                id: device.clone(),
+                name: device,
+                last_sync: None,
+            });
+        status.devices.push(info);
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    /// One machine sharing the sync folder, with its own library and state.
+    struct Device {
+        state: SyncState,
+        file_manager: FileManager,
+        history: Mutex<TextHistory>,
+    }
+
+    impl Device {
+        fn new(dir: &Path, name: &str) -> Self {
+            let home = dir.join(name);
+            let settings = AppSettings {
+                save_directory: home.join("shots").to_string_lossy().to_string(),
+                ..Default::default()
+            };
+            Self {
+                state: SyncState::load(&home.join("sync_state.json")),
+                file_manager: FileManager::new(&settings),
+                history: Mutex::new(TextHistory::in_memory(home.join("blobs"))),
+            }
+        }
+
+        fn sync(&mut self, root: &Path) -> SyncReport {
+            exchange(root, &mut self.state, &self.file_manager, &self.history).unwrap()
+        }
+
+        fn add(&self, text: &str) -> String {
+            self.history.lock().unwrap().add_entry(text).unwrap().id
+        }
+
+        /// Delete as the user would, leaving the deletion to be published.
+        fn delete(&mut self, id: &str) {
+            let mut th = self.history.lock().unwrap();
+            let hash = th.hash_of(id).unwrap();
+            assert!(th.delete_entry(id));
+            self.state.tombstones.insert(hash.clone(), now());
+            self.state
+                .pending_deletes
+                .push(Change::DeleteText { hash, at: now() });
+        }
+
+        fn texts(&self) -> Vec<String> {
+            let mut texts: Vec<String> = self
+                .history
+                .lock()
+                .unwrap()
+                .get_entries(None)
+                .into_iter()
+                .map(|e| e.content)
+                .collect();
+            texts.sort();
+            texts
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cli-buddy-sync-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn devices_converge() {
+        let dir = temp_dir("converge");
+        let root = dir.join(SYNC_DIR);
+        let mut a = Device::new(&dir, "a");
+        let mut b = Device::new(&dir, "b");
+        a.add("shared");
+        a.add("only a");
+        b.add("shared");
+        b.add("only b");
+
+        assert_eq!(a.sync(&root).published, 2);
+        // "shared" is merged by its hash rather than added twice
+        let report = b.sync(&root);
+        assert_eq!((report.applied, report.published), (1, 1));
+        let report = a.sync(&root);
+        assert_eq!((report.applied, report.published), (1, 0));
+
+        let expected = ["only a", "only b", "shared"];
+        assert_eq!(a.texts(), expected);
+        assert_eq!(b.texts(), expected);
+        for device in [&mut a, &mut b] {
+            let report = device.sync(&root);
+            assert_eq!(
+                (report.applied, report.published, report.conflicts),
+                (0, 0, 0)
+            );
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let dir = temp_dir("conflicts");
+        let root = dir.join(SYNC_DIR);
+        let mut a = Device::new(&dir, "a");
+        let mut b = Device::new(&dir, "b");
+        let kept = a.add("pinned on a");
+        a.add("unpinned");
+        a.sync(&root);
+        b.sync(&root);
+
+        // Deleted on b, pinned on a in the meantime
+        let ids: HashMap<String, String> = b
+            .history
+            .lock()
+            .unwrap()
+            .get_entries(None)
+            .into_iter()
+            .map(|e| (e.content, e.id))
+            .collect();
+        b.delete(&ids["pinned on a"]);
+        b.delete(&ids["unpinned"]);
+        a.history.lock().unwrap().set_pinned(&kept, true);
+        assert_eq!(b.sync(&root).published, 2);
+
+        let report = a.sync(&root);
+        assert_eq!((report.applied, report.conflicts), (1, 1));
+        assert_eq!(a.texts(), ["pinned on a"]);
+        let conflict = a.state.conflicts.last().unwrap();
+        assert_eq!(conflict.reason, "deleted there, pinned here");
+        assert_eq!(conflict.device, b.state.device_id);
+
+        // Added on a after b deleted its own copy
+        let id = b.add("deleted on b");
+        b.delete(&id);
+        a.add("deleted on b");
+        a.sync(&root);
+        let report = b.sync(&root);
+        assert_eq!((report.applied, report.conflicts), (0, 1));
+        assert_eq!(
+            b.state.conflicts.last().unwrap().reason,
+            "added there, deleted here later"
+        );
+        assert!(b.texts().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn waits_for_screenshot_data() {
+        let dir = temp_dir("objects");
+        let root = dir.join(SYNC_DIR);
+        let mut a = Device::new(&dir, "a");
+        let mut b = Device::new(&dir, "b");
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([1, 2, 3, 255])));
+        a.file_manager.save_screenshot(&image).unwrap();
+        assert_eq!(a.sync(&root).published, 1);
+
+        let object = fs::read_dir(root.join("objects"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let data = fs::read(&object).unwrap();
+        let a_id = a.state.device_id.clone();
+
+        // Not copied over yet, then only partly
+        fs::remove_file(&object).unwrap();
+        assert_eq!(b.sync(&root).applied, 0);
+        fs::write(&object, &data[..data.len() / 2]).unwrap();
+        assert_eq!(b.sync(&root).applied, 0);
+        assert!(b.file_manager.scan_screenshots().unwrap().is_empty());
+        assert_eq!(b.state.offsets[&a_id], 0);
+
+        fs::write(&object, &data).unwrap();
+        assert_eq!(b.sync(&root).applied, 1);
+        assert_eq!(
+            b.file_manager.content_hashes().unwrap(),
+            a.file_manager.content_hashes().unwrap()
+        );
+        assert!(b.state.offsets[&a_id] > 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_only_complete_lines() {
+        let dir = temp_dir("log");
+        let path = dir.join(LOG_NAME);
+        assert!(read_log(&path, 0).unwrap().is_empty());
+
+        let change = |hash: &str| Change::DeleteText {
+            hash: hash.to_string(),
+            at: now(),
+        };
+        append_log(&path, &[change("h1")]).unwrap();
+        let first_end = fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"op":"delete_text","hash":"h2""#)
+            .unwrap();
+
+        // The half-written line is left for the next read
+        let lines = read_log(&path, 0).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].1, first_end);
+
+        file.write_all(b",\"at\":\"\"}\nnot json\n").unwrap();
+        let lines = read_log(&path, first_end).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(&lines[0].0, Some(Change::DeleteText { hash, .. }) if hash == "h2"));
+        // An unreadable line is skipped but still read past
+        assert!(lines[1].0.is_none());
+        assert_eq!(lines[1].1, fs::metadata(&path).unwrap().len());
+
+        // A log shorter than the saved offset was replaced and is read again
+        fs::remove_file(&path).unwrap();
+        append_log(&path, &[change("h3")]).unwrap();
+        let lines = read_log(&path, first_end * 10).unwrap();
+        assert_eq!(lines.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,25 +1,164 @@
+use crate::storage::blob_store::BlobStore;
+use crate::storage::classifier;
+use crate::storage::crypto;
+use crate::storage::database;
+use crate::storage::file_manager::ScreenshotInfo;
 use crate::storage::hash::sha256_hash;
-use chrono::Local;
+use crate::storage::trash::{self, TrashItem, REASON_DELETED, REASON_RETENTION, REASON_SYNC};
+use chrono::{DateTime, Local};
+use log::error;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
-const MAX_TEXT_BYTES: usize = 10 * 1024; // 10KB
+const DEFAULT_INLINE_TEXT_BYTES: usize = 10 * 1024; // 10KB
+const DEFAULT_MAX_TEXT_BYTES: usize = 32 * 1024 * 1024; // 32MB
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+/// Only the start of very large clips is looked at when classifying
+const MAX_CLASSIFY_BYTES: usize = 1024 * 1024;
+
+const TEXT_COLUMNS: &str =
+    "id, content, preview, hash, created_at, char_count, pinned, encrypted, sensitive, expires_at, blob, kind, kind_meta, use_count, last_used_at, format, screenshot";
+/// Every column but `seq`, for moving rows to and from `trash_text_entries`
+const ENTRY_COLUMNS: &str =
+    "id, content, preview, hash, created_at, char_count, pinned, encrypted, sensitive, expires_at, blob, kind, kind_meta, use_count, last_used_at, format, html, screenshot";
+/// `TEXT_COLUMNS` with the content of anything but file lists left out, for
+/// listings that only show previews
+const PREVIEW_COLUMNS: &str =
+    "id, CASE WHEN format = 'files' THEN content ELSE '' END, preview, hash, created_at, char_count, pinned, encrypted, sensitive, expires_at, blob, kind, kind_meta, use_count, last_used_at, format, screenshot";
+/// Frecency score of each row: uses weighted by how many days ago it was
+/// last touched, in the same steps as the clipboard listing uses for
+/// screenshots. Timestamps are stored with a `+hhmm` offset, which
+/// `julianday` only reads as `+hh:mm`.
+const FRECENCY_SCORE: &str = "(use_count + 1) * CASE
+        WHEN age < 1 THEN 100 WHEN age < 4 THEN 70 WHEN age < 14 THEN 50
+        WHEN age < 31 THEN 30 WHEN age < 90 THEN 10 ELSE 1
+    END";
+const ENTRY_AGE: &str = "julianday('now') - julianday(CASE WHEN length(touched) = 24
+        THEN substr(touched, 1, 22) || ':' || substr(touched, 23) ELSE touched END)";
+const LOCKED_PREVIEW: &str = "(encrypted)";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextEntry {
     pub id: String,
+    /// Empty in `list_page` results, except for file lists
     pub content: String,
     pub preview: String,
     pub hash: String,
     pub created_at: String,
     pub char_count: usize,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Set when secret detection flagged the copied text
+    #[serde(default)]
+    pub sensitive: bool,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// `content` holds only the start of the clip; the full text is in a blob
+    #[serde(default)]
+    pub truncated: bool,
+    /// Content type from `classifier::KINDS`
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub kind_meta: BTreeMap<String, String>,
+    /// Times pasted back from history
+    #[serde(default)]
+    pub use_count: u32,
+    /// Last re-copy or paste; entries move to the top when this is set
+    #[serde(default)]
+    pub last_used_at: Option<String>,
+    /// Clipboard format restored on paste: "text", "html" or "files"
+    /// (`content` then lists one path per line)
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Markup of an "html" entry; only filled in for export and paste
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Filename of the screenshot copied together with this text; pasting
+    /// the entry restores both
+    #[serde(default)]
+    pub screenshot: Option<String>,
+}
+
+fn default_kind() -> String {
+    "prose".to_string()
+}
+
+fn default_format() -> String {
+    "text".to_string()
+}
+
+/// Bytes an entry is deduplicated by. Plain text hashes as itself, so hashes
+/// of existing entries stay valid; other formats include the format and markup.
+pub fn hash_input(format: &str, content: &str, html: Option<&str>) -> Vec<u8> {
+    if format == "text" {
+        return content.as_bytes().to_vec();
+    }
+    [format, content, html.unwrap_or_default()]
+        .join("\0")
+        .into_bytes()
+}
+
+/// Extra attributes attached to an entry at capture time.
+#[derive(Debug, Clone, Default)]
+pub struct EntryOptions {
+    pub sensitive: bool,
+    /// Unix timestamp after which the entry is purged
+    pub expires_at: Option<i64>,
+    /// Markup captured alongside the text; the entry is restored as HTML
+    pub html: Option<String>,
+    /// The text is a list of copied files, one path per line
+    pub files: bool,
+    /// Screenshot saved from the same clipboard contents
+    pub screenshot: Option<String>,
+}
+
+/// Cut `s` to at most `max` bytes without splitting a character.
+fn truncate_to_char_boundary(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+fn format_timestamp(ts: i64) -> Option<String> {
+    DateTime::from_timestamp(ts, 0).map(|dt| {
+        dt.with_timezone(&Local)
+            .format("%Y-%m-%dT%H:%M:%S%z")
+            .to_string()
+    })
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchFilters {
+    /// "text" or "image"; both when unset
+    pub entry_type: Option<String>,
+    /// Inclusive `created_at` bounds, same format as the entries
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// Only text entries of this kind
+    pub kind: Option<String>,
+    pub limit: Option<u32>,
+}
+
+pub enum SearchHit {
+    Text(Box<TextEntry>),
+    Screenshot(String),
 }
 
 pub struct TextHistory {
-    entries: Vec<TextEntry>,
-    file_path: PathBuf,
+    conn: Connection,
+    blobs: BlobStore,
     max_entries: usize,
+    encrypt: bool,
+    inline_text_bytes: usize,
+    max_text_bytes: usize,
 }
 
 impl TextHistory {
@@ -27,100 +166,1650 @@ impl TextHistory {
         let config_dir = dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("cli-buddy");
-        let file_path = config_dir.join("clipboard_history.json");
 
-        let mut history = Self {
-            entries: Vec::new(),
-            file_path,
+        let mut conn = match database::open(&config_dir.join("history.db")) {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("{}; history will not be persisted", e);
+                database::open_in_memory()
+            }
+        };
+        database::import_legacy_json(&mut conn, &config_dir.join("clipboard_history.json"));
+
+        let history = Self {
+            conn,
+            blobs: BlobStore::new(config_dir.join("blobs")),
             max_entries,
+            encrypt: false,
+            inline_text_bytes: DEFAULT_INLINE_TEXT_BYTES,
+            max_text_bytes: DEFAULT_MAX_TEXT_BYTES,
         };
-        history.load();
+        history.classify_pending();
         history
     }
 
-    fn load(&mut self) {
-        if self.file_path.exists() {
-            if let Ok(content) = fs::read_to_string(&self.file_path) {
-                if let Ok(entries) = serde_json::from_str::<Vec<TextEntry>>(&content) {
-                    self.entries = entries;
-                }
-            }
+    /// History in a fresh in-memory database, for tests.
+    #[cfg(test)]
+    pub fn in_memory(blob_dir: PathBuf) -> Self {
+        Self {
+            conn: database::open_in_memory(),
+            blobs: BlobStore::new(blob_dir),
+            max_entries: 50,
+            encrypt: false,
+            inline_text_bytes: DEFAULT_INLINE_TEXT_BYTES,
+            max_text_bytes: DEFAULT_MAX_TEXT_BYTES,
         }
     }
 
-    fn save(&self) {
-        if let Some(parent) = self.file_path.parent() {
-            let _ = fs::create_dir_all(parent);
+    /// Classify plaintext rows stored before classification existed.
+    /// Encrypted ones are left as NULL and read back as prose.
+    fn classify_pending(&self) {
+        let result = (|| -> rusqlite::Result<usize> {
+            let rows: Vec<(i64, String)> = {
+                let mut stmt = self.conn.prepare(
+                    "SELECT seq, content FROM text_entries WHERE kind IS NULL AND encrypted = 0",
+                )?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            for (seq, content) in &rows {
+                let class = classifier::classify(content);
+                self.conn.execute(
+                    "UPDATE text_entries SET kind = ?1, kind_meta = ?2 WHERE seq = ?3",
+                    params![
+                        class.kind,
+                        serde_json::to_string(&class.meta).unwrap_or_default(),
+                        seq
+                    ],
+                )?;
+            }
+            Ok(rows.len())
+        })();
+
+        if let Err(e) = result {
+            error!("Failed to classify text history: {}", e);
         }
-        if let Ok(content) = serde_json::to_string_pretty(&self.entries) {
-            let _ = fs::write(&self.file_path, content);
+    }
+
+    /// Encrypted rows are decrypted with the session key; while locked they
+    /// come back with empty content and a placeholder preview.
+    fn entry_from_row(row: &Row) -> rusqlite::Result<TextEntry> {
+        let mut content: String = row.get(1)?;
+        let mut preview: String = row.get(2)?;
+        let mut kind_meta: Option<String> = row.get(12)?;
+        if row.get::<_, bool>(7)? {
+            // Listings leave the content out
+            let plain_content = if content.is_empty() {
+                Ok(String::new())
+            } else {
+                crypto::decrypt_string(&content)
+            };
+            match (plain_content, crypto::decrypt_string(&preview)) {
+                (Ok(c), Ok(p)) => {
+                    content = c;
+                    preview = p;
+                    kind_meta = kind_meta.and_then(|m| crypto::decrypt_string(&m).ok());
+                }
+                _ => {
+                    content = String::new();
+                    preview = LOCKED_PREVIEW.to_string();
+                    kind_meta = None;
+                }
+            }
         }
+
+        Ok(TextEntry {
+            id: row.get(0)?,
+            content,
+            preview,
+            hash: row.get(3)?,
+            created_at: row.get(4)?,
+            char_count: row.get::<_, i64>(5)? as usize,
+            pinned: row.get(6)?,
+            sensitive: row.get(8)?,
+            expires_at: row.get::<_, Option<i64>>(9)?.and_then(format_timestamp),
+            truncated: row.get::<_, Option<String>>(10)?.is_some(),
+            kind: row.get::<_, Option<String>>(11)?.unwrap_or_else(default_kind),
+            kind_meta: kind_meta
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .unwrap_or_default(),
+            use_count: row.get::<_, i64>(13)? as u32,
+            last_used_at: row.get(14)?,
+            format: row.get(15)?,
+            html: None,
+            screenshot: row.get(16)?,
+        })
     }
 
     pub fn add_entry(&mut self, text: &str) -> Option<TextEntry> {
-        // Truncate if over 10KB
-        let content = if text.len() > MAX_TEXT_BYTES {
-            &text[..MAX_TEXT_BYTES]
+        self.add_entry_with(text, EntryOptions::default())
+    }
+
+    /// Apply the configured size cap without splitting a character.
+    fn clamp<'a>(&self, text: &'a str) -> &'a str {
+        if self.max_text_bytes > 0 {
+            truncate_to_char_boundary(text, self.max_text_bytes)
         } else {
             text
+        }
+    }
+
+    fn content_hash(&self, format: &str, content: &str, html: Option<&str>) -> Result<String, String> {
+        let input = hash_input(format, content, html);
+        if self.encrypt {
+            crypto::keyed_hash(&input)
+        } else {
+            Ok(sha256_hash(&input))
+        }
+    }
+
+    /// Hash `add_entry_with` would store this capture under, so a watcher can
+    /// compare what it sees with `last_hash`.
+    pub fn capture_hash(
+        &self,
+        format: &str,
+        text: &str,
+        html: Option<&str>,
+    ) -> Result<String, String> {
+        self.content_hash(format, self.clamp(text), html.map(|h| self.clamp(h)))
+    }
+
+    fn find_by_hash(&self, hash: &str) -> Option<i64> {
+        self.conn
+            .query_row(
+                "SELECT seq FROM text_entries WHERE hash = ?1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)
+    }
+
+    pub fn add_entry_with(&mut self, text: &str, options: EntryOptions) -> Option<TextEntry> {
+        self.purge_expired();
+
+        let content = self.clamp(text);
+        let html = options.html.as_deref().map(|h| self.clamp(h));
+        let format = match (options.files, html) {
+            (true, _) => "files",
+            (false, Some(_)) => "html",
+            (false, None) => "text",
+        };
+        let hash = match self.content_hash(format, content, html) {
+            Ok(h) => h,
+            Err(e) => {
+                error!("Not recording text entry: {}", e);
+                return None;
+            }
+        };
+
+        // Copying something already in history moves it to the top, linked to
+        // whatever image came with it this time
+        if let Some(seq) = self.find_by_hash(&hash) {
+            if let Err(e) = self.conn.execute(
+                "UPDATE text_entries SET screenshot = ?1 WHERE seq = ?2",
+                params![options.screenshot, seq],
+            ) {
+                error!("Failed to update entry screenshot: {}", e);
+            }
+            return self.promote(seq, false);
+        }
+
+        let now = Local::now();
+        let entry = TextEntry {
+            id: format!("text_{}", now.format("%Y-%m-%d_%H-%M-%S_%3f")),
+            content: content.to_string(),
+            preview: String::new(),
+            hash,
+            created_at: now.format("%Y-%m-%dT%H:%M:%S%z").to_string(),
+            char_count: 0,
+            pinned: false,
+            sensitive: options.sensitive,
+            expires_at: None,
+            truncated: false,
+            kind: default_kind(),
+            kind_meta: BTreeMap::new(),
+            use_count: 0,
+            last_used_at: None,
+            format: format.to_string(),
+            html: html.map(str::to_string),
+            screenshot: options.screenshot,
+        };
+
+        match self.insert(entry, options.expires_at) {
+            Ok(entry) => {
+                self.enforce_max_entries();
+                Some(entry)
+            }
+            Err(e) => {
+                error!("Failed to save text entry: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Store `entry.content` as a new row. Identity and usage fields are kept
+    /// as given; preview, size, kind and blob are derived from the content.
+    fn insert(&self, mut entry: TextEntry, expires_at: Option<i64>) -> Result<TextEntry, String> {
+        let content = std::mem::take(&mut entry.content);
+        entry.id = self.unused_id(&entry.id);
+
+        // Oversized clips go to a blob named by their hash; the row keeps the start
+        let blob = if content.len() > self.inline_text_bytes {
+            let data = if self.encrypt {
+                crypto::encrypt(content.as_bytes())?
+            } else {
+                content.as_bytes().to_vec()
+            };
+            self.blobs.put(&entry.hash, &data)?;
+            Some(entry.hash.clone())
+        } else {
+            None
+        };
+        let class = classifier::classify(truncate_to_char_boundary(&content, MAX_CLASSIFY_BYTES));
+
+        entry.content = truncate_to_char_boundary(&content, self.inline_text_bytes).to_string();
+        entry.preview = content.chars().take(100).collect();
+        entry.char_count = content.chars().count();
+        entry.expires_at = expires_at.and_then(format_timestamp);
+        entry.truncated = blob.is_some();
+        entry.kind = class.kind;
+        entry.kind_meta = class.meta;
+
+        let kind_meta = serde_json::to_string(&entry.kind_meta).unwrap_or_default();
+        let html = entry.html.take().filter(|_| entry.format == "html");
+        let (stored_content, stored_preview, stored_meta, stored_html) = if self.encrypt {
+            (
+                crypto::encrypt_string(&entry.content)?,
+                crypto::encrypt_string(&entry.preview)?,
+                crypto::encrypt_string(&kind_meta)?,
+                html.as_deref().map(crypto::encrypt_string).transpose()?,
+            )
+        } else {
+            (entry.content.clone(), entry.preview.clone(), kind_meta, html)
         };
 
-        let hash = sha256_hash(content.as_bytes());
+        self.conn
+            .execute(
+                "INSERT INTO text_entries
+                    (id, content, preview, hash, created_at, char_count, pinned, encrypted,
+                     sensitive, expires_at, blob, kind, kind_meta, use_count, last_used_at,
+                     format, html, screenshot)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                         ?16, ?17, ?18)",
+                params![
+                    entry.id,
+                    stored_content,
+                    stored_preview,
+                    entry.hash,
+                    entry.created_at,
+                    entry.char_count as i64,
+                    entry.pinned,
+                    self.encrypt,
+                    entry.sensitive,
+                    expires_at,
+                    blob,
+                    entry.kind,
+                    stored_meta,
+                    entry.use_count,
+                    entry.last_used_at,
+                    entry.format,
+                    stored_html,
+                    entry.screenshot
+                ],
+            )
+            .map_err(|e| e.to_string())?;
 
-        // Deduplicate
-        if self.entries.iter().any(|e| e.hash == hash) {
-            return None;
+        Ok(entry)
+    }
+
+    /// `id`, or `id` with `_2`, `_3`, ... appended if another entry has it, as
+    /// entries copied within the same millisecond or imported from elsewhere may.
+    fn unused_id(&self, id: &str) -> String {
+        let taken = |candidate: &str| {
+            self.conn
+                .query_row(
+                    "SELECT 1 FROM text_entries WHERE id = ?1",
+                    params![candidate],
+                    |_| Ok(()),
+                )
+                .optional()
+                .unwrap_or(None)
+                .is_some()
+        };
+        let mut candidate = id.to_string();
+        let mut n = 2;
+        while taken(&candidate) {
+            candidate = format!("{}_{}", id, n);
+            n += 1;
         }
+        candidate
+    }
 
-        let preview: String = content.chars().take(100).collect();
-        let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-        let id = format!("text_{}", timestamp);
+    /// Merge an entry exported from another machine. Returns false when the same
+    /// content is already in history or the entry has expired.
+    pub fn import_entry(&mut self, entry: &TextEntry) -> Result<bool, String> {
+        let content = self.clamp(&entry.content);
+        let format = match entry.format.as_str() {
+            "html" if entry.html.is_some() => "html",
+            "files" => "files",
+            _ => "text",
+        };
+        let html = entry.html.as_deref().filter(|_| format == "html");
+        let hash = self.content_hash(format, content, html)?;
+        if self.find_by_hash(&hash).is_some() {
+            return Ok(false);
+        }
+
+        let expires_at = entry
+            .expires_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S%z").ok())
+            .map(|t| t.timestamp());
+        if expires_at.is_some_and(|t| t <= Local::now().timestamp()) {
+            return Ok(false);
+        }
 
         let entry = TextEntry {
-            id,
             content: content.to_string(),
-            preview,
             hash,
-            created_at: Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string(),
-            char_count: content.chars().count(),
+            format: format.to_string(),
+            html: html.map(|h| self.clamp(h).to_string()),
+            ..entry.clone()
         };
+        self.insert(entry, expires_at)
+            .map_err(|e| format!("Failed to import text entry: {}", e))?;
+        Ok(true)
+    }
 
-        self.entries.insert(0, entry.clone());
+    /// Highest seq in use. Entries imported afterwards get larger ones; pass
+    /// it to `finish_import` once they are in.
+    pub fn last_seq(&self) -> i64 {
+        self.conn
+            .query_row(
+                "SELECT COALESCE(MAX(seq), 0) FROM text_entries",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(0)
+    }
 
-        // Enforce max entries
-        if self.entries.len() > self.max_entries {
-            self.entries.truncate(self.max_entries);
+    /// Renumber entries imported after `since_seq` by when they were last
+    /// copied or used, so they interleave with local history, then apply the
+    /// entry limit. Only the imported entries and local ones newer than the
+    /// oldest of them are moved.
+    pub fn finish_import(&mut self, since_seq: i64) -> Result<(), String> {
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = self.conn.transaction()?;
+            let oldest: Option<String> = tx.query_row(
+                "SELECT MIN(COALESCE(last_used_at, created_at)) FROM text_entries
+                 WHERE seq > ?1",
+                params![since_seq],
+                |row| row.get(0),
+            )?;
+            let Some(oldest) = oldest else {
+                return Ok(());
+            };
+            let seqs: Vec<i64> = {
+                let mut stmt = tx.prepare(
+                    "SELECT seq FROM text_entries
+                     WHERE seq > ?1 OR COALESCE(last_used_at, created_at) >= ?2
+                     ORDER BY COALESCE(last_used_at, created_at), seq",
+                )?;
+                let rows = stmt.query_map(params![since_seq, oldest], |row| row.get(0))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            let base: i64 = tx.query_row(
+                "SELECT COALESCE(MAX(seq), 0) + 1 FROM text_entries",
+                [],
+                |row| row.get(0),
+            )?;
+            for (i, seq) in seqs.iter().enumerate() {
+                tx.execute(
+                    "UPDATE text_entries SET seq = ?1 WHERE seq = ?2",
+                    params![base + i as i64, seq],
+                )?;
+            }
+            tx.commit()
+        })();
+        result.map_err(|e| format!("Failed to reorder history: {}", e))?;
+
+        self.enforce_max_entries();
+        Ok(())
+    }
+
+    /// Give an entry the newest seq so it sorts first, stamping `last_used_at`
+    /// and counting a use when `used` is set.
+    fn promote(&mut self, seq: i64, used: bool) -> Option<TextEntry> {
+        let now = Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string();
+        let sql = format!(
+            "SELECT {} FROM text_entries ORDER BY seq DESC LIMIT 1",
+            TEXT_COLUMNS
+        );
+        let result = (|| -> rusqlite::Result<TextEntry> {
+            let tx = self.conn.transaction()?;
+            tx.execute(
+                "UPDATE text_entries
+                 SET seq = (SELECT MAX(seq) + 1 FROM text_entries),
+                     last_used_at = ?1,
+                     use_count = use_count + ?2
+                 WHERE seq = ?3",
+                params![now, used as i64, seq],
+            )?;
+            let entry = tx.query_row(&sql, [], Self::entry_from_row)?;
+            tx.commit()?;
+            Ok(entry)
+        })();
+
+        result
+            .map_err(|e| error!("Failed to promote text entry: {}", e))
+            .ok()
+    }
+
+    /// Record a paste of entry `id` from history.
+    pub fn record_use(&mut self, id: &str) -> Option<TextEntry> {
+        let seq: i64 = self
+            .conn
+            .query_row(
+                "SELECT seq FROM text_entries WHERE id = ?1 ORDER BY seq DESC LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)?;
+        self.promote(seq, true)
+    }
+
+    pub fn record_screenshot_use(&mut self, filename: &str) {
+        if let Err(e) = self.conn.execute(
+            "UPDATE screenshots SET use_count = use_count + 1, last_used_at = ?1
+             WHERE filename = ?2",
+            params![Local::now().format("%Y-%m-%dT%H:%M:%S%z").to_string(), filename],
+        ) {
+            error!("Failed to record use of {}: {}", filename, e);
         }
+    }
 
-        self.save();
-        Some(entry)
+    /// `(use_count, last_used_at)` for every screenshot that has been used.
+    pub fn screenshot_usage(&self) -> HashMap<String, (u32, Option<String>)> {
+        let result = self
+            .conn
+            .prepare(
+                "SELECT filename, use_count, last_used_at FROM screenshots
+                 WHERE last_used_at IS NOT NULL",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok((row.get(0)?, (row.get::<_, i64>(1)? as u32, row.get(2)?)))
+                })?
+                .collect::<rusqlite::Result<HashMap<_, _>>>()
+            });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read screenshot usage: {}", e);
+            HashMap::new()
+        })
     }
 
-    pub fn get_entries(&self) -> &[TextEntry] {
-        &self.entries
+    /// Move the oldest unpinned entries to the trash; pinned entries do not
+    /// count toward the limit.
+    fn enforce_max_entries(&mut self) -> usize {
+        let result = self
+            .seqs_where(
+                "SELECT seq FROM text_entries WHERE pinned = 0
+                 ORDER BY seq DESC LIMIT -1 OFFSET ?1",
+                params![self.max_entries as i64],
+            )
+            .and_then(|seqs| self.trash_seqs(&seqs, REASON_RETENTION));
+        result.unwrap_or_else(|e| {
+            error!("Failed to truncate text history: {}", e);
+            0
+        })
     }
 
-    pub fn delete_entry(&mut self, id: &str) -> bool {
-        let len_before = self.entries.len();
-        self.entries.retain(|e| e.id != id);
-        if self.entries.len() != len_before {
-            self.save();
-            true
+    fn seqs_where(&self, sql: &str, params: impl rusqlite::Params) -> rusqlite::Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Move the entries with these `seq`s to the trash as one batch.
+    fn trash_seqs(&mut self, seqs: &[i64], reason: &str) -> rusqlite::Result<usize> {
+        if seqs.is_empty() {
+            return Ok(0);
+        }
+        let batch = trash::new_batch();
+        let insert = format!(
+            "INSERT INTO trash_text_entries (seq, {0}, batch, reason)
+             SELECT seq, {0}, ?2, ?3 FROM text_entries WHERE seq = ?1",
+            ENTRY_COLUMNS
+        );
+        let tx = self.conn.transaction()?;
+        let mut moved = 0;
+        for seq in seqs {
+            tx.execute(&insert, params![seq, batch, reason])?;
+            moved += tx.execute("DELETE FROM text_entries WHERE seq = ?1", params![seq])?;
+        }
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    fn remove_orphan_blobs(&self) {
+        let referenced = self
+            .conn
+            .prepare(
+                "SELECT blob FROM text_entries WHERE blob IS NOT NULL
+                 UNION SELECT blob FROM trash_text_entries WHERE blob IS NOT NULL",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<HashSet<_>>>()
+            });
+        match referenced {
+            Ok(referenced) => {
+                self.blobs.remove_unreferenced(&referenced);
+            }
+            Err(e) => error!("Failed to list text blobs: {}", e),
+        }
+    }
+
+    /// Trash unpinned entries not created or used in the last `max_age_days`.
+    fn delete_older_than(&mut self, max_age_days: u32) -> rusqlite::Result<usize> {
+        let cutoff = Local::now() - chrono::Duration::days(max_age_days as i64);
+        let expired: Vec<i64> = {
+            let mut stmt = self
+                .conn
+                .prepare(
+                    "SELECT seq, COALESCE(last_used_at, created_at) FROM text_entries
+                     WHERE pinned = 0",
+                )?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+            rows.filter_map(|r| r.ok())
+                .filter(|(_, created_at)| {
+                    DateTime::parse_from_str(created_at, "%Y-%m-%dT%H:%M:%S%z")
+                        .map(|t| t < cutoff)
+                        .unwrap_or(false)
+                })
+                .map(|(seq, _)| seq)
+                .collect()
+        };
+
+        self.trash_seqs(&expired, REASON_RETENTION)
+    }
+
+    /// Apply auto-expiry, the age limit (0 disables it) and the entry limit.
+    /// Returns the number of entries removed.
+    pub fn apply_retention(&mut self, max_age_days: u32) -> usize {
+        let mut removed = self.purge_expired();
+        if max_age_days > 0 {
+            removed += self.delete_older_than(max_age_days).unwrap_or_else(|e| {
+                error!("Failed to apply text age limit: {}", e);
+                0
+            });
+        }
+        removed + self.enforce_max_entries()
+    }
+
+    /// Delete entries whose auto-expiry has passed, skipping the trash.
+    /// Returns the number removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = Local::now().timestamp();
+        let trashed = self
+            .conn
+            .execute(
+                "DELETE FROM trash_text_entries WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now],
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to purge expired entries from trash: {}", e);
+                0
+            });
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM text_entries WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+                params![now],
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to purge expired entries: {}", e);
+                0
+            });
+        if removed + trashed > 0 {
+            self.remove_orphan_blobs();
+        }
+        removed
+    }
+
+    /// Entries newest first, optionally only those of one kind.
+    pub fn get_entries(&self, kind: Option<&str>) -> Vec<TextEntry> {
+        self.purge_expired();
+        let sql = format!(
+            "SELECT {} FROM text_entries
+             WHERE ?1 IS NULL OR COALESCE(kind, 'prose') = ?1
+             ORDER BY seq DESC",
+            TEXT_COLUMNS
+        );
+        let result = self.conn.prepare(&sql).and_then(|mut stmt| {
+            stmt.query_map(params![kind], Self::entry_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read text history: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Up to `limit` entries of `kind` (or of any kind) listed after
+    /// `after`, without their content. Entries come newest first, or by
+    /// frecency score when `frecency` is set; each is returned with its
+    /// `(score, seq)` position to continue from. Scores are 0 when listing
+    /// newest first.
+    pub fn list_page(
+        &self,
+        kind: Option<&str>,
+        frecency: bool,
+        after: Option<(i64, i64)>,
+        limit: usize,
+    ) -> Vec<((i64, i64), TextEntry)> {
+        self.purge_expired();
+        let sql = if frecency {
+            format!(
+                "SELECT {}, score, seq FROM (
+                     SELECT *, {} AS score FROM (
+                         SELECT *, {} AS age FROM (
+                             SELECT *, COALESCE(last_used_at, created_at) AS touched
+                             FROM text_entries
+                             WHERE ?1 IS NULL OR COALESCE(kind, 'prose') = ?1
+                         )
+                     )
+                 )
+                 WHERE ?2 IS NULL OR score < ?2 OR (score = ?2 AND seq < ?3)
+                 ORDER BY score DESC, seq DESC LIMIT ?4",
+                PREVIEW_COLUMNS, FRECENCY_SCORE, ENTRY_AGE
+            )
         } else {
-            false
+            format!(
+                "SELECT {}, 0, seq FROM text_entries
+                 WHERE (?1 IS NULL OR COALESCE(kind, 'prose') = ?1)
+                   AND (?2 IS NULL OR seq < ?3)
+                 ORDER BY seq DESC LIMIT ?4",
+                PREVIEW_COLUMNS
+            )
+        };
+        let (score, seq) = after.unzip();
+        let result = self.conn.prepare(&sql).and_then(|mut stmt| {
+            stmt.query_map(params![kind, score, seq, limit as i64], |row| {
+                Ok(((row.get(17)?, row.get(18)?), Self::entry_from_row(row)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read text history: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Number of entries of `kind`, or of all kinds.
+    pub fn count(&self, kind: Option<&str>) -> usize {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM text_entries
+                 WHERE ?1 IS NULL OR COALESCE(kind, 'prose') = ?1",
+                params![kind],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|n| n as usize)
+            .unwrap_or_else(|e| {
+                error!("Failed to count text history: {}", e);
+                0
+            })
+    }
+
+    /// Screenshots copied together with an entry, which listings show as
+    /// part of it.
+    pub fn linked_screenshots(&self) -> HashSet<String> {
+        let result = self
+            .conn
+            .prepare("SELECT DISTINCT screenshot FROM text_entries WHERE screenshot IS NOT NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<HashSet<String>>>()
+            });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read linked screenshots: {}", e);
+            HashSet::new()
+        })
+    }
+
+    /// Full text of an entry, read back from its blob when it was too large to
+    /// keep inline.
+    pub fn get_full_content(&self, id: &str) -> Result<String, String> {
+        let seq: i64 = self
+            .conn
+            .query_row(
+                "SELECT seq FROM text_entries WHERE id = ?1 ORDER BY seq DESC LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read text entry: {}", e))?
+            .ok_or_else(|| format!("Text entry not found: {}", id))?;
+        self.full_content_of(seq)
+    }
+
+    /// Every entry oldest first with its full, decrypted content and a plain
+    /// SHA-256 hash, so archives merge the same way on any machine.
+    pub fn export_entries(&self) -> Result<Vec<TextEntry>, String> {
+        self.purge_expired();
+        let sql = format!("SELECT {}, seq FROM text_entries ORDER BY seq", TEXT_COLUMNS);
+        let rows: Vec<(TextEntry, i64)> = self
+            .conn
+            .prepare(&sql)
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((Self::entry_from_row(row)?, row.get(17)?)))?
+                    .collect()
+            })
+            .map_err(|e| format!("Failed to read text history: {}", e))?;
+
+        rows.into_iter()
+            .map(|(entry, seq)| self.exported(entry, seq))
+            .collect()
+    }
+
+    /// One entry in the form `export_entries` produces, looked up by its
+    /// plaintext hash.
+    pub fn export_entry(&self, hash: &str) -> Result<Option<TextEntry>, String> {
+        let sql = format!("SELECT {}, seq FROM text_entries WHERE hash = ?1", TEXT_COLUMNS);
+        let row: Option<(TextEntry, i64)> = self
+            .conn
+            .query_row(&sql, params![hash], |row| {
+                Ok((Self::entry_from_row(row)?, row.get(17)?))
+            })
+            .optional()
+            .map_err(|e| format!("Failed to read text entry: {}", e))?;
+        row.map(|(entry, seq)| self.exported(entry, seq)).transpose()
+    }
+
+    fn exported(&self, mut entry: TextEntry, seq: i64) -> Result<TextEntry, String> {
+        entry.content = self.full_content_of(seq)?;
+        entry.html = self.html_of(seq)?;
+        entry.hash = sha256_hash(&hash_input(
+            &entry.format,
+            &entry.content,
+            entry.html.as_deref(),
+        ));
+        entry.truncated = false;
+        Ok(entry)
+    }
+
+    /// Markup of an "html" entry, decrypted.
+    pub fn get_html(&self, id: &str) -> Result<Option<String>, String> {
+        let seq: i64 = self
+            .conn
+            .query_row(
+                "SELECT seq FROM text_entries WHERE id = ?1 ORDER BY seq DESC LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read text entry: {}", e))?
+            .ok_or_else(|| format!("Text entry not found: {}", id))?;
+        self.html_of(seq)
+    }
+
+    /// Filename of the screenshot linked to entry `id`, if any.
+    pub fn screenshot_of(&self, id: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT screenshot FROM text_entries WHERE id = ?1 ORDER BY seq DESC LIMIT 1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)
+            .flatten()
+    }
+
+    fn html_of(&self, seq: i64) -> Result<Option<String>, String> {
+        let (html, encrypted): (Option<String>, bool) = self
+            .conn
+            .query_row(
+                "SELECT html, encrypted FROM text_entries WHERE seq = ?1",
+                params![seq],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Failed to read text entry: {}", e))?;
+        match html {
+            Some(html) if encrypted => crypto::decrypt_string(&html).map(Some),
+            html => Ok(html),
         }
     }
 
+    /// Hashes of entries that may leave this machine: plaintext and not flagged
+    /// as containing secrets.
+    pub fn shareable_hashes(&self) -> HashSet<String> {
+        self.conn
+            .prepare("SELECT hash FROM text_entries WHERE encrypted = 0 AND sensitive = 0")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<HashSet<String>>>()
+            })
+            .unwrap_or_else(|e| {
+                error!("Failed to read text hashes: {}", e);
+                HashSet::new()
+            })
+    }
+
+    pub fn hash_of(&self, id: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT hash FROM text_entries WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)
+    }
+
+    /// Hashes of the entries `clear` would remove.
+    pub fn unpinned_hashes(&self) -> Vec<String> {
+        self.conn
+            .prepare("SELECT hash FROM text_entries WHERE pinned = 0")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether the entry with `hash` is pinned, or None when there is none.
+    pub fn pinned_by_hash(&self, hash: &str) -> Option<bool> {
+        self.conn
+            .query_row(
+                "SELECT pinned FROM text_entries WHERE hash = ?1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)
+    }
+
+    /// Trash the entry with `hash`, as deleted on another machine.
+    pub fn delete_by_hash(&mut self, hash: &str) -> bool {
+        self.seqs_where(
+            "SELECT seq FROM text_entries WHERE hash = ?1",
+            params![hash],
+        )
+        .and_then(|seqs| self.trash_seqs(&seqs, REASON_SYNC))
+        .is_ok_and(|n| n > 0)
+    }
+
+    fn full_content_of(&self, seq: i64) -> Result<String, String> {
+        let (content, encrypted, blob): (String, bool, Option<String>) = self
+            .conn
+            .query_row(
+                "SELECT content, encrypted, blob FROM text_entries WHERE seq = ?1",
+                params![seq],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| format!("Failed to read text entry: {}", e))?;
+
+        match blob {
+            Some(hash) => {
+                let data = crypto::decrypt_if_needed(self.blobs.get(&hash)?)?;
+                String::from_utf8(data).map_err(|e| format!("Invalid text blob: {}", e))
+            }
+            None if encrypted => crypto::decrypt_string(&content),
+            None => Ok(content),
+        }
+    }
+
+    /// Move an entry to the trash.
+    pub fn delete_entry(&mut self, id: &str) -> bool {
+        self.seqs_where("SELECT seq FROM text_entries WHERE id = ?1", params![id])
+            .and_then(|seqs| self.trash_seqs(&seqs, REASON_DELETED))
+            .is_ok_and(|n| n > 0)
+    }
+
+    /// Move all unpinned entries to the trash as one batch.
     pub fn clear(&mut self) {
-        self.entries.clear();
-        self.save();
+        let result = self
+            .seqs_where("SELECT seq FROM text_entries WHERE pinned = 0", [])
+            .and_then(|seqs| self.trash_seqs(&seqs, REASON_DELETED));
+        if let Err(e) = result {
+            error!("Failed to clear text history: {}", e);
+        }
+    }
+
+    /// Entries in the trash, most recently deleted first.
+    pub fn trashed_entries(&self) -> Vec<TrashItem> {
+        let sql = format!(
+            "SELECT {}, batch, reason FROM trash_text_entries ORDER BY batch DESC, seq DESC",
+            TEXT_COLUMNS
+        );
+        let result = self.conn.prepare(&sql).and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                let entry = Self::entry_from_row(row)?;
+                let batch: i64 = row.get(17)?;
+                Ok(TrashItem {
+                    kind: "text".to_string(),
+                    id: entry.id,
+                    preview: entry.preview,
+                    batch,
+                    deleted_at: trash::format_batch(batch),
+                    reason: row.get(18)?,
+                    pinned: entry.pinned,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+        });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read trash: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Newest batch trashed for `reason`, if any.
+    pub fn last_trash_batch(&self, reason: &str) -> Option<i64> {
+        self.conn
+            .query_row(
+                "SELECT MAX(batch) FROM trash_text_entries WHERE reason = ?1",
+                params![reason],
+                |row| row.get(0),
+            )
+            .unwrap_or(None)
+    }
+
+    /// Put the entries of `batch`, or only the one with `id`, back in history
+    /// where they were, or on top if their `seq` has been reused. An entry
+    /// whose content has been copied again since is merged into that copy,
+    /// which moves to the top; one whose id has been taken gets a new id.
+    /// Returns the number restored, and fails if `id` is not in the batch.
+    pub fn restore_trashed(&mut self, batch: i64, id: Option<&str>) -> Result<usize, String> {
+        let other_columns = ENTRY_COLUMNS.strip_prefix("id, ").unwrap_or(ENTRY_COLUMNS);
+        let insert = format!(
+            "INSERT INTO text_entries (seq, {0})
+             SELECT CASE WHEN seq IN (SELECT seq FROM text_entries) THEN NULL ELSE seq END,
+                    ?3, {1}
+             FROM trash_text_entries WHERE batch = ?1 AND id = ?2",
+            ENTRY_COLUMNS, other_columns
+        );
+        let result = (|| -> rusqlite::Result<(usize, Vec<i64>)> {
+            let tx = self.conn.transaction()?;
+            let trashed: Vec<(String, String, bool)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, hash, pinned FROM trash_text_entries
+                     WHERE batch = ?1 AND (?2 IS NULL OR id = ?2)",
+                )?;
+                let rows = stmt.query_map(params![batch, id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+
+            let mut restored = 0;
+            let mut merged = Vec::new();
+            for (trashed_id, hash, pinned) in &trashed {
+                let existing: Option<i64> = tx
+                    .query_row(
+                        "SELECT seq FROM text_entries WHERE hash = ?1",
+                        params![hash],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(seq) = existing {
+                    if *pinned {
+                        tx.execute(
+                            "UPDATE text_entries SET pinned = 1 WHERE seq = ?1",
+                            params![seq],
+                        )?;
+                    }
+                    merged.push(seq);
+                } else {
+                    let mut new_id = trashed_id.clone();
+                    let mut n = 2;
+                    while tx
+                        .query_row(
+                            "SELECT 1 FROM text_entries WHERE id = ?1",
+                            params![new_id],
+                            |_| Ok(()),
+                        )
+                        .optional()?
+                        .is_some()
+                    {
+                        new_id = format!("{}_{}", trashed_id, n);
+                        n += 1;
+                    }
+                    tx.execute(&insert, params![batch, trashed_id, new_id])?;
+                }
+                tx.execute(
+                    "DELETE FROM trash_text_entries WHERE batch = ?1 AND id = ?2",
+                    params![batch, trashed_id],
+                )?;
+                restored += 1;
+            }
+            tx.commit()?;
+            Ok((restored, merged))
+        })();
+        let (restored, merged) =
+            result.map_err(|e| format!("Failed to restore from trash: {}", e))?;
+
+        for seq in &merged {
+            self.promote(*seq, false);
+        }
+        if !merged.is_empty() {
+            self.remove_orphan_blobs();
+        }
+        if restored == 0 {
+            if let Some(id) = id {
+                return Err(format!("Not in trash: {}", id));
+            }
+        }
+        Ok(restored)
+    }
+
+    /// Permanently delete trashed entries from batches before `before`, or
+    /// all of them. Returns the number deleted.
+    pub fn purge_trash(&mut self, before: Option<i64>) -> usize {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM trash_text_entries WHERE ?1 IS NULL OR batch < ?1",
+                params![before],
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to empty trash: {}", e);
+                0
+            });
+        if removed > 0 {
+            self.remove_orphan_blobs();
+        }
+        removed
+    }
+
+    pub fn set_pinned(&mut self, id: &str, pinned: bool) -> bool {
+        self.conn
+            .execute(
+                "UPDATE text_entries SET pinned = ?1 WHERE id = ?2",
+                params![pinned, id],
+            )
+            .map(|n| n > 0)
+            .unwrap_or(false)
     }
 
     pub fn set_max_entries(&mut self, max: usize) {
         self.max_entries = max;
     }
 
-    pub fn last_hash(&self) -> Option<&str> {
-        self.entries.first().map(|e| e.hash.as_str())
+    pub fn set_encrypt(&mut self, encrypt: bool) {
+        self.encrypt = encrypt;
+    }
+
+    pub fn set_size_limits(&mut self, inline_kb: u32, max_mb: u32) {
+        self.inline_text_bytes = inline_kb as usize * 1024;
+        self.max_text_bytes = max_mb as usize * 1024 * 1024;
+    }
+
+    /// Encrypt every plaintext entry in place and purge it from the search index.
+    /// Plaintext entries in the trash are deleted rather than encrypted.
+    pub fn encrypt_existing(&mut self) -> Result<u32, String> {
+        let count = (|| -> Result<u32, String> {
+            let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+            struct Plain {
+                seq: i64,
+                content: String,
+                preview: String,
+                blob: Option<String>,
+                kind_meta: Option<String>,
+                format: String,
+                html: Option<String>,
+            }
+            let rows: Vec<Plain> = {
+                let mut stmt = tx
+                    .prepare(
+                        "SELECT seq, content, preview, blob, kind_meta, format, html
+                         FROM text_entries WHERE encrypted = 0",
+                    )
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok(Plain {
+                            seq: row.get(0)?,
+                            content: row.get(1)?,
+                            preview: row.get(2)?,
+                            blob: row.get(3)?,
+                            kind_meta: row.get(4)?,
+                            format: row.get(5)?,
+                            html: row.get(6)?,
+                        })
+                    })
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+            };
+
+            for Plain { seq, content, preview, blob, kind_meta, format, html } in &rows {
+                // Blob-backed entries are hashed over the full text, like new ones
+                let full = match blob {
+                    Some(old) => Some(self.blobs.get(old)?),
+                    None => None,
+                };
+                let full_text = match &full {
+                    Some(data) => String::from_utf8_lossy(data),
+                    None => content.into(),
+                };
+                let hash = crypto::keyed_hash(&hash_input(format, &full_text, html.as_deref()))?;
+
+                // Already captured again since encryption was turned on
+                let duplicate = tx
+                    .query_row(
+                        "SELECT 1 FROM text_entries WHERE hash = ?1",
+                        params![hash],
+                        |_| Ok(()),
+                    )
+                    .optional()
+                    .map_err(|e| e.to_string())?
+                    .is_some();
+                if duplicate {
+                    tx.execute("DELETE FROM text_entries WHERE seq = ?1", params![seq])
+                        .map_err(|e| e.to_string())?;
+                    continue;
+                }
+
+                // The plaintext blob is left for remove_orphan_blobs once committed
+                let new_blob = match &full {
+                    Some(data) => {
+                        self.blobs.put(&hash, &crypto::encrypt(data)?)?;
+                        Some(hash.clone())
+                    }
+                    None => None,
+                };
+                let kind_meta = kind_meta.as_deref().map(crypto::encrypt_string).transpose()?;
+                let html = html.as_deref().map(crypto::encrypt_string).transpose()?;
+                tx.execute(
+                    "UPDATE text_entries
+                     SET content = ?1, preview = ?2, hash = ?3, encrypted = 1, blob = ?4,
+                         kind_meta = ?5, html = ?6
+                     WHERE seq = ?7",
+                    params![
+                        crypto::encrypt_string(content)?,
+                        crypto::encrypt_string(preview)?,
+                        hash,
+                        new_blob,
+                        kind_meta,
+                        html,
+                        seq
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                tx.execute(
+                    "DELETE FROM search_index WHERE kind = 'text' AND ref_id = CAST(?1 AS TEXT)",
+                    params![seq],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+
+            tx.execute("DELETE FROM trash_text_entries WHERE encrypted = 0", [])
+                .map_err(|e| e.to_string())?;
+            tx.commit().map_err(|e| e.to_string())?;
+            Ok(rows.len() as u32)
+        })()
+        .map_err(|e| format!("Failed to encrypt history: {}", e))?;
+        self.remove_orphan_blobs();
+
+        // Merge index segments and rewrite the file so old plaintext pages are dropped
+        let _ = self
+            .conn
+            .execute("INSERT INTO search_index(search_index) VALUES ('optimize')", []);
+        let _ = self
+            .conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()));
+        let _ = self.conn.execute_batch("VACUUM");
+
+        Ok(count)
+    }
+
+    pub fn last_hash(&self) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT hash FROM text_entries ORDER BY seq DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or(None)
+    }
+
+    pub fn record_screenshot(&mut self, info: &ScreenshotInfo) {
+        if let Err(e) = self.conn.execute(
+            "INSERT OR IGNORE INTO screenshots (filename, path, created_at, size_bytes)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![info.filename, info.path, info.created_at, info.size_bytes as i64],
+        ) {
+            error!("Failed to record screenshot {}: {}", info.filename, e);
+        }
+    }
+
+    pub fn forget_screenshot(&mut self, filename: &str) {
+        let _ = self
+            .conn
+            .execute("DELETE FROM screenshots WHERE filename = ?1", params![filename]);
+    }
+
+    /// Point metadata, search and linked text entries at a screenshot's new
+    /// file after it was re-encoded under another name.
+    pub fn rename_screenshot(&mut self, old_filename: &str, info: &ScreenshotInfo) {
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = self.conn.transaction()?;
+            tx.execute(
+                "UPDATE screenshots SET filename = ?1, path = ?2, size_bytes = ?3
+                 WHERE filename = ?4",
+                params![info.filename, info.path, info.size_bytes as i64, old_filename],
+            )?;
+            // The search triggers only cover insert and delete
+            tx.execute(
+                "UPDATE search_index SET ref_id = ?1, body = ?1
+                 WHERE kind = 'image' AND ref_id = ?2",
+                params![info.filename, old_filename],
+            )?;
+            tx.execute(
+                "UPDATE text_entries SET screenshot = ?1 WHERE screenshot = ?2",
+                params![info.filename, old_filename],
+            )?;
+            tx.commit()
+        })();
+
+        if let Err(e) = result {
+            error!("Failed to rename screenshot {}: {}", old_filename, e);
+        }
+    }
+
+    pub fn set_screenshot_phash(&mut self, filename: &str, phash: u64) {
+        if let Err(e) = self.conn.execute(
+            "UPDATE screenshots SET phash = ?1 WHERE filename = ?2",
+            params![phash as i64, filename],
+        ) {
+            error!("Failed to store hash of {}: {}", filename, e);
+        }
+    }
+
+    /// Keep the pixel hash of a screenshot as captured, which its file no
+    /// longer gives once encoded lossily or scaled down.
+    pub fn set_screenshot_pixel_hash(&mut self, filename: &str, hash: &str) {
+        if let Err(e) = self.conn.execute(
+            "UPDATE screenshots SET pixel_hash = ?1 WHERE filename = ?2",
+            params![hash, filename],
+        ) {
+            error!("Failed to store pixel hash of {}: {}", filename, e);
+        }
+    }
+
+    /// Capture-time pixel hashes of the screenshots that have one, keyed by
+    /// filename.
+    pub fn screenshot_pixel_hashes(&self) -> HashMap<String, String> {
+        let result = self
+            .conn
+            .prepare("SELECT filename, pixel_hash FROM screenshots WHERE pixel_hash IS NOT NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<rusqlite::Result<HashMap<_, _>>>()
+            });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read screenshot pixel hashes: {}", e);
+            HashMap::new()
+        })
+    }
+
+    /// Perceptual hashes of the screenshots that have one, keyed by filename.
+    pub fn screenshot_phashes(&self) -> HashMap<String, u64> {
+        let result = self
+            .conn
+            .prepare("SELECT filename, phash FROM screenshots WHERE phash IS NOT NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?
+                    .collect::<rusqlite::Result<HashMap<_, _>>>()
+            });
+        result.unwrap_or_else(|e| {
+            error!("Failed to read screenshot hashes: {}", e);
+            HashMap::new()
+        })
+    }
+
+    /// Reconcile screenshot metadata with what is actually on disk.
+    pub fn sync_screenshots(&mut self, screenshots: &[ScreenshotInfo]) {
+        let result = (|| -> rusqlite::Result<()> {
+            let tx = self.conn.transaction()?;
+            let known: HashSet<String> = {
+                let mut stmt = tx.prepare("SELECT filename FROM screenshots")?;
+                let names = stmt.query_map([], |row| row.get(0))?;
+                names.collect::<rusqlite::Result<_>>()?
+            };
+            let on_disk: HashSet<&str> = screenshots.iter().map(|s| s.filename.as_str()).collect();
+
+            for name in known.iter().filter(|n| !on_disk.contains(n.as_str())) {
+                tx.execute("DELETE FROM screenshots WHERE filename = ?1", params![name])?;
+            }
+            for info in screenshots.iter().filter(|s| !known.contains(&s.filename)) {
+                tx.execute(
+                    "INSERT INTO screenshots (filename, path, created_at, size_bytes)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![info.filename, info.path, info.created_at, info.size_bytes as i64],
+                )?;
+            }
+            tx.commit()
+        })();
+
+        if let Err(e) = result {
+            error!("Failed to sync screenshot metadata: {}", e);
+        }
+    }
+
+    /// Encrypted entries are not indexed, so match them by decrypting in memory.
+    fn search_encrypted(&self, query: &str, filters: &SearchFilters, limit: usize) -> Vec<TextEntry> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let sql = format!(
+            "SELECT {} FROM text_entries
+             WHERE encrypted = 1
+               AND (?1 IS NULL OR created_at >= ?1)
+               AND (?2 IS NULL OR created_at <= ?2)
+               AND (?3 IS NULL OR COALESCE(kind, 'prose') = ?3)
+             ORDER BY seq DESC",
+            TEXT_COLUMNS
+        );
+        let needle = query.to_lowercase();
+        let result = self.conn.prepare(&sql).and_then(|mut stmt| {
+            stmt.query_map(
+                params![filters.since, filters.until, filters.kind],
+                Self::entry_from_row,
+            )?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        });
+        result
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| e.content.to_lowercase().contains(&needle))
+            .take(limit)
+            .collect()
+    }
+
+    /// Full-text search over text content and screenshot filenames, best match first.
+    pub fn search(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SearchHit>, String> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.purge_expired();
+
+        let limit = filters.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as i64;
+
+        // The trigram tokenizer cannot match terms shorter than three characters,
+        // so short queries fall back to a substring scan ordered by recency.
+        let (sql, pattern) = if query.chars().count() < 3 {
+            let escaped = query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            (
+                "SELECT kind, ref_id FROM search_index
+                 WHERE body LIKE ?1 ESCAPE '\\'
+                   AND (?2 IS NULL OR kind = ?2)
+                   AND (?3 IS NULL OR created_at >= ?3)
+                   AND (?4 IS NULL OR created_at <= ?4)
+                   AND (?6 IS NULL OR ref_id IN (
+                       SELECT CAST(seq AS TEXT) FROM text_entries WHERE kind = ?6))
+                 ORDER BY created_at DESC LIMIT ?5",
+                format!("%{}%", escaped),
+            )
+        } else {
+            (
+                "SELECT kind, ref_id FROM search_index
+                 WHERE search_index MATCH ?1
+                   AND (?2 IS NULL OR kind = ?2)
+                   AND (?3 IS NULL OR created_at >= ?3)
+                   AND (?4 IS NULL OR created_at <= ?4)
+                   AND (?6 IS NULL OR ref_id IN (
+                       SELECT CAST(seq AS TEXT) FROM text_entries WHERE kind = ?6))
+                 ORDER BY rank LIMIT ?5",
+                // Quote as a single phrase so user input is never parsed as FTS syntax
+                format!("\"{}\"", query.replace('"', "\"\"")),
+            )
+        };
+
+        let matches: Vec<(String, String)> = self
+            .conn
+            .prepare(sql)
+            .and_then(|mut stmt| {
+                stmt.query_map(
+                    params![
+                        pattern,
+                        filters.entry_type,
+                        filters.since,
+                        filters.until,
+                        limit,
+                        filters.kind
+                    ],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?
+                .collect()
+            })
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let text_sql = format!("SELECT {} FROM text_entries WHERE seq = ?1", TEXT_COLUMNS);
+        let mut hits = Vec::with_capacity(matches.len());
+        for (kind, ref_id) in matches {
+            match kind.as_str() {
+                "text" => {
+                    let entry = self
+                        .conn
+                        .query_row(&text_sql, params![ref_id], Self::entry_from_row)
+                        .optional()
+                        .map_err(|e| format!("Search failed: {}", e))?;
+                    if let Some(entry) = entry {
+                        hits.push(SearchHit::Text(Box::new(entry)));
+                    }
+                }
+                "image" => hits.push(SearchHit::Screenshot(ref_id)),
+                _ => {}
+            }
+        }
+
+        if filters.entry_type.as_deref().unwrap_or("text") == "text" && crypto::is_unlocked() {
+            let remaining = (limit as usize).saturating_sub(hits.len());
+            hits.extend(
+                self.search_encrypted(query, filters, remaining)
+                    .into_iter()
+                    .map(|e| SearchHit::Text(Box::new(e))),
+            );
+        }
+
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::types::Value;
+
+    fn history() -> TextHistory {
+        TextHistory::in_memory(std::env::temp_dir().join("cli-buddy-test-blobs"))
+    }
+
+    /// Every column of the live entry with `id`, `seq` included.
+    fn row(th: &TextHistory, id: &str) -> Vec<Value> {
+        let sql = format!(
+            "SELECT seq, {} FROM text_entries WHERE id = ?1",
+            ENTRY_COLUMNS
+        );
+        th.conn
+            .query_row(&sql, params![id], |row| {
+                (0..=ENTRY_COLUMNS.split(", ").count())
+                    .map(|i| row.get(i))
+                    .collect()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn restore_round_trips_every_column() {
+        let mut th = history();
+        let options = EntryOptions {
+            sensitive: true,
+            expires_at: Some(Local::now().timestamp() + 3600),
+            html: Some("<b>bold</b>".to_string()),
+            screenshot: Some("2024-03-09/x.png".to_string()),
+            ..Default::default()
+        };
+        let entry = th.add_entry_with("bold", options).unwrap();
+        th.add_entry("newer").unwrap();
+        th.set_pinned(&entry.id, true);
+        th.record_use(&entry.id);
+        th.add_entry("newest").unwrap();
+        let before = row(&th, &entry.id);
+
+        assert!(th.delete_entry(&entry.id));
+        let batch = th.last_trash_batch(REASON_DELETED).unwrap();
+        assert_eq!(th.restore_trashed(batch, Some(&entry.id)).unwrap(), 1);
+        assert_eq!(row(&th, &entry.id), before);
+        assert!(th.trashed_entries().is_empty());
+    }
+
+    #[test]
+    fn restore_merges_into_recaptured_content() {
+        let mut th = history();
+        let entry = th.add_entry("again").unwrap();
+        th.set_pinned(&entry.id, true);
+        th.delete_entry(&entry.id);
+        let batch = th.last_trash_batch(REASON_DELETED).unwrap();
+        th.add_entry("again").unwrap();
+        th.add_entry("other").unwrap();
+
+        assert_eq!(th.restore_trashed(batch, None).unwrap(), 1);
+        let entries = th.get_entries(None);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].content, "again");
+        assert!(entries[0].pinned);
+        assert!(th.trashed_entries().is_empty());
+    }
+
+    #[test]
+    fn restore_renames_colliding_id() {
+        let mut th = history();
+        let entry = th.add_entry("first").unwrap();
+        th.delete_entry(&entry.id);
+        let batch = th.last_trash_batch(REASON_DELETED).unwrap();
+        let other = th.add_entry("second").unwrap();
+        th.conn
+            .execute(
+                "UPDATE text_entries SET id = ?1 WHERE id = ?2",
+                params![entry.id, other.id],
+            )
+            .unwrap();
+
+        assert_eq!(th.restore_trashed(batch, None).unwrap(), 1);
+        let restored = format!("{}_2", entry.id);
+        assert_eq!(th.get_full_content(&restored).unwrap(), "first");
+        assert_eq!(th.get_full_content(&entry.id).unwrap(), "second");
+        assert!(th.restore_trashed(batch, Some(&entry.id)).is_err());
+    }
+
+    #[test]
+    fn purge_trash_respects_before() {
+        let mut th = history();
+        let a = th.add_entry("a").unwrap();
+        let b = th.add_entry("b").unwrap();
+        th.delete_entry(&a.id);
+        th.delete_entry(&b.id);
+        let newer = th.last_trash_batch(REASON_DELETED).unwrap();
+
+        assert_eq!(th.purge_trash(Some(newer)), 1);
+        let left = th.trashed_entries();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, b.id);
+        assert_eq!(th.purge_trash(None), 1);
+        assert!(th.trashed_entries().is_empty());
+    }
+
+    #[test]
+    fn capture_hash_matches_stored_entry() {
+        let mut th = history();
+        th.add_entry("plain").unwrap();
+        assert_eq!(th.capture_hash("text", "plain", None).ok(), th.last_hash());
+
+        // Clips over the size cap are stored, and so hashed, cut short
+        th.max_text_bytes = 8;
+        let html = EntryOptions {
+            html: Some("<b>bold text</b>".to_string()),
+            ..Default::default()
+        };
+        th.add_entry_with("bold text", html).unwrap();
+        assert_eq!(
+            th.capture_hash("html", "bold text", Some("<b>bold text</b>"))
+                .ok(),
+            th.last_hash()
+        );
+        let unclamped = hash_input("html", "bold text", Some("<b>bold text</b>"));
+        assert_ne!(Some(sha256_hash(&unclamped)), th.last_hash());
+    }
+
+    #[test]
+    fn list_page_continues_after_position() {
+        let mut th = history();
+        let files = EntryOptions {
+            files: true,
+            screenshot: Some("shot.png".to_string()),
+            ..Default::default()
+        };
+        th.add_entry_with("/tmp/a.txt", files).unwrap();
+        for text in ["one", "two", "three"] {
+            th.add_entry(text).unwrap();
+        }
+        let old = th.add_entry("old").unwrap();
+        th.conn
+            .execute(
+                "UPDATE text_entries SET created_at = '2020-01-01T00:00:00+0900' WHERE id = ?1",
+                params![old.id],
+            )
+            .unwrap();
+        let used = th.add_entry("used").unwrap();
+        th.record_use(&used.id);
+        th.record_use(&used.id);
+
+        let list = |frecency: bool| -> Vec<String> {
+            let mut after = None;
+            let mut listed = Vec::new();
+            loop {
+                let page = th.list_page(None, frecency, after, 2);
+                let Some((position, _)) = page.last() else {
+                    return listed;
+                };
+                after = Some(*position);
+                listed.extend(page.into_iter().map(|(_, e)| e.preview));
+            }
+        };
+        assert_eq!(
+            list(false),
+            ["used", "old", "three", "two", "one", "/tmp/a.txt"]
+        );
+        // Used three times, then once each, then untouched for years
+        assert_eq!(
+            list(true),
+            ["used", "three", "two", "one", "/tmp/a.txt", "old"]
+        );
+
+        let page = th.list_page(None, false, None, 10);
+        assert!(page
+            .iter()
+            .all(|(_, e)| e.content.is_empty() == (e.format != "files")));
+        assert_eq!(page.last().unwrap().1.content, "/tmp/a.txt");
+        assert_eq!(th.count(None), 6);
+        assert_eq!(
+            th.count(Some("path")),
+            th.list_page(Some("path"), false, None, 10).len()
+        );
+        assert_eq!(
+            th.linked_screenshots(),
+            HashSet::from(["shot.png".to_string()])
+        );
     }
 }
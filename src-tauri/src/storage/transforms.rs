@@ -0,0 +1,306 @@
+use crate::storage::{paths, percent};
+use base64::Engine;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Built-in transforms accepted by `apply`, in the order they are offered.
+pub const TRANSFORMS: &[&str] = &[
+    "trim",
+    "upper",
+    "lower",
+    "title",
+    "snake",
+    "kebab",
+    "camel",
+    "json_pretty",
+    "json_minify",
+    "base64_encode",
+    "base64_decode",
+    "url_encode",
+    "url_decode",
+    "shell_quote",
+    "windows_to_wsl",
+    "wsl_to_windows",
+    "strip_ansi",
+    "dedent",
+];
+
+pub fn is_builtin(name: &str) -> bool {
+    TRANSFORMS.contains(&name)
+}
+
+/// Run `names` in order, each on the previous result.
+pub fn apply_chain(input: &str, names: &[String]) -> Result<String, String> {
+    names
+        .iter()
+        .try_fold(input.to_string(), |text, name| apply(name, &text))
+}
+
+pub fn apply(name: &str, text: &str) -> Result<String, String> {
+    Ok(match name {
+        "trim" => text.trim().to_string(),
+        "upper" => text.to_uppercase(),
+        "lower" => text.to_lowercase(),
+        "title" => title_case(text),
+        "snake" => words(text).join("_"),
+        "kebab" => words(text).join("-"),
+        "camel" => camel_case(text),
+        "json_pretty" => {
+            let value: serde_json::Value = parse_json(text)?;
+            serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?
+        }
+        "json_minify" => {
+            let value: serde_json::Value = parse_json(text)?;
+            serde_json::to_string(&value).map_err(|e| e.to_string())?
+        }
+        "base64_encode" => base64::engine::general_purpose::STANDARD.encode(text),
+        "base64_decode" => {
+            let compact: String = text.split_whitespace().collect();
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(&compact)
+                .or_else(|_| {
+                    // URL-safe alphabet, as used in JWTs
+                    base64::engine::general_purpose::URL_SAFE_NO_PAD
+                        .decode(compact.trim_end_matches('='))
+                })
+                .map_err(|e| format!("Invalid base64: {}", e))?;
+            String::from_utf8(data).map_err(|_| "Decoded base64 is not text".to_string())?
+        }
+        "url_encode" => percent::encode(text),
+        "url_decode" => percent::decode(text)?,
+        "shell_quote" => shell_quote(text),
+        "windows_to_wsl" => map_lines(text, paths::windows_to_wsl),
+        "wsl_to_windows" => map_lines(text, paths::wsl_to_windows),
+        "strip_ansi" => ansi_re().replace_all(text, "").into_owned(),
+        "dedent" => dedent(text),
+        _ => return Err(format!("Unknown transform: {}", name)),
+    })
+}
+
+fn parse_json(text: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(text.trim()).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+/// Lowercase words split on punctuation, whitespace and camelCase boundaries.
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1);
+        // "fooBar" splits before B; "HTTPResponse" splits before R
+        let boundary = c.is_uppercase()
+            && (prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(char::is_uppercase)
+                    && next.is_some_and(|n| n.is_lowercase())));
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Capitalize each whitespace-separated word, keeping the spacing as it was.
+fn title_case(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut at_word_start = true;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            at_word_start = true;
+            out.push(c);
+        } else if at_word_start {
+            out.extend(c.to_uppercase());
+            at_word_start = false;
+        } else {
+            out.extend(c.to_lowercase());
+        }
+    }
+    out
+}
+
+fn camel_case(text: &str) -> String {
+    let words = words(text);
+    let mut out = words.first().cloned().unwrap_or_default();
+    for word in words.iter().skip(1) {
+        out.push_str(&capitalize(word));
+    }
+    out
+}
+
+/// Quote for POSIX shells; words made only of safe characters are left bare.
+fn shell_quote(text: &str) -> String {
+    let safe = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if safe {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+/// Apply `convert` to each line, leaving lines it does not recognize unchanged.
+fn map_lines(text: &str, convert: fn(&str) -> Option<String>) -> String {
+    text.split('\n')
+        .map(|line| {
+            let (body, cr) = match line.strip_suffix('\r') {
+                Some(body) => (body, "\r"),
+                None => (line, ""),
+            };
+            let trimmed = body.trim();
+            let unquoted = trimmed
+                .strip_prefix('"')
+                .and_then(|l| l.strip_suffix('"'))
+                .unwrap_or(trimmed);
+            match convert(unquoted) {
+                Some(converted) => format!("{}{}", body.replacen(unquoted, &converted, 1), cr),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// CSI sequences (colors, cursor movement), OSC sequences (titles, links) and
+/// two-character escapes.
+fn ansi_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]")
+            .expect("valid ANSI pattern")
+    })
+}
+
+/// Remove the leading whitespace shared by every non-blank line.
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| &l[..l.len() - l.trim_start().len()])
+        .reduce(|common, ws| {
+            let shared = common
+                .char_indices()
+                .zip(ws.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((i, a), _)| i + a.len_utf8());
+            &common[..shared]
+        })
+        .unwrap_or("");
+    if indent.is_empty() {
+        return text.to_string();
+    }
+    text.split('\n')
+        .map(|l| l.strip_prefix(indent).unwrap_or(l.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, text: &str) -> String {
+        apply(name, text).unwrap()
+    }
+
+    #[test]
+    fn changes_case() {
+        assert_eq!(run("title", "hello  wORLD\tx"), "Hello  World\tX");
+        assert_eq!(
+            run("snake", "parseHTTPResponse2Body"),
+            "parse_http_response2_body"
+        );
+        assert_eq!(run("kebab", "Some_mixed value"), "some-mixed-value");
+        assert_eq!(run("camel", "user-account id"), "userAccountId");
+        assert_eq!(run("snake", "  --  "), "");
+    }
+
+    #[test]
+    fn round_trips_encodings() {
+        for text in ["", "plain", "a b&c=d/é?", "line\nbreak"] {
+            let encoded = run("url_encode", text);
+            assert_eq!(run("url_decode", &encoded), text);
+            let encoded = run("base64_encode", text);
+            assert_eq!(run("base64_decode", &encoded), text);
+        }
+        assert_eq!(run("url_encode", "a b/~"), "a%20b%2F~");
+        // URL-safe and wrapped base64
+        assert_eq!(run("base64_decode", "eyJhIjoiPz4ifQ"), "{\"a\":\"?>\"}");
+        assert_eq!(run("base64_decode", "aGVs\nbG8="), "hello");
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(apply("url_decode", "100%").is_err());
+        assert!(apply("url_decode", "%zz").is_err());
+        assert!(apply("url_decode", "%ff").is_err());
+        assert!(apply("base64_decode", "not base64!").is_err());
+        assert!(apply("base64_decode", "/w==").is_err());
+        assert!(apply("json_pretty", "{a: 1}").is_err());
+        assert!(apply("reverse", "x").is_err());
+    }
+
+    #[test]
+    fn formats_json() {
+        assert_eq!(run("json_minify", " { \"a\": [1, 2] }\n"), "{\"a\":[1,2]}");
+        assert_eq!(run("json_pretty", "[1]"), "[\n  1\n]");
+    }
+
+    #[test]
+    fn quotes_for_shell() {
+        assert_eq!(run("shell_quote", "src/main.rs"), "src/main.rs");
+        assert_eq!(run("shell_quote", "it's here"), "'it'\\''s here'");
+        assert_eq!(run("shell_quote", ""), "''");
+    }
+
+    #[test]
+    fn converts_paths_per_line() {
+        assert_eq!(
+            run("windows_to_wsl", "\"C:\\a b\\c\"\r\nnot a path"),
+            "\"/mnt/c/a b/c\"\r\nnot a path"
+        );
+        assert_eq!(
+            run("wsl_to_windows", "  /mnt/d/x\n/home/me"),
+            "  D:\\x\n/home/me"
+        );
+    }
+
+    #[test]
+    fn cleans_up_text() {
+        assert_eq!(
+            run("strip_ansi", "\x1b[1;31mred\x1b[0m \x1b]0;title\x07done"),
+            "red done"
+        );
+        assert_eq!(run("dedent", "    a\n\n      b\n    c"), "a\n\n  b\nc");
+        assert_eq!(run("dedent", "\ta\n  b"), "\ta\n  b");
+        assert_eq!(
+            apply_chain(
+                "  Foo Bar  ",
+                &["trim".into(), "snake".into(), "upper".into()]
+            )
+            .unwrap(),
+            "FOO_BAR"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::storage::safe_file;
+use chrono::{DateTime, Local};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Trashed by the user
+pub const REASON_DELETED: &str = "deleted";
+/// Evicted by a count or age limit
+pub const REASON_RETENTION: &str = "retention";
+/// Evicted by the total size limit
+pub const REASON_SIZE_LIMIT: &str = "size_limit";
+/// Deleted on another machine sharing the sync folder
+pub const REASON_SYNC: &str = "sync";
+
+static LAST_BATCH: AtomicI64 = AtomicI64::new(0);
+
+/// Serializes changes to the trashed screenshots and their manifest between
+/// commands and the background workers.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Hold while moving screenshots in or out of the trash and updating the
+/// manifest, so concurrent changes are not lost.
+pub fn lock() -> Result<MutexGuard<'static, ()>, String> {
+    MANIFEST_LOCK
+        .lock()
+        .map_err(|e| format!("Lock error: {}", e))
+}
+
+/// Id shared by everything removed in one deletion, so it can be undone as a
+/// whole. Increasing, and also the time of the deletion in milliseconds.
+pub fn new_batch() -> i64 {
+    let now = Local::now().timestamp_millis();
+    let mut batch = now;
+    let _ = LAST_BATCH.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+        batch = now.max(last + 1);
+        Some(batch)
+    });
+    batch
+}
+
+/// One text entry or screenshot in the trash.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashItem {
+    /// "text" or "image"
+    pub kind: String,
+    /// Entry id or screenshot filename
+    pub id: String,
+    pub preview: String,
+    pub batch: i64,
+    pub deleted_at: String,
+    /// `REASON_DELETED`, `REASON_RETENTION`, `REASON_SIZE_LIMIT` or `REASON_SYNC`
+    pub reason: String,
+    pub pinned: bool,
+}
+
+/// Identifies a trash item when restoring it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrashRef {
+    pub kind: String,
+    pub id: String,
+    pub batch: i64,
+}
+
+/// Manifest entry for a screenshot under `.trash/<batch>/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedScreenshot {
+    pub filename: String,
+    pub batch: i64,
+    pub reason: String,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl TrashedScreenshot {
+    pub fn to_item(&self) -> TrashItem {
+        TrashItem {
+            kind: "image".to_string(),
+            id: self.filename.clone(),
+            preview: self.filename.clone(),
+            batch: self.batch,
+            deleted_at: format_batch(self.batch),
+            reason: self.reason.clone(),
+            pinned: self.pinned,
+        }
+    }
+}
+
+/// Time a batch was deleted at, in the format entries use.
+pub fn format_batch(batch: i64) -> String {
+    DateTime::from_timestamp_millis(batch)
+        .map(|t| {
+            t.with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S%z")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Batch ids older than `days` days.
+pub fn cutoff(days: u32) -> i64 {
+    (Local::now() - chrono::Duration::days(days as i64)).timestamp_millis()
+}
+
+pub fn load_manifest(path: &Path) -> Vec<TrashedScreenshot> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        match safe_file::backup_corrupt(path) {
+            Ok(backup) => warn!(
+                "Trash list is corrupt ({}); moved to {}",
+                e,
+                backup.display()
+            ),
+            Err(backup_err) => warn!("Trash list is corrupt ({}): {}", e, backup_err),
+        }
+        Vec::new()
+    })
+}
+
+pub fn save_manifest(path: &Path, items: &[TrashedScreenshot]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+    safe_file::write_atomic(path, content.as_bytes())
+        .map_err(|e| format!("Failed to save trash list: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_increase() {
+        let first = new_batch();
+        let second = new_batch();
+        assert!(second > first);
+        assert!(!format_batch(second).is_empty());
+        assert!(cutoff(1) < first);
+    }
+
+    #[test]
+    fn manifest_round_trips() {
+        let path = std::env::temp_dir()
+            .join(format!("cli-buddy-trash-{}", new_batch()))
+            .join("trash.json");
+        assert!(load_manifest(&path).is_empty());
+        let items = vec![TrashedScreenshot {
+            filename: "2024-03-09/x.png".to_string(),
+            batch: 1,
+            reason: REASON_SIZE_LIMIT.to_string(),
+            pinned: true,
+        }];
+        save_manifest(&path, &items).unwrap();
+        let loaded = load_manifest(&path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].filename, items[0].filename);
+        assert_eq!(loaded[0].reason, REASON_SIZE_LIMIT);
+        assert!(loaded[0].pinned);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
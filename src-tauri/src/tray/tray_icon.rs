@@ -7,13 +7,21 @@ use tauri::{
 
 pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let toggle_monitor = MenuItemBuilder::with_id("toggle_monitor", "Monitor: Start").build(app)?;
+    let toggle_queue = MenuItemBuilder::with_id("toggle_queue", "Paste Queue").build(app)?;
     let open_folder = MenuItemBuilder::with_id("open_folder", "Open Folder").build(app)?;
     let restart_wsl = MenuItemBuilder::with_id("restart_wsl", "Restart WSL").build(app)?;
     let show_window = MenuItemBuilder::with_id("show_window", "Show Window").build(app)?;
     let quit = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
 
     let menu = MenuBuilder::new(app)
-        .items(&[&toggle_monitor, &open_folder, &restart_wsl, &show_window, &quit])
+        .items(&[
+            &toggle_monitor,
+            &toggle_queue,
+            &open_folder,
+            &restart_wsl,
+            &show_window,
+            &quit,
+        ])
         .build()?;
 
     let png_data = include_bytes!("../../icons/32x32.png");
@@ -31,9 +39,18 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
                 if state.watcher.is_running() {
                     state.watcher.stop();
                 } else {
-                    state.watcher.start(app.clone(), state.settings.clone(), state.text_history.clone());
+                    state.watcher.start(
+                        app.clone(),
+                        state.settings.clone(),
+                        state.text_history.clone(),
+                        state.paste_queue.clone(),
+                        state.hash_index.clone(),
+                    );
                 }
             }
+            "toggle_queue" => {
+                crate::toggle_paste_queue(app);
+            }
             "open_folder" => {
                 let state = app.state::<AppState>();
                 let settings = state.settings.lock().unwrap();
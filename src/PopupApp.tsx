@@ -2,25 +2,69 @@ import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { format } from "date-fns";
-import { Image, Type, X } from "lucide-react";
-import type { ClipboardEntry } from "./types";
+import { Clock, Code, Files, Flame, Image, Layers, ScrollText, Type, X } from "lucide-react";
+import type { ClipboardEntry, HistoryOrder, Page, Snippet } from "./types";
+
+const PAGE_SIZE = 50;
 
 export function PopupApp() {
   const [entries, setEntries] = useState<ClipboardEntry[]>([]);
   const [loading, setLoading] = useState(true);
   const [selectedIndex, setSelectedIndex] = useState(0);
+  const [order, setOrder] = useState<HistoryOrder>("recent");
+  const [cursor, setCursor] = useState<string | null>(null);
+  const loadingMore = useRef(false);
+  const [snippets, setSnippets] = useState<Snippet[] | null>(null);
   const listRef = useRef<HTMLDivElement>(null);
   const isDragging = useRef(false);
 
   useEffect(() => {
-    invoke<ClipboardEntry[]>("get_clipboard_history")
-      .then((data) => {
-        setEntries(data);
+    invoke<Page<ClipboardEntry>>("get_clipboard_history", { order, limit: PAGE_SIZE })
+      .then((page) => {
+        setEntries(page.items);
+        setCursor(page.next_cursor);
         setSelectedIndex(0);
       })
       .catch(console.error)
       .finally(() => setLoading(false));
+  }, [order]);
+
+  const loadMore = () => {
+    if (!cursor || loadingMore.current) return;
+    loadingMore.current = true;
+    invoke<Page<ClipboardEntry>>("get_clipboard_history", { order, cursor, limit: PAGE_SIZE })
+      .then((page) => {
+        setEntries((prev) => [...prev, ...page.items]);
+        setCursor(page.next_cursor);
+      })
+      .catch(console.error)
+      .finally(() => {
+        loadingMore.current = false;
+      });
+  };
+
+  const handleListScroll = (e: React.UIEvent<HTMLDivElement>) => {
+    const el = e.currentTarget;
+    if (!snippets && el.scrollTop + el.clientHeight >= el.scrollHeight - 200) {
+      loadMore();
+    }
+  };
+
+  const toggleSnippets = () => {
+    if (snippets) {
+      setSnippets(null);
+      setSelectedIndex(0);
+      return;
+    }
+    invoke<Snippet[]>("get_snippets")
+      .then((data) => {
+        setSnippets(data);
+        setSelectedIndex(0);
+      })
+      .catch(console.error);
+  };
 
+  useEffect(() => {
     // Escape to close
     const handleEsc = (e: KeyboardEvent) => {
       if (e.key === "Escape") getCurrentWindow().close();
@@ -54,23 +98,29 @@ export function PopupApp() {
 
   // Separate effect for keyboard with access to entries
   useEffect(() => {
+    const count = snippets ? snippets.length : entries.length;
     const handleKeyDown = (e: KeyboardEvent) => {
-      if (entries.length === 0) return;
+      if (count === 0) return;
       if (e.key === "ArrowDown") {
         e.preventDefault();
-        setSelectedIndex((prev) => Math.min(entries.length - 1, prev + 1));
+        setSelectedIndex((prev) => Math.min(count - 1, prev + 1));
       } else if (e.key === "ArrowUp") {
         e.preventDefault();
         setSelectedIndex((prev) => Math.max(0, prev - 1));
       } else if (e.key === "Enter") {
         e.preventDefault();
-        const entry = entries[selectedIndex];
-        if (entry) handleClick(entry);
+        if (snippets) {
+          const snippet = snippets[selectedIndex];
+          if (snippet) handleSnippetClick(snippet);
+        } else {
+          const entry = entries[selectedIndex];
+          if (entry) handleClick(entry);
+        }
       }
     };
     window.addEventListener("keydown", handleKeyDown);
     return () => window.removeEventListener("keydown", handleKeyDown);
-  }, [entries, selectedIndex]);
+  }, [entries, snippets, selectedIndex]);
 
   // Scroll selected item into view
   useEffect(() => {
@@ -80,10 +130,12 @@ export function PopupApp() {
 
   const handleClick = async (entry: ClipboardEntry) => {
     try {
-      if (entry.type === "text") {
+      if (entry.type !== "image") {
+        // HTML, file lists and image-with-text are restored in their original formats
         await invoke("paste_from_history", {
           content: entry.data.content,
-          entryType: "text",
+          entryType: entry.type,
+          id: entry.data.id,
         });
       } else {
         await invoke("paste_from_history", {
@@ -97,6 +149,20 @@ export function PopupApp() {
     }
   };
 
+  const handleSnippetClick = async (snippet: Snippet) => {
+    try {
+      // Placeholders are expanded by the backend at paste time
+      await invoke("paste_from_history", {
+        content: snippet.content,
+        entryType: "snippet",
+        id: snippet.id,
+      });
+      getCurrentWindow().close();
+    } catch (e) {
+      console.error("Paste failed:", e);
+    }
+  };
+
   const handleClose = () => {
     getCurrentWindow().close();
   };
@@ -122,19 +188,73 @@ export function PopupApp() {
           className="pointer-events-none select-none text-xs font-semibold text-[var(--color-text-primary)]"
           data-tauri-drag-region
         >
-          Clipboard History
+          {snippets ? "Snippets" : "Clipboard History"}
         </span>
-        <button
-          onClick={handleClose}
-          className="rounded-md p-1 text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
-        >
-          <X size={14} />
-        </button>
+        <div className="flex items-center gap-1">
+          <button
+            onClick={toggleSnippets}
+            title={snippets ? "Show history" : "Show snippets"}
+            className={`rounded-md p-1 hover:bg-[var(--color-bg-hover)] ${
+              snippets
+                ? "text-[var(--color-accent)]"
+                : "text-[var(--color-text-secondary)]"
+            }`}
+          >
+            <ScrollText size={14} />
+          </button>
+          <button
+            onClick={() => setOrder(order === "recent" ? "frecency" : "recent")}
+            title={order === "recent" ? "Most used first" : "Most recent first"}
+            className="rounded-md p-1 text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+          >
+            {order === "recent" ? <Clock size={14} /> : <Flame size={14} />}
+          </button>
+          <button
+            onClick={handleClose}
+            className="rounded-md p-1 text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+          >
+            <X size={14} />
+          </button>
+        </div>
       </div>
 
       {/* List */}
-      <div className="flex-1 overflow-y-auto" ref={listRef}>
-        {loading ? (
+      <div className="flex-1 overflow-y-auto" ref={listRef} onScroll={handleListScroll}>
+        {snippets ? (
+          snippets.length === 0 ? (
+            <div className="flex h-full items-center justify-center">
+              <p className="text-xs text-[var(--color-text-secondary)]">
+                No snippets
+              </p>
+            </div>
+          ) : (
+            snippets.map((snippet, index) => (
+              <button
+                key={snippet.id}
+                data-entry-index={index}
+                onClick={() => handleSnippetClick(snippet)}
+                onMouseEnter={() => setSelectedIndex(index)}
+                className={`flex w-full items-center gap-2 border-b border-[var(--color-border)] px-3 py-2 text-left transition-colors ${
+                  index === selectedIndex
+                    ? "bg-[var(--color-accent)]/15"
+                    : "hover:bg-[var(--color-bg-hover)]"
+                }`}
+              >
+                <div className="flex h-7 w-7 shrink-0 items-center justify-center rounded bg-[var(--color-bg-tertiary)]">
+                  <ScrollText size={14} className="text-[var(--color-accent)]" />
+                </div>
+                <div className="min-w-0 flex-1">
+                  <p className="truncate text-xs text-[var(--color-text-primary)]">
+                    {snippet.name}
+                  </p>
+                  <p className="truncate text-[10px] text-[var(--color-text-secondary)]">
+                    {snippet.content}
+                  </p>
+                </div>
+              </button>
+            ))
+          )
+        ) : loading ? (
           <div className="flex h-full items-center justify-center">
             <p className="text-xs text-[var(--color-text-secondary)]">...</p>
           </div>
@@ -148,10 +268,7 @@ export function PopupApp() {
           entries.map((entry, index) => {
             const key =
               entry.type === "image" ? entry.data.filename : entry.data.id;
-            const createdAt =
-              entry.type === "image"
-                ? entry.data.created_at
-                : entry.data.created_at;
+            const touchedAt = entry.data.last_used_at ?? entry.data.created_at;
             const isSelected = index === selectedIndex;
 
             return (
@@ -169,6 +286,12 @@ export function PopupApp() {
                 <div className="flex h-7 w-7 shrink-0 items-center justify-center rounded bg-[var(--color-bg-tertiary)]">
                   {entry.type === "image" ? (
                     <Image size={14} className="text-[var(--color-accent)]" />
+                  ) : entry.type === "files" ? (
+                    <Files size={14} className="text-[var(--color-accent)]" />
+                  ) : entry.type === "multi" ? (
+                    <Layers size={14} className="text-[var(--color-accent)]" />
+                  ) : entry.type === "html" ? (
+                    <Code size={14} className="text-[var(--color-success)]" />
                   ) : (
                     <Type size={14} className="text-[var(--color-success)]" />
                   )}
@@ -177,10 +300,15 @@ export function PopupApp() {
                   <p className="truncate text-xs text-[var(--color-text-primary)]">
                     {entry.type === "image"
                       ? entry.data.filename
-                      : entry.data.preview}
+                      : entry.type === "files"
+                        ? entry.data.paths
+                            .map((p) => p.split(/[\\/]/).pop())
+                            .join(", ")
+                        : entry.data.preview}
                   </p>
                   <p className="text-[10px] text-[var(--color-text-secondary)]">
-                    {formatDate(createdAt)}
+                    {formatDate(touchedAt)}
+                    {entry.data.use_count > 0 && ` · ×${entry.data.use_count}`}
                   </p>
                 </div>
               </button>
@@ -0,0 +1,154 @@
+import { useCallback, useEffect, useState } from "react";
+import { Loader2, Pin, X } from "lucide-react";
+import { format } from "date-fns";
+import { useScreenshotStore } from "../../stores/screenshotStore";
+import { thumbnailUrl } from "../../utils/imageUrl";
+import type { ScreenshotInfo } from "../../types";
+
+interface DuplicatesPanelProps {
+  onClose: () => void;
+}
+
+// Everything but the newest screenshot, keeping pinned ones too
+function extras(group: ScreenshotInfo[]): string[] {
+  return group.slice(1).filter((s) => !s.pinned).map((s) => s.filename);
+}
+
+export function DuplicatesPanel({ onClose }: DuplicatesPanelProps) {
+  const { findDuplicates, deleteScreenshots } = useScreenshotStore();
+  const t = useScreenshotStore((s) => s.t);
+  const [groups, setGroups] = useState<ScreenshotInfo[][] | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  const refresh = useCallback(async () => {
+    try {
+      setGroups(await findDuplicates());
+    } catch (e) {
+      console.error("Failed to find duplicates:", e);
+      setGroups([]);
+    }
+  }, [findDuplicates]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  useEffect(() => {
+    const handler = (e: KeyboardEvent) => {
+      if (e.key === "Escape") onClose();
+    };
+    window.addEventListener("keydown", handler);
+    return () => window.removeEventListener("keydown", handler);
+  }, [onClose]);
+
+  const cleanUp = async (targets: ScreenshotInfo[][]) => {
+    setBusy(true);
+    try {
+      await deleteScreenshots(targets.flatMap(extras));
+      await refresh();
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const formatDate = (iso: string) => {
+    try {
+      return format(new Date(iso), "MM/dd HH:mm:ss");
+    } catch {
+      return iso;
+    }
+  };
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/80 backdrop-blur-sm"
+      onClick={onClose}
+    >
+      <div
+        className="flex max-h-[85vh] w-[90vw] max-w-3xl flex-col rounded-lg bg-[var(--color-bg-primary)] shadow-xl"
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="flex items-center justify-between border-b border-[var(--color-border)] px-4 py-3">
+          <h2 className="text-sm font-semibold text-[var(--color-text-primary)]">
+            {t("findDuplicates")}
+          </h2>
+          <div className="flex items-center gap-2">
+            {groups && groups.length > 0 && (
+              <button
+                onClick={() => cleanUp(groups)}
+                disabled={busy}
+                className="rounded-md bg-[var(--color-danger)] px-3 py-1 text-xs font-medium text-white transition-colors hover:bg-[var(--color-danger-hover)] disabled:opacity-50"
+              >
+                {t("cleanUpAll")}
+              </button>
+            )}
+            <button
+              onClick={onClose}
+              className="rounded-md p-1.5 text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] hover:text-[var(--color-text-primary)]"
+            >
+              <X size={14} />
+            </button>
+          </div>
+        </div>
+
+        <div className="flex-1 overflow-y-auto p-4">
+          {groups === null ? (
+            <div className="flex justify-center py-8">
+              <Loader2 size={20} className="animate-spin text-[var(--color-text-secondary)]" />
+            </div>
+          ) : groups.length === 0 ? (
+            <p className="py-8 text-center text-sm text-[var(--color-text-secondary)]">
+              {t("noDuplicates")}
+            </p>
+          ) : (
+            <div className="flex flex-col gap-3">
+              {groups.map((group) => (
+                <div
+                  key={group[0].filename}
+                  className="rounded-lg border border-[var(--color-border)] bg-[var(--color-bg-secondary)] p-2"
+                >
+                  <div className="flex gap-2 overflow-x-auto">
+                    {group.map((shot, i) => (
+                      <div key={shot.filename} className="w-32 shrink-0">
+                        <div
+                          className={`relative aspect-video overflow-hidden rounded border ${
+                            i === 0 || shot.pinned
+                              ? "border-[var(--color-accent)]"
+                              : "border-transparent opacity-60"
+                          }`}
+                        >
+                          <img
+                            src={thumbnailUrl(shot.filename)}
+                            alt={shot.filename}
+                            loading="lazy"
+                            className="h-full w-full object-cover"
+                            draggable={false}
+                          />
+                          {shot.pinned && (
+                            <Pin size={10} className="absolute right-1 top-1 text-[var(--color-accent)]" />
+                          )}
+                        </div>
+                        <p className="mt-1 truncate text-[10px] text-[var(--color-text-secondary)]">
+                          {formatDate(shot.created_at)}
+                        </p>
+                      </div>
+                    ))}
+                  </div>
+                  <div className="mt-2 flex justify-end">
+                    <button
+                      onClick={() => cleanUp([group])}
+                      disabled={busy || extras(group).length === 0}
+                      className="rounded-md px-2.5 py-1 text-xs text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-danger)]/15 hover:text-[var(--color-danger)] disabled:opacity-40"
+                    >
+                      {t("keepNewest")}
+                    </button>
+                  </div>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      </div>
+    </div>
+  );
+}
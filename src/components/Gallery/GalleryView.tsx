@@ -1,17 +1,57 @@
-import { Camera, Type, Wrench } from "lucide-react";
+import { useEffect, useRef, useState } from "react";
+import { Camera, CopyMinus, Trash2, Type, Wrench } from "lucide-react";
 import { useScreenshotStore } from "../../stores/screenshotStore";
 import { ThumbnailCard } from "./ThumbnailCard";
 import { TextCard } from "./TextCard";
+import { DuplicatesPanel } from "./DuplicatesPanel";
+import { TrashPanel } from "./TrashPanel";
 import { DevToolsPanel } from "../DevTools/DevToolsPanel";
 import type { FilterMode } from "../../types";
 
 const filterModes: FilterMode[] = ["all", "images", "text", "devtools"];
 
+/** Fetches the next page of screenshots when scrolled into view */
+function LoadMoreScreenshots() {
+  const cursor = useScreenshotStore((s) => s.screenshotsCursor);
+  const fetchMore = useScreenshotStore((s) => s.fetchMoreScreenshots);
+  const ref = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    if (!cursor || !ref.current) return;
+    const observer = new IntersectionObserver(
+      (entries) => {
+        if (entries.some((e) => e.isIntersecting)) fetchMore();
+      },
+      { rootMargin: "400px" },
+    );
+    observer.observe(ref.current);
+    return () => observer.disconnect();
+  }, [cursor, fetchMore]);
+
+  return cursor ? <div ref={ref} className="col-span-full h-1" /> : null;
+}
+
 export function GalleryView() {
   const { screenshots, textEntries, filterMode, isLoading } =
     useScreenshotStore();
   const t = useScreenshotStore((s) => s.t);
   const setFilterMode = useScreenshotStore((s) => s.setFilterMode);
+  const undoDelete = useScreenshotStore((s) => s.undoDelete);
+  const [showDuplicates, setShowDuplicates] = useState(false);
+  const [showTrash, setShowTrash] = useState(false);
+
+  // Ctrl+Z outside text fields brings back the last deletion
+  useEffect(() => {
+    const handler = (e: KeyboardEvent) => {
+      if ((e.target as HTMLElement).closest("input, textarea, [contenteditable]")) return;
+      if ((e.ctrlKey || e.metaKey) && !e.shiftKey && e.key.toLowerCase() === "z") {
+        e.preventDefault();
+        undoDelete().catch((err) => console.error("Failed to undo delete:", err));
+      }
+    };
+    window.addEventListener("keydown", handler);
+    return () => window.removeEventListener("keydown", handler);
+  }, [undoDelete]);
 
   const filterLabels: Record<FilterMode, string> = {
     all: t("filterAll"),
@@ -62,7 +102,29 @@ export function GalleryView() {
             {filterLabels[mode]}
           </button>
         ))}
+        <div className="ml-auto flex items-center gap-1">
+          {showImages && hasImages && (
+            <button
+              onClick={() => setShowDuplicates(true)}
+              title={t("findDuplicates")}
+              className="flex items-center gap-1 rounded-full px-3 py-1 text-xs font-medium text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)]"
+            >
+              <CopyMinus size={11} />
+              {t("findDuplicates")}
+            </button>
+          )}
+          <button
+            onClick={() => setShowTrash(true)}
+            title={t("trash")}
+            className="flex items-center gap-1 rounded-full px-3 py-1 text-xs font-medium text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)]"
+          >
+            <Trash2 size={11} />
+            {t("trash")}
+          </button>
+        </div>
       </div>
+      {showDuplicates && <DuplicatesPanel onClose={() => setShowDuplicates(false)} />}
+      {showTrash && <TrashPanel onClose={() => setShowTrash(false)} />}
 
       {/* DevTools tab */}
       {filterMode === "devtools" ? (
@@ -103,6 +165,7 @@ export function GalleryView() {
                       screenshot={screenshot}
                     />
                   ))}
+                  <LoadMoreScreenshots />
                 </div>
               </div>
               <div className="flex-1 overflow-y-auto">
@@ -124,6 +187,7 @@ export function GalleryView() {
                       screenshot={screenshot}
                     />
                   ))}
+                  <LoadMoreScreenshots />
                 </div>
               )}
 
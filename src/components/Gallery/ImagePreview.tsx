@@ -1,21 +1,19 @@
 import { useCallback, useEffect, useState } from "react";
 import { X, ClipboardCopy, Image, Trash2, Maximize2, Minimize2, Loader2 } from "lucide-react";
-import { invoke } from "@tauri-apps/api/core";
 import { useScreenshotStore } from "../../stores/screenshotStore";
 import { CopyButton } from "../common/CopyButton";
+import { imageUrl, thumbnailUrl } from "../../utils/imageUrl";
 
 export function ImagePreview() {
   const { selectedImage, setSelectedImage, copyPath, copyImage, deleteScreenshot } =
     useScreenshotStore();
   const [fullSize, setFullSize] = useState(false);
-  const [fullImageSrc, setFullImageSrc] = useState<string | null>(null);
   const [loadingFull, setLoadingFull] = useState(false);
   const [imageError, setImageError] = useState(false);
 
   const handleClose = useCallback(() => {
     setSelectedImage(null);
     setFullSize(false);
-    setFullImageSrc(null);
     setImageError(false);
   }, [setSelectedImage]);
 
@@ -25,21 +23,6 @@ export function ImagePreview() {
     setSelectedImage(null);
   }, [selectedImage, deleteScreenshot, setSelectedImage]);
 
-  // Load full image from backend
-  const loadFullImage = useCallback(async (path: string) => {
-    setLoadingFull(true);
-    setImageError(false);
-    try {
-      const dataUrl = await invoke<string>("get_image_base64", { path });
-      setFullImageSrc(dataUrl);
-    } catch (e) {
-      console.error("Failed to load full image:", e);
-      setImageError(true);
-    } finally {
-      setLoadingFull(false);
-    }
-  }, []);
-
   // Close on Escape
   useEffect(() => {
     const handler = (e: KeyboardEvent) => {
@@ -55,20 +38,15 @@ export function ImagePreview() {
     return () => window.removeEventListener("keydown", handler);
   }, [handleClose, fullSize]);
 
-  // Load full image when preview opens
+  // Reset when another image opens; the full image loads from its URL
   useEffect(() => {
     setFullSize(false);
-    setFullImageSrc(null);
     setImageError(false);
-    if (selectedImage) {
-      loadFullImage(selectedImage.path);
-    }
-  }, [selectedImage, loadFullImage]);
+    setLoadingFull(selectedImage !== null);
+  }, [selectedImage]);
 
   if (!selectedImage) return null;
 
-  const imageSrc = fullImageSrc || selectedImage.thumbnail;
-
   return (
     <div
       className="fixed inset-0 z-50 flex items-center justify-center bg-black/80 backdrop-blur-sm"
@@ -95,17 +73,33 @@ export function ImagePreview() {
               </p>
             </div>
           ) : (
-            <img
-              src={imageSrc}
-              alt={selectedImage.filename}
-              className={`rounded-lg ${
-                fullSize
-                  ? "max-h-none max-w-none cursor-zoom-out"
-                  : "max-h-[80vh] max-w-[85vw] cursor-zoom-in object-contain"
-              }`}
-              draggable={false}
-              onClick={() => setFullSize(!fullSize)}
-            />
+            <>
+              {/* Thumbnail stands in until the full image arrives */}
+              {loadingFull && (
+                <img
+                  src={thumbnailUrl(selectedImage.filename)}
+                  alt={selectedImage.filename}
+                  className="h-[60vh] max-w-[85vw] rounded-lg object-contain"
+                  draggable={false}
+                />
+              )}
+              <img
+                src={imageUrl(selectedImage.filename)}
+                alt={selectedImage.filename}
+                className={`rounded-lg ${loadingFull ? "hidden" : ""} ${
+                  fullSize
+                    ? "max-h-none max-w-none cursor-zoom-out"
+                    : "max-h-[80vh] max-w-[85vw] cursor-zoom-in object-contain"
+                }`}
+                draggable={false}
+                onClick={() => setFullSize(!fullSize)}
+                onLoad={() => setLoadingFull(false)}
+                onError={() => {
+                  setLoadingFull(false);
+                  setImageError(true);
+                }}
+              />
+            </>
           )}
           {/* Loading overlay */}
           {loadingFull && (
@@ -15,8 +15,8 @@ export function TextCard({ entry }: TextCardProps) {
   const [expanded, setExpanded] = useState(false);
 
   const handleCopy = useCallback(
-    () => pasteFromHistory(entry.content, "text"),
-    [pasteFromHistory, entry.content],
+    () => pasteFromHistory(entry.content, "text", entry.id),
+    [pasteFromHistory, entry.content, entry.id],
   );
 
   const handleDelete = useCallback(async () => {
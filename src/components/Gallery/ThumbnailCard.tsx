@@ -3,6 +3,7 @@ import { ClipboardCopy, Image, Trash2, ZoomIn } from "lucide-react";
 import { format } from "date-fns";
 import { CopyButton } from "../common/CopyButton";
 import { useScreenshotStore } from "../../stores/screenshotStore";
+import { thumbnailUrl } from "../../utils/imageUrl";
 import type { ScreenshotInfo } from "../../types";
 
 interface ThumbnailCardProps {
@@ -54,8 +55,9 @@ export function ThumbnailCard({ screenshot }: ThumbnailCardProps) {
       >
         <div className="aspect-video w-full overflow-hidden bg-[var(--color-bg-tertiary)]">
           <img
-            src={screenshot.thumbnail}
+            src={thumbnailUrl(screenshot.filename)}
             alt={screenshot.filename}
+            loading="lazy"
             className="h-full w-full object-cover transition-transform group-hover:scale-105"
             draggable={false}
           />
@@ -0,0 +1,157 @@
+import { useCallback, useEffect, useState } from "react";
+import { Image, Loader2, Pin, RotateCcw, Type, X } from "lucide-react";
+import { format } from "date-fns";
+import { useScreenshotStore } from "../../stores/screenshotStore";
+import type { TrashItem } from "../../types";
+
+interface TrashPanelProps {
+  onClose: () => void;
+}
+
+export function TrashPanel({ onClose }: TrashPanelProps) {
+  const { fetchTrash, restoreFromTrash, undoDelete, emptyTrash } = useScreenshotStore();
+  const t = useScreenshotStore((s) => s.t);
+  const [items, setItems] = useState<TrashItem[] | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  const refresh = useCallback(async () => {
+    try {
+      setItems(await fetchTrash());
+    } catch (e) {
+      console.error("Failed to load trash:", e);
+      setItems([]);
+    }
+  }, [fetchTrash]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  useEffect(() => {
+    const handler = (e: KeyboardEvent) => {
+      if (e.key === "Escape") onClose();
+    };
+    window.addEventListener("keydown", handler);
+    return () => window.removeEventListener("keydown", handler);
+  }, [onClose]);
+
+  const run = async (action: () => Promise<void>) => {
+    setBusy(true);
+    try {
+      await action();
+    } catch (e) {
+      console.error("Trash action failed:", e);
+    } finally {
+      await refresh();
+      setBusy(false);
+    }
+  };
+
+  const reasonLabels: Record<TrashItem["reason"], string> = {
+    deleted: t("reasonDeleted"),
+    retention: t("reasonRetention"),
+    size_limit: t("reasonSizeLimit"),
+    sync: t("reasonSync"),
+  };
+
+  const formatDate = (iso: string) => {
+    try {
+      return format(new Date(iso), "MM/dd HH:mm:ss");
+    } catch {
+      return iso;
+    }
+  };
+
+  const hasUndo = items?.some((item) => item.reason === "deleted") ?? false;
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/80 backdrop-blur-sm"
+      onClick={onClose}
+    >
+      <div
+        className="flex max-h-[85vh] w-[90vw] max-w-2xl flex-col rounded-lg bg-[var(--color-bg-primary)] shadow-xl"
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="flex items-center justify-between border-b border-[var(--color-border)] px-4 py-3">
+          <h2 className="text-sm font-semibold text-[var(--color-text-primary)]">
+            {t("trash")}
+          </h2>
+          <div className="flex items-center gap-2">
+            {hasUndo && (
+              <button
+                onClick={() => run(undoDelete)}
+                disabled={busy}
+                className="rounded-md px-3 py-1 text-xs font-medium text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] hover:text-[var(--color-text-primary)] disabled:opacity-50"
+              >
+                {t("undoDelete")}
+              </button>
+            )}
+            {items && items.length > 0 && (
+              <button
+                onClick={() => run(emptyTrash)}
+                disabled={busy}
+                className="rounded-md bg-[var(--color-danger)] px-3 py-1 text-xs font-medium text-white transition-colors hover:bg-[var(--color-danger-hover)] disabled:opacity-50"
+              >
+                {t("emptyTrash")}
+              </button>
+            )}
+            <button
+              onClick={onClose}
+              className="rounded-md p-1.5 text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] hover:text-[var(--color-text-primary)]"
+            >
+              <X size={14} />
+            </button>
+          </div>
+        </div>
+
+        <div className="flex-1 overflow-y-auto p-4">
+          {items === null ? (
+            <div className="flex justify-center py-8">
+              <Loader2 size={20} className="animate-spin text-[var(--color-text-secondary)]" />
+            </div>
+          ) : items.length === 0 ? (
+            <p className="py-8 text-center text-sm text-[var(--color-text-secondary)]">
+              {t("trashEmpty")}
+            </p>
+          ) : (
+            <div className="flex flex-col gap-1">
+              {items.map((item) => (
+                <div
+                  key={`${item.kind}:${item.batch}:${item.id}`}
+                  className="flex items-center gap-2 rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-2"
+                >
+                  {item.kind === "image" ? (
+                    <Image size={12} className="shrink-0 text-[var(--color-text-secondary)]" />
+                  ) : (
+                    <Type size={12} className="shrink-0 text-[var(--color-text-secondary)]" />
+                  )}
+                  <div className="min-w-0 flex-1">
+                    <p className="truncate text-xs text-[var(--color-text-primary)]">
+                      {item.preview}
+                    </p>
+                    <p className="text-[10px] text-[var(--color-text-secondary)]">
+                      {formatDate(item.deleted_at)} · {reasonLabels[item.reason]}
+                    </p>
+                  </div>
+                  {item.pinned && (
+                    <Pin size={10} className="shrink-0 text-[var(--color-accent)]" />
+                  )}
+                  <button
+                    onClick={() => run(() => restoreFromTrash([item]))}
+                    disabled={busy}
+                    title={t("restore")}
+                    className="flex shrink-0 items-center gap-1 rounded-md px-2.5 py-1 text-xs text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] hover:text-[var(--color-text-primary)] disabled:opacity-40"
+                  >
+                    <RotateCcw size={11} />
+                    {t("restore")}
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,7 +1,8 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Settings, X } from "lucide-react";
-import type { AppSettings, Language } from "../types";
+import { listen } from "@tauri-apps/api/event";
+import { Plus, Settings, X } from "lucide-react";
+import type { AppSettings, ImportFolder, Language, ReencodeProgress } from "../types";
 import { useScreenshotStore } from "../stores/screenshotStore";
 
 interface SettingsPanelProps {
@@ -53,15 +54,47 @@ export function SettingsPanel({ isOpen, onClose }: SettingsPanelProps) {
   const [settings, setSettings] = useState<AppSettings | null>(null);
   const [saving, setSaving] = useState(false);
   const [recording, setRecording] = useState(false);
+  const [reencode, setReencode] = useState<ReencodeProgress | null>(null);
+  const [reencoding, setReencoding] = useState(false);
   const t = useScreenshotStore((s) => s.t);
   const setLanguage = useScreenshotStore((s) => s.setLanguage);
 
   useEffect(() => {
     if (isOpen) {
       invoke<AppSettings>("get_settings").then(setSettings).catch(console.error);
+      invoke<boolean>("is_reencoding").then(setReencoding).catch(console.error);
     }
   }, [isOpen]);
 
+  useEffect(() => {
+    const unlistenProgress = listen<ReencodeProgress>("reencode-progress", (e) => {
+      setReencode(e.payload);
+      setReencoding(true);
+    });
+    const unlistenComplete = listen<ReencodeProgress>("reencode-complete", (e) => {
+      setReencode(e.payload);
+      setReencoding(false);
+    });
+    return () => {
+      unlistenProgress.then((fn) => fn());
+      unlistenComplete.then((fn) => fn());
+    };
+  }, []);
+
+  // The library is re-encoded with the saved settings, so save the form first
+  const handleReencode = useCallback(async () => {
+    if (!settings) return;
+    try {
+      await invoke("update_settings", { newSettings: settings });
+      setReencode(null);
+      setReencoding(true);
+      await invoke("reencode_screenshots");
+    } catch (e) {
+      setReencoding(false);
+      console.error("Failed to re-encode screenshots:", e);
+    }
+  }, [settings]);
+
   const handleSave = useCallback(async () => {
     if (!settings) return;
     setSaving(true);
@@ -83,6 +116,16 @@ export function SettingsPanel({ isOpen, onClose }: SettingsPanelProps) {
     setSettings((prev) => (prev ? { ...prev, [key]: value } : null));
   };
 
+  const updateImportFolder = (index: number, folder: ImportFolder | null) => {
+    setSettings((prev) => {
+      if (!prev) return null;
+      const import_folders = prev.import_folders.flatMap((f, i) =>
+        i !== index ? [f] : folder ? [folder] : [],
+      );
+      return { ...prev, import_folders };
+    });
+  };
+
   // Shortcut recorder
   useEffect(() => {
     if (!recording) return;
@@ -207,6 +250,110 @@ export function SettingsPanel({ isOpen, onClose }: SettingsPanelProps) {
               />
             </div>
 
+            {/* Filename template */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("filenameTemplate")}
+              </label>
+              <input
+                type="text"
+                value={settings.filename_template}
+                onChange={(e) => updateField("filename_template", e.target.value)}
+                className="w-full rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 font-mono text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+              />
+              <p className="mt-1 text-[11px] text-[var(--color-text-secondary)]">
+                {t("filenameTemplateHint")}
+              </p>
+            </div>
+
+            {/* Subfolders */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("subfolders")}
+              </label>
+              <div className="flex gap-2">
+                {(
+                  [
+                    ["none", t("subfolderNone")],
+                    ["day", t("subfolderDay")],
+                    ["month", t("subfolderMonth")],
+                  ] as const
+                ).map(([layout, label]) => (
+                  <button
+                    key={layout}
+                    onClick={() => updateField("screenshot_subfolders", layout)}
+                    className={`flex-1 rounded-md px-3 py-1.5 text-xs font-medium transition-colors ${
+                      settings.screenshot_subfolders === layout
+                        ? "bg-[var(--color-accent)] text-white"
+                        : "bg-[var(--color-bg-tertiary)] text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+                    }`}
+                  >
+                    {label}
+                  </button>
+                ))}
+              </div>
+            </div>
+
+            {/* Import folders */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("importFolders")}
+              </label>
+              <div className="flex flex-col gap-1.5">
+                {settings.import_folders.map((folder, index) => (
+                  <div key={index} className="flex gap-1.5">
+                    <input
+                      type="text"
+                      value={folder.path}
+                      onChange={(e) =>
+                        updateImportFolder(index, { ...folder, path: e.target.value })
+                      }
+                      className="min-w-0 flex-1 rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+                    />
+                    {(
+                      [
+                        ["copy", t("importCopy")],
+                        ["move", t("importMove")],
+                      ] as const
+                    ).map(([mode, label]) => (
+                      <button
+                        key={mode}
+                        onClick={() => updateImportFolder(index, { ...folder, mode })}
+                        className={`rounded-md px-2.5 py-1.5 text-xs font-medium transition-colors ${
+                          folder.mode === mode
+                            ? "bg-[var(--color-accent)] text-white"
+                            : "bg-[var(--color-bg-tertiary)] text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                    <button
+                      onClick={() => updateImportFolder(index, null)}
+                      className="rounded-md p-1.5 text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] hover:text-[var(--color-text-primary)]"
+                    >
+                      <X size={14} />
+                    </button>
+                  </div>
+                ))}
+                <button
+                  onClick={() =>
+                    updateField("import_folders", [
+                      ...settings.import_folders,
+                      { path: "", mode: "copy" },
+                    ])
+                  }
+                  className="flex items-center justify-center gap-1 rounded-md bg-[var(--color-bg-tertiary)] px-3 py-1.5 text-xs font-medium text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)]"
+                >
+                  <Plus size={12} />
+                  {t("addImportFolder")}
+                </button>
+              </div>
+              <p className="mt-1 text-[11px] text-[var(--color-text-secondary)]">
+                {t("importFoldersHint")}
+              </p>
+            </div>
+
             {/* Polling Interval */}
             <div>
               <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
@@ -242,6 +389,131 @@ export function SettingsPanel({ isOpen, onClose }: SettingsPanelProps) {
               />
             </div>
 
+            {/* Trash retention */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("trashRetentionDays")}
+              </label>
+              <input
+                type="number"
+                min={1}
+                max={365}
+                value={settings.trash_retention_days}
+                onChange={(e) =>
+                  updateField(
+                    "trash_retention_days",
+                    Math.min(365, Math.max(1, parseInt(e.target.value) || 30)),
+                  )
+                }
+                className="w-full rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+              />
+            </div>
+
+            {/* Image format */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("imageFormat")}
+              </label>
+              <div className="grid grid-cols-2 gap-2">
+                {(
+                  [
+                    ["png", "PNG"],
+                    ["webp_lossless", t("formatWebpLossless")],
+                    ["webp", t("formatWebp")],
+                    ["jpeg", "JPEG"],
+                  ] as const
+                ).map(([format, label]) => (
+                  <button
+                    key={format}
+                    onClick={() => updateField("image_format", format)}
+                    className={`rounded-md px-3 py-1.5 text-xs font-medium transition-colors ${
+                      settings.image_format === format
+                        ? "bg-[var(--color-accent)] text-white"
+                        : "bg-[var(--color-bg-tertiary)] text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+                    }`}
+                  >
+                    {label}
+                  </button>
+                ))}
+              </div>
+            </div>
+
+            {(settings.image_format === "webp" || settings.image_format === "jpeg") && (
+              <div>
+                <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                  {t("imageQuality")}
+                </label>
+                <input
+                  type="number"
+                  min={1}
+                  max={100}
+                  value={settings.image_quality}
+                  onChange={(e) =>
+                    updateField(
+                      "image_quality",
+                      Math.min(100, Math.max(1, parseInt(e.target.value) || 85)),
+                    )
+                  }
+                  className="w-full rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+                />
+              </div>
+            )}
+
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("maxImageDimension")}
+              </label>
+              <input
+                type="number"
+                min={0}
+                step={100}
+                value={settings.max_image_dimension}
+                onChange={(e) =>
+                  updateField("max_image_dimension", Math.max(0, parseInt(e.target.value) || 0))
+                }
+                className="w-full rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+              />
+            </div>
+
+            {/* Re-encode existing library */}
+            <div>
+              <button
+                onClick={handleReencode}
+                disabled={reencoding}
+                className="w-full rounded-md bg-[var(--color-bg-tertiary)] px-3 py-1.5 text-xs font-medium text-[var(--color-text-secondary)] transition-colors hover:bg-[var(--color-bg-hover)] disabled:opacity-50"
+              >
+                {reencoding
+                  ? `${t("reencoding")}${reencode ? ` ${reencode.done}/${reencode.total}` : "..."}`
+                  : t("reencodeLibrary")}
+              </button>
+              {!reencoding && reencode && (
+                <p className="mt-1 text-[11px] text-[var(--color-text-secondary)]">
+                  {t("reencodeDone")}: {reencode.reencoded}/{reencode.total},{" "}
+                  {(reencode.bytes_saved / 1024 / 1024).toFixed(1)} MB
+                </p>
+              )}
+            </div>
+
+            {/* Near-duplicate threshold */}
+            <div>
+              <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
+                {t("duplicateThreshold")}
+              </label>
+              <input
+                type="number"
+                min={0}
+                max={64}
+                value={settings.duplicate_threshold}
+                onChange={(e) =>
+                  updateField(
+                    "duplicate_threshold",
+                    Math.min(64, Math.max(0, parseInt(e.target.value) || 0)),
+                  )
+                }
+                className="w-full rounded-md border border-[var(--color-border)] bg-[var(--color-bg-secondary)] px-3 py-1.5 text-xs text-[var(--color-text-primary)] outline-none focus:border-[var(--color-accent)]"
+              />
+            </div>
+
             {/* Max Text Entries */}
             <div>
               <label className="mb-1 block text-xs font-medium text-[var(--color-text-secondary)]">
@@ -294,6 +566,32 @@ export function SettingsPanel({ isOpen, onClose }: SettingsPanelProps) {
                 />
               </label>
 
+              {settings.image_format === "png" && (
+                <label className="flex cursor-pointer items-center justify-between">
+                  <span className="text-xs text-[var(--color-text-secondary)]">
+                    {t("optimizePng")}
+                  </span>
+                  <input
+                    type="checkbox"
+                    checked={settings.optimize_png}
+                    onChange={(e) => updateField("optimize_png", e.target.checked)}
+                    className="h-4 w-4 rounded accent-[var(--color-accent)]"
+                  />
+                </label>
+              )}
+
+              <label className="flex cursor-pointer items-center justify-between">
+                <span className="text-xs text-[var(--color-text-secondary)]">
+                  {t("skipNearDuplicates")}
+                </span>
+                <input
+                  type="checkbox"
+                  checked={settings.skip_near_duplicates}
+                  onChange={(e) => updateField("skip_near_duplicates", e.target.checked)}
+                  className="h-4 w-4 rounded accent-[var(--color-accent)]"
+                />
+              </label>
+
               <label className="flex cursor-pointer items-center justify-between">
                 <span className="text-xs text-[var(--color-text-secondary)]">
                   {t("captureText")}
@@ -1,10 +1,11 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { Activity, ActivitySquare, Settings } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
+import { Activity, ActivitySquare, ListOrdered, Settings } from "lucide-react";
 import { open } from "@tauri-apps/plugin-shell";
 import { useMonitorStatus } from "../hooks/useMonitorStatus";
 import { useScreenshotStore } from "../stores/screenshotStore";
-import type { VmmemStats } from "../types";
+import type { PasteQueueState, VmmemStats } from "../types";
 
 interface StatusBarProps {
   onSettingsClick: () => void;
@@ -12,10 +13,23 @@ interface StatusBarProps {
 
 export function StatusBar({ onSettingsClick }: StatusBarProps) {
   const { isMonitoring, toggleMonitor } = useMonitorStatus();
-  const screenshotCount = useScreenshotStore((s) => s.screenshots.length);
+  const screenshotCount = useScreenshotStore((s) => s.screenshotTotal);
   const textCount = useScreenshotStore((s) => s.textEntries.length);
   const t = useScreenshotStore((s) => s.t);
   const [vmmem, setVmmem] = useState<VmmemStats | null>(null);
+  const [queue, setQueue] = useState<PasteQueueState | null>(null);
+
+  useEffect(() => {
+    invoke<PasteQueueState>("get_paste_queue").then(setQueue).catch(console.error);
+    const unlisten = listen<PasteQueueState>("paste-queue-changed", (e) => setQueue(e.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const toggleQueue = () => {
+    invoke<PasteQueueState>("toggle_paste_queue").then(setQueue).catch(console.error);
+  };
 
   useEffect(() => {
     const fetch = async () => {
@@ -55,6 +69,19 @@ export function StatusBar({ onSettingsClick }: StatusBarProps) {
       </div>
 
       <div className="flex items-center gap-2">
+        <button
+          onClick={toggleQueue}
+          title={t("pasteQueue")}
+          className={`flex items-center gap-1.5 rounded-full px-2.5 py-1 text-xs font-medium transition-colors
+            ${queue?.active
+              ? "bg-[var(--color-accent)]/15 text-[var(--color-accent)] hover:bg-[var(--color-accent)]/25"
+              : "text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)]"
+            }`}
+        >
+          <ListOrdered size={12} />
+          {queue?.active && queue.items.length}
+        </button>
+
         <button
           onClick={toggleMonitor}
           className={`flex items-center gap-1.5 rounded-full px-3 py-1 text-xs font-medium transition-colors
@@ -1,12 +1,19 @@
 import { useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { useScreenshotStore } from "../stores/screenshotStore";
-import type { ScreenshotInfo, TextEntry } from "../types";
+import type {
+  ReencodeProgress,
+  RetentionSummary,
+  ScreenshotInfo,
+  SyncReport,
+  TextEntry,
+} from "../types";
 
 export function useScreenshots() {
   const {
     fetchScreenshots,
     addScreenshot,
+    removeScreenshot,
     fetchMonitorStatus,
     fetchSettings,
     fetchTextHistory,
@@ -26,17 +33,51 @@ export function useScreenshots() {
       },
     );
 
+    // Files added or removed in the save folder by other programs
+    const unlistenAdded = listen<ScreenshotInfo>("screenshot-added", (event) => {
+      addScreenshot(event.payload);
+    });
+
+    const unlistenRemoved = listen<string>("screenshot-removed", (event) => {
+      removeScreenshot(event.payload);
+    });
+
     const unlistenText = listen<TextEntry>("new-text-entry", (event) => {
       addTextEntry(event.payload);
     });
 
+    const unlistenRetention = listen<RetentionSummary>(
+      "retention-cleanup",
+      () => {
+        fetchScreenshots();
+        fetchTextHistory();
+      },
+    );
+
+    const unlistenSync = listen<SyncReport>("sync-complete", () => {
+      fetchScreenshots();
+      fetchTextHistory();
+    });
+
+    // Re-encoding may rename screenshots
+    const unlistenReencode = listen<ReencodeProgress>("reencode-complete", () => {
+      fetchScreenshots();
+      fetchTextHistory();
+    });
+
     return () => {
       unlistenScreenshot.then((fn) => fn());
+      unlistenAdded.then((fn) => fn());
+      unlistenRemoved.then((fn) => fn());
       unlistenText.then((fn) => fn());
+      unlistenRetention.then((fn) => fn());
+      unlistenSync.then((fn) => fn());
+      unlistenReencode.then((fn) => fn());
     };
   }, [
     fetchScreenshots,
     addScreenshot,
+    removeScreenshot,
     fetchMonitorStatus,
     fetchSettings,
     fetchTextHistory,
@@ -4,21 +4,31 @@ import type {
   AppSettings,
   FilterMode,
   Language,
+  Page,
   ScreenshotInfo,
+  Snippet,
   TextEntry,
+  TrashItem,
 } from "../types";
 import { translations } from "../types";
 
+const PAGE_SIZE = 60;
+
 interface ScreenshotStore {
   screenshots: ScreenshotInfo[];
+  /** Screenshots on disk, including pages not loaded yet */
+  screenshotTotal: number;
+  screenshotsCursor: string | null;
   textEntries: TextEntry[];
   filterMode: FilterMode;
   isMonitoring: boolean;
   isLoading: boolean;
   selectedImage: ScreenshotInfo | null;
   language: Language;
+  snippets: Snippet[];
 
   fetchScreenshots: () => Promise<void>;
+  fetchMoreScreenshots: () => Promise<void>;
   addScreenshot: (info: ScreenshotInfo) => void;
   removeScreenshot: (filename: string) => void;
   setMonitoring: (status: boolean) => void;
@@ -30,6 +40,9 @@ interface ScreenshotStore {
   copyPath: (path: string) => Promise<void>;
   copyImage: (filename: string) => Promise<void>;
   deleteScreenshot: (filename: string) => Promise<void>;
+  deleteScreenshots: (filenames: string[]) => Promise<void>;
+  /** Groups of near-identical screenshots, newest first in each */
+  findDuplicates: () => Promise<ScreenshotInfo[][]>;
   toggleMonitor: () => Promise<void>;
   fetchMonitorStatus: () => Promise<void>;
   fetchSettings: () => Promise<void>;
@@ -40,24 +53,47 @@ interface ScreenshotStore {
   removeTextEntry: (id: string) => void;
   deleteTextEntry: (id: string) => Promise<void>;
   clearTextHistory: () => Promise<void>;
-  pasteFromHistory: (content: string, entryType: string) => Promise<void>;
+  pasteFromHistory: (content: string, entryType: string, id?: string) => Promise<void>;
+  /** `transforms` may mix built-in transform names and preset names */
+  pasteTransformed: (entryId: string, transforms: string[]) => Promise<void>;
+
+  // Snippets
+  fetchSnippets: () => Promise<void>;
+  createSnippet: (name: string, content: string) => Promise<void>;
+  updateSnippet: (id: string, name: string, content: string) => Promise<void>;
+  deleteSnippet: (id: string) => Promise<void>;
+
+  // Trash
+  fetchTrash: () => Promise<TrashItem[]>;
+  restoreFromTrash: (items: TrashItem[]) => Promise<void>;
+  /** Restores the last deletion made from the app */
+  undoDelete: () => Promise<void>;
+  emptyTrash: () => Promise<void>;
 }
 
 export const useScreenshotStore = create<ScreenshotStore>((set, get) => ({
   screenshots: [],
+  screenshotTotal: 0,
+  screenshotsCursor: null,
   textEntries: [],
   filterMode: "all",
   isMonitoring: false,
   isLoading: false,
   selectedImage: null,
   language: "ko",
+  snippets: [],
 
   fetchScreenshots: async () => {
     set({ isLoading: true });
     try {
-      const screenshots =
-        await invoke<ScreenshotInfo[]>("get_screenshots");
-      set({ screenshots });
+      const page = await invoke<Page<ScreenshotInfo>>("get_screenshots", {
+        limit: PAGE_SIZE,
+      });
+      set({
+        screenshots: page.items,
+        screenshotTotal: page.total,
+        screenshotsCursor: page.next_cursor,
+      });
     } catch (e) {
       console.error("Failed to fetch screenshots:", e);
     } finally {
@@ -65,16 +101,55 @@ export const useScreenshotStore = create<ScreenshotStore>((set, get) => ({
     }
   },
 
+  fetchMoreScreenshots: async () => {
+    const cursor = get().screenshotsCursor;
+    if (!cursor) return;
+    // Stops a second scroll event from fetching the same page
+    set({ screenshotsCursor: null });
+    try {
+      const page = await invoke<Page<ScreenshotInfo>>("get_screenshots", {
+        cursor,
+        limit: PAGE_SIZE,
+      });
+      set((state) => {
+        const known = new Set(state.screenshots.map((s) => s.filename));
+        return {
+          screenshots: [
+            ...state.screenshots,
+            ...page.items.filter((s) => !known.has(s.filename)),
+          ],
+          screenshotTotal: page.total,
+          screenshotsCursor: page.next_cursor,
+        };
+      });
+    } catch (e) {
+      console.error("Failed to fetch screenshots:", e);
+      set({ screenshotsCursor: cursor });
+    }
+  },
+
   addScreenshot: (info) => {
-    set((state) => ({
-      screenshots: [info, ...state.screenshots],
-    }));
+    set((state) => {
+      // The folder watcher also reports screenshots saved by the app itself
+      if (state.screenshots.some((s) => s.filename === info.filename)) {
+        return {};
+      }
+      return {
+        screenshots: [info, ...state.screenshots],
+        screenshotTotal: state.screenshotTotal + 1,
+      };
+    });
   },
 
   removeScreenshot: (filename) => {
-    set((state) => ({
-      screenshots: state.screenshots.filter((s) => s.filename !== filename),
-    }));
+    set((state) => {
+      const screenshots = state.screenshots.filter((s) => s.filename !== filename);
+      return {
+        screenshots,
+        screenshotTotal:
+          state.screenshotTotal - (state.screenshots.length - screenshots.length),
+      };
+    });
   },
 
   setMonitoring: (status) => set({ isMonitoring: status }),
@@ -100,6 +175,16 @@ export const useScreenshotStore = create<ScreenshotStore>((set, get) => ({
     get().removeScreenshot(filename);
   },
 
+  deleteScreenshots: async (filenames) => {
+    await invoke<number>("delete_screenshots", { filenames });
+    // Some may have failed; the listing shows what is left
+    await get().fetchScreenshots();
+  },
+
+  findDuplicates: async () => {
+    return await invoke<ScreenshotInfo[][]>("find_duplicate_screenshots");
+  },
+
   toggleMonitor: async () => {
     const isRunning = await invoke<boolean>("toggle_monitor");
     set({ isMonitoring: isRunning });
@@ -131,7 +216,8 @@ export const useScreenshotStore = create<ScreenshotStore>((set, get) => ({
 
   addTextEntry: (entry) => {
     set((state) => ({
-      textEntries: [entry, ...state.textEntries],
+      // Re-copied entries come back with the same hash and move to the top
+      textEntries: [entry, ...state.textEntries.filter((t) => t.hash !== entry.hash)],
     }));
   },
 
@@ -151,7 +237,61 @@ export const useScreenshotStore = create<ScreenshotStore>((set, get) => ({
     set({ textEntries: [] });
   },
 
-  pasteFromHistory: async (content, entryType) => {
-    await invoke("paste_from_history", { content, entryType });
+  pasteFromHistory: async (content, entryType, id) => {
+    await invoke("paste_from_history", { content, entryType, id });
+  },
+
+  pasteTransformed: async (entryId, transforms) => {
+    await invoke("paste_transformed", { entryId, transforms });
+  },
+
+  // Snippet actions
+  fetchSnippets: async () => {
+    try {
+      const snippets = await invoke<Snippet[]>("get_snippets");
+      set({ snippets });
+    } catch (e) {
+      console.error("Failed to fetch snippets:", e);
+    }
+  },
+
+  createSnippet: async (name, content) => {
+    await invoke<Snippet>("create_snippet", { name, content });
+    await get().fetchSnippets();
+  },
+
+  updateSnippet: async (id, name, content) => {
+    await invoke<Snippet>("update_snippet", { id, name, content });
+    await get().fetchSnippets();
+  },
+
+  deleteSnippet: async (id) => {
+    await invoke("delete_snippet", { id });
+    set((state) => ({ snippets: state.snippets.filter((s) => s.id !== id) }));
+  },
+
+  // Trash actions
+  fetchTrash: async () => {
+    return await invoke<TrashItem[]>("get_trash");
+  },
+
+  restoreFromTrash: async (items) => {
+    try {
+      await invoke<number>("restore_from_trash", {
+        items: items.map(({ kind, id, batch }) => ({ kind, id, batch })),
+      });
+    } finally {
+      // Some items may have been restored even when others failed
+      await Promise.all([get().fetchScreenshots(), get().fetchTextHistory()]);
+    }
+  },
+
+  undoDelete: async () => {
+    await invoke<number>("undo_delete");
+    await Promise.all([get().fetchScreenshots(), get().fetchTextHistory()]);
+  },
+
+  emptyTrash: async () => {
+    await invoke<number>("empty_trash");
   },
 }));
@@ -1,9 +1,18 @@
 export interface ScreenshotInfo {
   filename: string;
   path: string;
-  thumbnail: string;
   created_at: string;
   size_bytes: number;
+  pinned: boolean;
+  use_count: number;
+  last_used_at: string | null;
+}
+
+/** One page of a listing; pass `next_cursor` back for the next one */
+export interface Page<T> {
+  items: T[];
+  next_cursor: string | null;
+  total: number;
 }
 
 export interface TextEntry {
@@ -13,11 +22,60 @@ export interface TextEntry {
   hash: string;
   created_at: string;
   char_count: number;
+  pinned: boolean;
+  sensitive: boolean;
+  expires_at: string | null;
+  truncated: boolean;
+  kind: TextKind;
+  kind_meta: Record<string, string>;
+  use_count: number;
+  last_used_at: string | null;
+  /** Clipboard format restored on paste; "files" lists one path per line */
+  format: EntryFormat;
+  /** Screenshot copied together with this text, pasted back with it */
+  screenshot: string | null;
+}
+
+export type EntryFormat = "text" | "html" | "files";
+
+export interface FileList extends TextEntry {
+  paths: string[];
+  wsl_paths: string[];
+}
+
+export interface MultiEntry extends TextEntry {
+  image: ScreenshotInfo;
 }
 
+export type TextKind =
+  | "url"
+  | "windows_path"
+  | "posix_path"
+  | "json"
+  | "shell"
+  | "stack_trace"
+  | "git_sha"
+  | "uuid"
+  | "color"
+  | "code"
+  | "prose";
+
 export type ClipboardEntry =
   | { type: "image"; data: ScreenshotInfo }
-  | { type: "text"; data: TextEntry };
+  | { type: "text"; data: TextEntry }
+  | { type: "html"; data: TextEntry }
+  | { type: "files"; data: FileList }
+  | { type: "multi"; data: MultiEntry };
+
+export interface SearchFilters {
+  entry_type?: "text" | "image";
+  since?: string;
+  until?: string;
+  kind?: TextKind;
+  limit?: number;
+}
+
+export type HistoryOrder = "recent" | "frecency";
 
 export type FilterMode = "all" | "images" | "text" | "devtools";
 
@@ -51,6 +109,106 @@ export interface AppSettings {
   capture_text: boolean;
   max_text_entries: number;
   global_shortcut: string;
+  encrypt_at_rest: boolean;
+  secret_action: "off" | "store" | "skip" | "redact" | "expire";
+  secret_expiry_minutes: number;
+  text_max_age_days: number;
+  screenshot_max_age_days: number;
+  screenshot_max_total_mb: number;
+  /** Days deleted and evicted items stay in the trash; at least 1 */
+  trash_retention_days: number;
+  max_inline_text_kb: number;
+  max_text_size_mb: number;
+  /** Shared folder for multi-machine sync; empty disables it */
+  sync_folder: string;
+  transform_presets: TransformPreset[];
+  /** Toggles paste queue mode */
+  queue_shortcut: string;
+  /** Pastes the oldest queued clip and loads the next */
+  queue_paste_shortcut: string;
+  /** Drop captures that look the same as a stored screenshot */
+  skip_near_duplicates: boolean;
+  /** Most perceptual hash bits (of 64) near-duplicates may differ in */
+  duplicate_threshold: number;
+  /** Format new screenshots are saved in */
+  image_format: "png" | "webp_lossless" | "webp" | "jpeg";
+  /** 1-100, for lossy WebP and JPEG */
+  image_quality: number;
+  /** Losslessly recompress PNG screenshots */
+  optimize_png: boolean;
+  /** Longest side screenshots are stored at; 0 keeps the original size */
+  max_image_dimension: number;
+  /** Name of new screenshots; tokens {date} {time} {seq} {hash8} {project} */
+  filename_template: string;
+  screenshot_subfolders: "none" | "day" | "month";
+  /** Folders other screenshot tools save into, imported automatically */
+  import_folders: ImportFolder[];
+}
+
+/** "copy" keeps the original; "move" deletes it once imported */
+export interface ImportFolder {
+  path: string;
+  mode: "copy" | "move";
+}
+
+/** Named chain of built-in transforms, usable by name in `paste_transformed` */
+export interface TransformPreset {
+  name: string;
+  transforms: string[];
+}
+
+export interface QueueItem {
+  entry_type: ClipboardEntry["type"];
+  id: string | null;
+  content: string;
+  preview: string;
+}
+
+export interface PasteQueueState {
+  active: boolean;
+  items: QueueItem[];
+}
+
+export interface RetentionSummary {
+  text_entries_removed: number;
+  screenshots_removed: number;
+  bytes_freed: number;
+  /** Items deleted for good after their time in the trash */
+  trash_purged: number;
+}
+
+/** A deleted text entry or screenshot waiting in the trash */
+export interface TrashItem {
+  kind: "text" | "image";
+  /** Entry id or screenshot filename */
+  id: string;
+  preview: string;
+  /** Items deleted together share a batch and are undone together */
+  batch: number;
+  deleted_at: string;
+  reason: "deleted" | "retention" | "size_limit" | "sync";
+  pinned: boolean;
+}
+
+/** Payload of `reencode-progress` and `reencode-complete` */
+export interface ReencodeProgress {
+  done: number;
+  total: number;
+  reencoded: number;
+  skipped: number;
+  failed: number;
+  bytes_saved: number;
+}
+
+export interface EncryptionStatus {
+  enabled: boolean;
+  configured: boolean;
+  unlocked: boolean;
+}
+
+export interface EncryptionReport {
+  text_entries: number;
+  screenshots: number;
 }
 
 export type Language = "ko" | "en";
@@ -111,6 +269,43 @@ export const translations = {
     viewOriginal: "원본 보기",
     expandText: "펼치기",
     collapseText: "접기",
+    pasteQueue: "붙여넣기 큐",
+    skipNearDuplicates: "비슷한 스크린샷은 저장 안 함",
+    duplicateThreshold: "유사도 기준 (다른 비트 수, 0-64)",
+    findDuplicates: "중복 찾기",
+    noDuplicates: "중복된 스크린샷이 없습니다",
+    keepNewest: "최신만 남기기",
+    cleanUpAll: "모두 정리",
+    imageFormat: "이미지 형식",
+    formatWebpLossless: "WebP (무손실)",
+    formatWebp: "WebP (손실)",
+    imageQuality: "품질 (1-100)",
+    maxImageDimension: "최대 크기 (px, 0: 원본)",
+    optimizePng: "PNG 최적화 (저장이 느려짐)",
+    reencodeLibrary: "기존 스크린샷 다시 인코딩",
+    reencoding: "인코딩 중",
+    reencodeDone: "완료",
+    filenameTemplate: "파일 이름 형식",
+    filenameTemplateHint: "{date} {time} {seq} {hash8} {project}",
+    subfolders: "하위 폴더",
+    subfolderNone: "없음",
+    subfolderDay: "날짜별",
+    subfolderMonth: "월별",
+    importFolders: "가져오기 폴더",
+    importFoldersHint: "캡처 도구, ShareX 등이 이 폴더에 저장한 새 이미지를 가져옵니다",
+    importCopy: "복사",
+    importMove: "이동",
+    addImportFolder: "폴더 추가",
+    trash: "휴지통",
+    trashEmpty: "휴지통이 비어 있습니다",
+    emptyTrash: "휴지통 비우기",
+    restore: "복원",
+    undoDelete: "삭제 취소",
+    reasonDeleted: "삭제함",
+    reasonRetention: "보관 한도 초과",
+    reasonSizeLimit: "용량 한도 초과",
+    reasonSync: "다른 기기에서 삭제",
+    trashRetentionDays: "휴지통 보관 기간 (일)",
   },
   en: {
     appName: "CLI Buddy",
@@ -167,7 +362,91 @@ export const translations = {
     viewOriginal: "View Original",
     expandText: "Expand",
     collapseText: "Collapse",
+    pasteQueue: "Paste queue",
+    skipNearDuplicates: "Skip near-duplicate screenshots",
+    duplicateThreshold: "Similarity threshold (differing bits, 0-64)",
+    findDuplicates: "Find duplicates",
+    noDuplicates: "No duplicate screenshots",
+    keepNewest: "Keep newest",
+    cleanUpAll: "Clean up all",
+    imageFormat: "Image format",
+    formatWebpLossless: "WebP (lossless)",
+    formatWebp: "WebP (lossy)",
+    imageQuality: "Quality (1-100)",
+    maxImageDimension: "Max dimension (px, 0: original)",
+    optimizePng: "Optimize PNG (slower saves)",
+    reencodeLibrary: "Re-encode existing screenshots",
+    reencoding: "Re-encoding",
+    reencodeDone: "Done",
+    filenameTemplate: "Filename template",
+    filenameTemplateHint: "{date} {time} {seq} {hash8} {project}",
+    subfolders: "Subfolders",
+    subfolderNone: "None",
+    subfolderDay: "Per day",
+    subfolderMonth: "Per month",
+    importFolders: "Import folders",
+    importFoldersHint: "New images saved here by tools like Snipping Tool or ShareX are imported",
+    importCopy: "Copy",
+    importMove: "Move",
+    addImportFolder: "Add folder",
+    trash: "Trash",
+    trashEmpty: "Trash is empty",
+    emptyTrash: "Empty trash",
+    restore: "Restore",
+    undoDelete: "Undo delete",
+    reasonDeleted: "Deleted",
+    reasonRetention: "Over retention limit",
+    reasonSizeLimit: "Over size limit",
+    reasonSync: "Deleted on another device",
+    trashRetentionDays: "Keep trash for (days)",
   },
 } as const;
 
 export type TranslationKey = keyof (typeof translations)["ko"];
+
+export interface ArchiveSummary {
+  text_entries: number;
+  screenshots: number;
+  skipped: number;
+}
+
+export interface SyncReport {
+  applied: number;
+  published: number;
+  conflicts: number;
+}
+
+export interface SyncConflict {
+  hash: string;
+  device: string;
+  reason: string;
+  at: string;
+}
+
+export interface SyncDevice {
+  id: string;
+  name: string;
+  last_sync: string | null;
+}
+
+export interface SyncStatus {
+  enabled: boolean;
+  folder: string;
+  device_id: string;
+  last_sync: string | null;
+  pending_local: number;
+  pending_remote: number;
+  devices: SyncDevice[];
+  conflicts: SyncConflict[];
+}
+
+export interface Snippet {
+  id: string;
+  name: string;
+  /** Template text; placeholders like {date} or {clipboard} are filled in on paste */
+  content: string;
+  created_at: string;
+  updated_at: string;
+  use_count: number;
+  last_used_at: string | null;
+}
@@ -0,0 +1,14 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+// Served by the `buddy` URI scheme registered in the backend
+const SCHEME = "buddy";
+
+/** URL of a screenshot's full image */
+export function imageUrl(filename: string): string {
+  return convertFileSrc(`image/${filename}`, SCHEME);
+}
+
+/** URL of a screenshot's thumbnail, created on first request */
+export function thumbnailUrl(filename: string): string {
+  return convertFileSrc(`thumb/${filename}`, SCHEME);
+}